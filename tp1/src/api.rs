@@ -0,0 +1,295 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::captures::Captures;
+use crate::matching::{captures_at, captures_iter, find_at, find_iter};
+use crate::nfa::Program;
+use crate::parser::Syntax;
+use crate::regex;
+use crate::regex_error::RegexError;
+use crate::regex_match::Match;
+use crate::regex_options::RegexOptions;
+
+/// Struct to represent a compiled regex, the entry point to use tp1 as a library
+/// - It keeps the expression it was created from, so it can be shown again
+/// - The matches are leftmost-longest, or leftmost-first with the perl option
+/// - A search with backreferences, or a big one on a long line, can fail with a BacktrackLimit error, so the searches return a Result
+/// # Example
+/// let regex: Regex = "([[:digit:]]+)-([[:digit:]]+)".parse().unwrap();
+/// assert_eq!(regex.replace_all("1-2 and 30-40", "$2-$1"), Ok("2-1 and 40-30".to_string()));
+#[derive(Debug, Clone)]
+pub struct Regex {
+    /// The expression the regex was created from
+    expression: String,
+    /// The compiled regex
+    program: Program,
+}
+
+/// Implementation of the Regex struct
+impl Regex {
+    /// Compiles an expression with the default options
+    /// # Arguments
+    /// * `expression` - A string slice that holds the regex expression
+    /// # Returns
+    /// * A Result with a Regex or an error
+    /// # Example
+    /// let regex = Regex::new("a+b").unwrap();
+    /// assert_eq!(regex.is_match("xaab"), Ok(true));
+    pub fn new(expression: &str) -> Result<Self, RegexError> {
+        Regex::with_options(expression, RegexOptions::default())
+    }
+
+    /// Compiles an expression with the given options
+    /// - With the perl option the lazy and possessive quantifiers can be used
+    /// # Arguments
+    /// * `expression` - A string slice that holds the regex expression
+    /// * `options` - A RegexOptions
+    /// # Returns
+    /// * A Result with a Regex or an error
+    /// # Example
+    /// let regex = Regex::with_options("hola", RegexOptions { ignore_case: true, ..Default::default() }).unwrap();
+    /// assert_eq!(regex.is_match("HOLA"), Ok(true));
+    pub fn with_options(expression: &str, options: RegexOptions) -> Result<Self, RegexError> {
+        let syntax = Syntax {
+            perl: options.perl,
+            ..Default::default()
+        };
+        let parsed = regex::Regex::with_syntax(expression, &syntax)?;
+        Ok(Regex {
+            expression: expression.to_string(),
+            program: Program::with_options(&parsed, options),
+        })
+    }
+
+    /// Returns the expression the regex was created from
+    pub fn as_str(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns the compiled regex, to use it with the functions of the matching module
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Checks if the regex matches anywhere in a word
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with a boolean, or a BacktrackLimit error
+    pub fn is_match(&self, word: &str) -> Result<bool, RegexError> {
+        Ok(self.find(word)?.is_some())
+    }
+
+    /// Finds the leftmost match of the regex in a word
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with an Option with the Match, or a BacktrackLimit error
+    /// # Example
+    /// let regex = Regex::new("o+").unwrap();
+    /// assert_eq!(regex.find("foo"), Ok(Some(Match { start: 1, end: 3 })));
+    pub fn find(&self, word: &str) -> Result<Option<Match>, RegexError> {
+        find_at(std::slice::from_ref(&self.program), word, 0)
+    }
+
+    /// Finds every non-overlapping match of the regex in a word, from left to right
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with a vector of Match, or a BacktrackLimit error
+    pub fn find_iter(&self, word: &str) -> Result<Vec<Match>, RegexError> {
+        find_iter(std::slice::from_ref(&self.program), word)
+    }
+
+    /// Finds the leftmost match of the regex in a word, with the text matched by each group
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with an Option with the Captures, or a BacktrackLimit error
+    /// # Example
+    /// let regex = Regex::new("(a+)(b)").unwrap();
+    /// assert_eq!(regex.captures("xaab").unwrap().unwrap().get(1), Some(Match { start: 1, end: 3 }));
+    pub fn captures(&self, word: &str) -> Result<Option<Captures>, RegexError> {
+        captures_at(&self.program, word, 0)
+    }
+
+    /// Finds the captures of every non-overlapping match of the regex in a word
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with a vector of Captures, or a BacktrackLimit error
+    pub fn captures_iter(&self, word: &str) -> Result<Vec<Captures>, RegexError> {
+        captures_iter(std::slice::from_ref(&self.program), word)
+    }
+
+    /// Replaces the leftmost match of the regex in a word
+    /// - The replacement is expanded like in Captures::expand, so $1 is the text of the first group
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// * `replacement` - The replacement template
+    /// # Returns
+    /// * A Result with a String with the word, the same word if the regex doesn't match,
+    ///   or a BacktrackLimit error
+    /// # Example
+    /// let regex = Regex::new("(o)+").unwrap();
+    /// assert_eq!(regex.replace("foo boo", "[$1]"), Ok("f[o] boo".to_string()));
+    pub fn replace(&self, word: &str, replacement: &str) -> Result<String, RegexError> {
+        let all = self.captures(word)?.into_iter().collect();
+        Ok(self.replace_captures(word, replacement, all))
+    }
+
+    /// Replaces every non-overlapping match of the regex in a word
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// * `replacement` - The replacement template
+    /// # Returns
+    /// * A Result with a String with the word, or a BacktrackLimit error
+    /// # Example
+    /// let regex = Regex::new("(o)+").unwrap();
+    /// assert_eq!(regex.replace_all("foo boo", "[$1]"), Ok("f[o] b[o]".to_string()));
+    pub fn replace_all(&self, word: &str, replacement: &str) -> Result<String, RegexError> {
+        let all = self.captures_iter(word)?;
+        Ok(self.replace_captures(word, replacement, all))
+    }
+
+    /// Replaces the whole match of each Captures, which must be in order and not overlap
+    fn replace_captures(&self, word: &str, replacement: &str, all: Vec<Captures>) -> String {
+        let mut result = String::with_capacity(word.len());
+        let mut last = 0;
+        for captures in all {
+            let Some(m) = captures.get(0) else {
+                continue;
+            };
+            result.push_str(&word[last..m.start]);
+            captures.expand(replacement, word, &mut result);
+            last = m.end;
+        }
+        result.push_str(&word[last..]);
+        result
+    }
+
+    /// Splits a word by the matches of the regex
+    /// - The parts before the first match and after the last one are kept, even if they are empty
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with a vector with the parts of the word, or a BacktrackLimit error
+    /// # Example
+    /// let regex = Regex::new("[, ]+").unwrap();
+    /// assert_eq!(regex.split("a, b,c"), Ok(vec!["a", "b", "c"]));
+    pub fn split<'a>(&self, word: &'a str) -> Result<Vec<&'a str>, RegexError> {
+        let mut parts = Vec::new();
+        let mut last = 0;
+        for m in self.find_iter(word)? {
+            parts.push(&word[last..m.start]);
+            last = m.end;
+        }
+        parts.push(&word[last..]);
+        Ok(parts)
+    }
+}
+
+/// Parses a Regex with the default options, so that str::parse can be used
+impl FromStr for Regex {
+    type Err = RegexError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        Regex::new(expression)
+    }
+}
+
+/// Shows the expression the Regex was created from
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(matches: Vec<Match>) -> Vec<(usize, usize)> {
+        matches.iter().map(|m| (m.start, m.end)).collect()
+    }
+
+    #[test]
+    fn matches() {
+        let regex = Regex::new("ab|abab").unwrap();
+        assert!(regex.is_match("xabab").unwrap());
+        assert!(!regex.is_match("ba").unwrap());
+        assert_eq!(
+            regex.find("xabab").unwrap(),
+            Some(Match { start: 1, end: 5 })
+        );
+        assert_eq!(
+            spans(regex.find_iter("ab abab").unwrap()),
+            vec![(0, 2), (3, 7)]
+        );
+    }
+
+    #[test]
+    fn options() {
+        let options = RegexOptions {
+            perl: true,
+            ..Default::default()
+        };
+        let regex = Regex::with_options("ab|abab", options.clone()).unwrap();
+        assert_eq!(
+            regex.find("abab").unwrap(),
+            Some(Match { start: 0, end: 2 })
+        );
+        assert!(Regex::with_options("a*?", options).is_ok());
+        assert!(Regex::new("a*?").is_err());
+    }
+
+    #[test]
+    fn captures() {
+        let regex = Regex::new("([a-z]+)=([0-9]+)").unwrap();
+        let captures = regex.captures("x a=1").unwrap().unwrap();
+        assert_eq!(captures.get(1).unwrap().as_str("x a=1"), "a");
+        let all = regex.captures_iter("a=1 bc=23").unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].get(2), Some(Match { start: 7, end: 9 }));
+    }
+
+    #[test]
+    fn named_captures() {
+        let regex = Regex::new("(?P<key>[a-z]+)=(?P<value>[0-9]+)").unwrap();
+        let captures = regex.captures("x a=1").unwrap().unwrap();
+        assert_eq!(captures.name("value"), Some(Match { start: 4, end: 5 }));
+        assert_eq!(captures.group_name(1), Some("key"));
+        assert_eq!(
+            regex.replace_all("a=1 b=2", "$value=${key}").unwrap(),
+            "1=a 2=b"
+        );
+    }
+
+    #[test]
+    fn replace() {
+        let regex = Regex::new("([a-z]+)=([0-9]+)").unwrap();
+        assert_eq!(regex.replace("a=1 b=2", "$2=$1").unwrap(), "1=a b=2");
+        assert_eq!(regex.replace_all("a=1 b=2", "$2=$1").unwrap(), "1=a 2=b");
+        assert_eq!(regex.replace_all("a=1", "${1}x $$0").unwrap(), "ax $0");
+        assert_eq!(regex.replace_all("none", "$1").unwrap(), "none");
+        let regex = Regex::new("x*").unwrap();
+        assert_eq!(regex.replace_all("abc", "-").unwrap(), "-a-b-c-");
+    }
+
+    #[test]
+    fn split() {
+        let regex = Regex::new(",").unwrap();
+        assert_eq!(regex.split("a,b,,c").unwrap(), vec!["a", "b", "", "c"]);
+        assert_eq!(regex.split(",a,").unwrap(), vec!["", "a", ""]);
+        assert_eq!(regex.split("").unwrap(), vec![""]);
+    }
+
+    #[test]
+    fn from_str_and_display() {
+        let regex: Regex = "^(ab)+$".parse().unwrap();
+        assert_eq!(regex.to_string(), "^(ab)+$");
+        assert_eq!(regex.as_str(), "^(ab)+$");
+        assert!(regex.is_match("abab").unwrap());
+        assert!("a{2,1}".parse::<Regex>().is_err());
+    }
+}
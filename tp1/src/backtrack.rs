@@ -0,0 +1,517 @@
+use crate::matching::{handle_regex_value, other_cases};
+use crate::nfa::{Inst, Program};
+use crate::regex_error::RegexError;
+use crate::regex_options::RegexOptions;
+use crate::simulation::handle_restriction;
+use std::collections::{HashMap, HashSet};
+
+/// Enum to represent a pending job of the backtracking
+enum Job {
+    /// Runs the instruction at a position of the word
+    Explore(usize, usize),
+    /// Gives a slot back its previous value, when the path that saved it is abandoned
+    Restore(usize, Option<usize>),
+}
+
+/// Struct to represent where a path of the backtracking stopped
+struct Found {
+    /// The instruction, a Match or the Commit of an atomic part
+    pc: usize,
+    /// The position of the word
+    position: usize,
+    /// The slots of the groups
+    slots: Vec<Option<usize>>,
+}
+
+/// The most states that a search with backreferences can explore before it gives up
+/// - Each state of a regex with backreferences keeps the text of the groups, so their number can grow
+///   with the square of the length of the line, like the ones of ([a-z]{3,})\1
+pub const BACKTRACK_LIMIT: usize = 1 << 22;
+
+/// The most states that a search without backreferences can remember, with a bit for each one, 32 MiB
+/// - The bitmap has a bit for each instruction and position, so a big regex on a long line, like
+///   ([a-z]{1,20000})x with the Perl option, would need gigabytes
+pub const VISITED_LIMIT: usize = 1 << 28;
+
+/// Struct to remember the states that were already explored
+/// - Without backreferences a state is an instruction and a position, since the rest of the
+///   match doesn't depend on the saved groups, so every state is explored once
+/// - With backreferences the slots that the backreferences read are part of the state, the other
+///   slots don't change the rest of the match
+enum Visited {
+    /// A bit for each instruction and position
+    Bits(Vec<u64>, usize),
+    /// The instruction, the position and the id of the slots read by the backreferences
+    /// - `key` and `id` are the last slots that were seen, most states have the slots of the one before
+    States {
+        states: HashSet<(usize, usize, usize)>,
+        ids: HashMap<Vec<Option<usize>>, usize>,
+        read: Vec<usize>,
+        key: Vec<Option<usize>>,
+        id: usize,
+    },
+}
+
+impl Visited {
+    /// Creates the visited states of a search, or a BacktrackLimit error if the bitmap needs more than VISITED_LIMIT bits
+    fn new(program: &Program, word: &str) -> Result<Self, RegexError> {
+        if program.has_backrefs {
+            let mut read: Vec<usize> = program
+                .insts
+                .iter()
+                .filter_map(|inst| match inst {
+                    Inst::Backref(group) => Some(*group),
+                    _ => None,
+                })
+                .flat_map(|group| [2 * group, 2 * group + 1])
+                .collect();
+            read.sort_unstable();
+            read.dedup();
+            return Ok(Visited::States {
+                states: HashSet::new(),
+                ids: HashMap::new(),
+                read,
+                key: vec![],
+                id: usize::MAX,
+            });
+        }
+        let width = word.len() + 1;
+        let size = program.insts.len().saturating_mul(width);
+        if size > VISITED_LIMIT {
+            return Err(RegexError::BacktrackLimit {
+                limit: VISITED_LIMIT,
+            });
+        }
+        Ok(Visited::Bits(vec![0; size.div_ceil(64)], width))
+    }
+
+    /// Forgets the states explored from another start position
+    /// - Without backreferences a state that failed from a start fails from every other one, so they are kept
+    fn clear(&mut self) {
+        if let Visited::States {
+            states, ids, id, ..
+        } = self
+        {
+            states.clear();
+            ids.clear();
+            *id = usize::MAX;
+        }
+    }
+
+    /// Marks the state as visited, and returns whether it was new
+    /// - Each new state with backreferences uses one unit of the budget, and it fails when there is none left,
+    ///   backtrack_with_limit gives the error its limit
+    fn insert(
+        &mut self,
+        pc: usize,
+        position: usize,
+        slots: &[Option<usize>],
+        budget: &mut usize,
+    ) -> Result<bool, RegexError> {
+        match self {
+            Visited::Bits(bits, width) => {
+                let index = pc * *width + position;
+                let (word, bit) = (index / 64, 1 << (index % 64));
+                let new = bits[word] & bit == 0;
+                bits[word] |= bit;
+                Ok(new)
+            }
+            Visited::States {
+                states,
+                ids,
+                read,
+                key,
+                id,
+            } => {
+                let value = |slot: &usize| slots.get(*slot).copied().flatten();
+                if *id == usize::MAX || !read.iter().map(value).eq(key.iter().copied()) {
+                    key.clear();
+                    key.extend(read.iter().map(value));
+                    let next = ids.len();
+                    *id = *ids.entry(key.clone()).or_insert(next);
+                }
+                if !states.insert((pc, position, *id)) {
+                    return Ok(false);
+                }
+                if *budget == 0 {
+                    return Err(RegexError::BacktrackLimit { limit: 0 });
+                }
+                *budget -= 1;
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Checks if the text of a group is at a position of the word, and returns the position after it
+fn match_backreference(
+    word: &str,
+    position: usize,
+    group: &str,
+    options: &RegexOptions,
+) -> Option<usize> {
+    let mut rest = word[position..].chars();
+    for expected in group.chars() {
+        let c = rest.next()?;
+        if c != expected && !(options.ignore_case && other_cases(c).contains(&expected)) {
+            return None;
+        }
+    }
+    Some(word.len() - rest.as_str().len())
+}
+
+/// Explores the paths of the program from an instruction and a position, in the order of priority of the splits
+/// - If `first` is set, it stops at the first Match, like Perl, otherwise it returns the longest match,
+///   and between matches with the same end the groups are the ones of the first path
+/// - It always stops at the first Commit, the end of the atomic part that is being explored
+/// - An atomic part is explored on its own, and only the first way of matching it is kept
+fn explore(
+    program: &Program,
+    word: &str,
+    (pc, position): (usize, usize),
+    mut slots: Vec<Option<usize>>,
+    visited: &mut Visited,
+    first: bool,
+    budget: &mut usize,
+) -> Result<Option<Found>, RegexError> {
+    let mut best: Option<Found> = None;
+    let mut jobs = vec![Job::Explore(pc, position)];
+
+    while let Some(job) = jobs.pop() {
+        let (pc, position) = match job {
+            Job::Explore(pc, position) => (pc, position),
+            Job::Restore(slot, value) => {
+                slots[slot] = value;
+                continue;
+            }
+        };
+        if !visited.insert(pc, position, &slots, budget)? {
+            continue;
+        }
+        match &program.insts[pc] {
+            Inst::Char(val) => {
+                if let Some(c) = word[position..].chars().next() {
+                    if handle_regex_value(val, c, &program.options) {
+                        jobs.push(Job::Explore(pc + 1, position + c.len_utf8()));
+                    }
+                }
+            }
+            Inst::Split(first, second) => {
+                jobs.push(Job::Explore(*second, position));
+                jobs.push(Job::Explore(*first, position));
+            }
+            Inst::Jump(next) => jobs.push(Job::Explore(*next, position)),
+            Inst::Assert(restriction) => {
+                if handle_restriction(restriction, word, position, &program.options) {
+                    jobs.push(Job::Explore(pc + 1, position));
+                }
+            }
+            Inst::Save(slot) => {
+                jobs.push(Job::Restore(*slot, slots[*slot]));
+                slots[*slot] = Some(position);
+                jobs.push(Job::Explore(pc + 1, position));
+            }
+            Inst::Backref(group) => {
+                if let (Some(Some(from)), Some(Some(to))) =
+                    (slots.get(2 * group), slots.get(2 * group + 1))
+                {
+                    let text = &word[*from..*to];
+                    if let Some(next) = match_backreference(word, position, text, &program.options)
+                    {
+                        jobs.push(Job::Explore(pc + 1, next));
+                    }
+                }
+            }
+            Inst::Atomic => {
+                let mut inner = Visited::new(program, word)?;
+                let atomic = explore(
+                    program,
+                    word,
+                    (pc + 1, position),
+                    slots.clone(),
+                    &mut inner,
+                    true,
+                    budget,
+                )?;
+                if let Some(atomic) = atomic {
+                    for (slot, value) in atomic.slots.into_iter().enumerate() {
+                        if slots[slot] != value {
+                            jobs.push(Job::Restore(slot, slots[slot]));
+                            slots[slot] = value;
+                        }
+                    }
+                    jobs.push(Job::Explore(atomic.pc + 1, atomic.position));
+                }
+            }
+            Inst::Commit => {
+                return Ok(Some(Found {
+                    pc,
+                    position,
+                    slots,
+                }))
+            }
+            Inst::Match => {
+                if best.as_ref().is_none_or(|b| position > b.position) {
+                    let mut found = slots.clone();
+                    found[1] = Some(position);
+                    best = Some(Found {
+                        pc,
+                        position,
+                        slots: found,
+                    });
+                }
+                if first {
+                    return Ok(best);
+                }
+            }
+        }
+    }
+    Ok(best)
+}
+
+/// Explores every path of the program from a start position, and returns the match with its groups
+/// - The match is the longest one, or the first one with the Perl option
+fn match_from(
+    program: &Program,
+    word: &str,
+    start: usize,
+    visited: &mut Visited,
+    budget: &mut usize,
+) -> Result<Option<Vec<Option<usize>>>, RegexError> {
+    let mut slots: Vec<Option<usize>> = vec![None; 2 * (program.groups + 1)];
+    slots[0] = Some(start);
+    explore(
+        program,
+        word,
+        (0, start),
+        slots,
+        visited,
+        program.options.perl,
+        budget,
+    )
+    .map(|found| found.map(|found| found.slots))
+}
+
+/// Runs the program with backtracking from a byte offset, and returns the leftmost-longest match with its groups
+/// - It is slower than the NFA simulation, but it knows where each group matched, so it can match backreferences
+/// - With the Perl option the match is the leftmost-first one, and the lazy quantifiers match as little as they can
+/// - With backreferences it gives up after exploring BACKTRACK_LIMIT states, instead of running out of memory,
+///   and without them it gives up when the bitmap of the states would have more than VISITED_LIMIT bits
+/// # Arguments
+/// * `program` - A reference to a Program
+/// * `word` - A reference to a str
+/// * `from` - The byte offset where the search starts, at the start of a char
+/// # Returns
+/// * A Result with an Option with the slots of the groups, the whole match is in the slots 0 and 1,
+///   or a BacktrackLimit error
+/// # Example
+/// let program = Program::new(&Regex::new("(a|b)\\1").unwrap());
+/// let result = backtrack_at(&program, "abba", 0);
+/// assert_eq!(result, Ok(Some(vec![Some(1), Some(3), Some(1), Some(2)])));
+pub fn backtrack_at(
+    program: &Program,
+    word: &str,
+    from: usize,
+) -> Result<Option<Vec<Option<usize>>>, RegexError> {
+    backtrack_with_limit(program, word, from, BACKTRACK_LIMIT)
+}
+
+/// Runs the program with backtracking like backtrack_at, exploring at most `limit` states with backreferences
+fn backtrack_with_limit(
+    program: &Program,
+    word: &str,
+    from: usize,
+    limit: usize,
+) -> Result<Option<Vec<Option<usize>>>, RegexError> {
+    let mut visited = Visited::new(program, word)?;
+    let mut budget = limit;
+    let mut start = from;
+    loop {
+        visited.clear();
+        let found = match_from(program, word, start, &mut visited, &mut budget)
+            .map_err(|_| RegexError::BacktrackLimit { limit })?;
+        if let Some(slots) = found {
+            return Ok(Some(slots));
+        }
+        if program.start_of_line {
+            return Ok(None);
+        }
+        match word[start..].chars().next() {
+            Some(c) => start += c.len_utf8(),
+            None => return Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Syntax;
+    use crate::regex::Regex;
+
+    fn find(expression: &str, word: &str) -> Option<Vec<Option<usize>>> {
+        backtrack_at(&Program::new(&Regex::new(expression).unwrap()), word, 0).unwrap()
+    }
+
+    #[test]
+    fn groups() {
+        assert_eq!(
+            find("(a+)(b)", "xaab"),
+            Some(vec![Some(1), Some(4), Some(1), Some(3), Some(3), Some(4)])
+        );
+        assert_eq!(
+            find("(a)|(b)", "b"),
+            Some(vec![Some(0), Some(1), None, None, Some(0), Some(1)])
+        );
+    }
+
+    #[test]
+    fn longest() {
+        assert_eq!(find("a|ab", "ab"), Some(vec![Some(0), Some(2)]));
+        assert_eq!(find("(a*)*", "aa").map(|s| s[1]), Some(Some(2)));
+    }
+
+    #[test]
+    fn backreferences() {
+        assert_eq!(
+            find("(..)\\1", "xabab"),
+            Some(vec![Some(1), Some(5), Some(1), Some(3)])
+        );
+        assert_eq!(find("(a)\\1", "ab"), None);
+        assert_eq!(find("^(a*)b\\1$", "aabaa").map(|s| s[1]), Some(Some(5)));
+        assert_eq!(find("^(a*)b\\1$", "aaba"), None);
+    }
+
+    #[test]
+    fn backreference_to_unmatched_group() {
+        assert_eq!(find("(a)|b\\1", "b"), None);
+    }
+
+    fn find_perl(expression: &str, word: &str) -> Option<(usize, usize)> {
+        let syntax = Syntax {
+            perl: true,
+            ..Default::default()
+        };
+        let regex = Regex::with_syntax(expression, &syntax).unwrap();
+        let options = RegexOptions {
+            perl: true,
+            ..Default::default()
+        };
+        let slots = backtrack_at(&Program::with_options(&regex, options), word, 0).unwrap()?;
+        Some((slots[0]?, slots[1]?))
+    }
+
+    #[test]
+    fn first_match() {
+        assert_eq!(find_perl("a|ab", "ab"), Some((0, 1)));
+        assert_eq!(find_perl("ab|a", "ab"), Some((0, 2)));
+        assert_eq!(find_perl("a*", "aaa"), Some((0, 3)));
+    }
+
+    #[test]
+    fn lazy() {
+        assert_eq!(find_perl("\"(.*?)\"", "say \"a\" and \"b\""), Some((4, 7)));
+        assert_eq!(find_perl("a+?", "aaa"), Some((0, 1)));
+        assert_eq!(find_perl("a{2,4}?", "aaaa"), Some((0, 2)));
+        assert_eq!(find_perl("a??b", "ab"), Some((0, 2)));
+        assert_eq!(find_perl("x.*?$", "xab"), Some((0, 3)));
+    }
+
+    #[test]
+    fn possessive() {
+        assert_eq!(find_perl("a*+a", "aaa"), None);
+        assert_eq!(find_perl("a*+b", "aab"), Some((0, 3)));
+        assert_eq!(find_perl("(a|ab)++c", "abc"), None);
+        assert_eq!(find_perl("(ab|a)++c", "abc"), Some((0, 3)));
+        assert_eq!(find_perl("\"[^\"]*+\"", "x \"y\""), Some((2, 5)));
+    }
+
+    #[test]
+    fn possessive_keeps_groups() {
+        let syntax = Syntax {
+            perl: true,
+            ..Default::default()
+        };
+        let regex = Regex::with_syntax("(b)?(a)++", &syntax).unwrap();
+        let program = Program::new(&regex);
+        assert_eq!(
+            backtrack_at(&program, "aa", 0),
+            Ok(Some(vec![Some(0), Some(2), None, None, Some(1), Some(2)]))
+        );
+    }
+
+    #[test]
+    fn ignore_case() {
+        let regex = Regex::new("(a)\\1").unwrap();
+        let options = RegexOptions {
+            ignore_case: true,
+            ..Default::default()
+        };
+        let program = Program::with_options(&regex, options);
+        assert_eq!(
+            backtrack_at(&program, "aA", 0).map(|s| s.map(|s| s[1])),
+            Ok(Some(Some(2)))
+        );
+    }
+
+    /// Returns a word without two equal parts one after the other, from the Thue-Morse sequence
+    fn square_free(len: usize) -> String {
+        let mut word = String::new();
+        let mut ones = 0;
+        let mut n: u32 = 1;
+        while word.len() < len {
+            match n.count_ones() % 2 {
+                0 => {
+                    word.push(['a', 'b', 'c'][ones]);
+                    ones = 0;
+                }
+                _ => ones += 1,
+            }
+            n += 1;
+        }
+        word
+    }
+
+    #[test]
+    fn long_line() {
+        let line = square_free(5000);
+        let program = Program::new(&Regex::new("([a-z]{3,})\\1").unwrap());
+        assert_eq!(
+            backtrack_with_limit(&program, &line, 0, 100_000),
+            Err(RegexError::BacktrackLimit { limit: 100_000 })
+        );
+        assert_eq!(
+            find("([a-z]{3,})\\1", &format!("{}-abcabc", &line[..500])).map(|s| s[0]),
+            Some(Some(501))
+        );
+        let line = format!("{}xabab", line);
+        assert_eq!(find("x(ab)\\1", &line).map(|s| s[1]), Some(Some(5005)));
+        let line = format!("{}b", "a".repeat(2000));
+        assert_eq!(find("(a)\\1*b", &line).map(|s| s[0]), Some(Some(0)));
+    }
+
+    #[test]
+    fn big_bitmap() {
+        let syntax = Syntax {
+            perl: true,
+            ..Default::default()
+        };
+        let regex = Regex::with_syntax("([a-z]{1,20000})x", &syntax).unwrap();
+        let options = RegexOptions {
+            perl: true,
+            ..Default::default()
+        };
+        let program = Program::with_options(&regex, options);
+        let line = format!("{}x", "a".repeat(10000));
+        assert_eq!(
+            backtrack_at(&program, &line, 0),
+            Err(RegexError::BacktrackLimit {
+                limit: VISITED_LIMIT
+            })
+        );
+        assert_eq!(
+            backtrack_at(&program, "abx", 0).unwrap().map(|s| s[3]),
+            Some(Some(2))
+        );
+    }
+}
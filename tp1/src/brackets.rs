@@ -1,139 +1,129 @@
-use crate::metachars::{handle_metachar, RegexClass};
+use crate::char_set::{collating_symbol, CharSet, SetItem};
+use crate::metachars::handle_metachar;
+use crate::regex_error::RegexError;
 use crate::regex_rep::RegexRep;
 use crate::regex_step::RegexStep;
 use crate::regex_val::RegexValue;
 use std::str::Chars;
 
-/// Function to handle random strings
-/// It receives a string and returns a Result with a vector of characters or an error
+/// Function to handle the name of a class, an equivalence class or a collating symbol
+/// It receives a mutable reference to Chars right after the "[:", "[=" or "[." and returns the text before the closing ":]", "=]" or ".]"
 /// # Example
-/// receives "aeiou" and returns Ok(vec!['a', 'e', 'i', 'o', 'u'])
-fn handle_random_string(n: String) -> Result<Vec<char>, &'static str> {
-    Ok(n.chars().collect())
+/// receives a mutable reference to Chars with "alpha:]]" and ':' and returns Ok("alpha".to_string())
+fn handle_bracket_name(
+    chars_iter: &mut Chars,
+    delimiter: char,
+    position: usize,
+) -> Result<String, RegexError> {
+    let rest = chars_iter.as_str();
+    let close = format!("{}]", delimiter);
+    let end = rest
+        .find(&close)
+        .ok_or(RegexError::InvalidClass { position })?;
+    let name = rest[..end].to_string();
+    *chars_iter = rest[end + close.len()..].chars();
+    Ok(name)
 }
 
-/// Function to handle the content of the brackets
-/// It receives a mutable reference to Chars and returns a Result with a RegexValue or an error
+/// Function to handle a character that can be part of a range: a literal or a collating symbol
+/// It receives the character already read and a mutable reference to Chars and returns a Result with the character or an error
 /// # Example
-/// receives a mutable reference to Chars with "aeiou" and returns Ok(RegexValue::OneOf(vec!['a', 'e', 'i', 'o', 'u']))
-fn handle_content(chars: &mut Chars) -> Result<RegexValue, &'static str> {
-    let n = chars.as_str().to_string();
-    let chars_vec: Vec<char> = n.chars().collect();
-    let value: RegexValue;
-    match n.as_str() {
-        "aeiou" => {
-            value = RegexValue::OneOf(vec!['a', 'e', 'i', 'o', 'u']);
-        }
-        "0-9" => {
-            value = RegexValue::Class(RegexClass::Digit);
-        }
-        "a-z" => {
-            value = RegexValue::Class(RegexClass::Lower);
-        }
-        "A-Z" => {
-            value = RegexValue::Class(RegexClass::Upper);
-        }
-        _ => {
-            if chars_vec[0] == ':' {
-                value = RegexValue::Class(handle_metachar(n)?);
-            } else {
-                value = RegexValue::OneOf(handle_random_string(n)?);
-            }
-        }
+/// receives '[' and a mutable reference to Chars with ".hyphen.]]" and returns Ok('-')
+fn handle_range_char(c: char, chars_iter: &mut Chars, position: usize) -> Result<char, RegexError> {
+    if c == '[' && chars_iter.as_str().starts_with('.') {
+        chars_iter.next();
+        let name = handle_bracket_name(chars_iter, '.', position)?;
+        return collating_symbol(&name).ok_or(RegexError::InvalidClass { position });
     }
-
-    Ok(value)
+    Ok(c)
 }
 
-/// Function to handle the not metacharacter
-/// It receives a mutable reference to Chars and returns a Result with a RegexStep or an error
+/// Function to handle an item of the brackets
+/// It receives the character already read and a mutable reference to Chars and returns a Result with a SetItem or an error
+/// - A '-' is a range only between two characters, at the edges it's a literal
+/// - The end of a range can't start another range, so a-c-e is an error like in GNU grep
 /// # Example
-/// receives a mutable reference to Chars with "aeiou" and returns Ok(RegexStep { rep: RegexRep::None, val: RegexValue::OneOf(vec!['a', 'e', 'i', 'o', 'u']) })
-fn handle_not(chars: &mut Chars) -> Result<Option<RegexStep>, &'static str> {
-    let value = handle_content(chars)?;
-    Ok(Some(RegexStep {
-        rep: RegexRep::None,
-        val: value,
-    }))
-}
+/// receives 'a' and a mutable reference to Chars with "-f]" and returns Ok(SetItem::Range('a', 'f'))
+fn handle_item(c: char, chars_iter: &mut Chars, position: usize) -> Result<SetItem, RegexError> {
+    if c == '[' && chars_iter.as_str().starts_with(':') {
+        chars_iter.next();
+        let name = handle_bracket_name(chars_iter, ':', position)?;
+        return Ok(SetItem::Class(handle_metachar(
+            format!(":{}:", name),
+            position,
+        )?));
+    }
+    if c == '[' && chars_iter.as_str().starts_with('=') {
+        chars_iter.next();
+        let name = handle_bracket_name(chars_iter, '=', position)?;
+        let equivalent = collating_symbol(&name).ok_or(RegexError::InvalidClass { position })?;
+        return Ok(SetItem::Equivalence(equivalent));
+    }
 
-/// Function to handle the optional metacharacter
-/// It receives a mutable reference to Chars and returns a Result with a RegexStep or an error
-/// # Example
-/// receives a mutable reference to Chars with "aeiou" and returns Ok(RegexStep { rep: RegexRep::Exact(1), val: RegexValue::OneOf(vec!['a', 'e', 'i', 'o', 'u']) })
-fn handle_optional(chars: &mut Chars) -> Result<Option<RegexStep>, &'static str> {
-    let value = handle_content(chars)?;
-    Ok(Some(RegexStep {
-        rep: RegexRep::Exact(1),
-        val: value,
-    }))
+    let start = handle_range_char(c, chars_iter, position)?;
+    let rest = chars_iter.as_str();
+    if !rest.starts_with('-') || rest.len() == 1 || rest[1..].starts_with(']') {
+        return Ok(SetItem::Char(start));
+    }
+    chars_iter.next();
+    let c = chars_iter
+        .next()
+        .ok_or(RegexError::UnclosedBrackets { position })?;
+    let end = handle_range_char(c, chars_iter, position)?;
+    let rest = chars_iter.as_str();
+    let another_range = rest.starts_with('-') && rest.len() > 1 && !rest[1..].starts_with(']');
+    if end < start || another_range {
+        return Err(RegexError::InvalidRange { position });
+    }
+    Ok(SetItem::Range(start, end))
 }
 
-/// Function to process the content inside the brackets
-/// It receives a mutable reference to Chars, a mutable reference to a String, a mutable reference to a bool and a mutable reference to a bool
+/// Function to handle the set of the brackets
+/// It receives a CharSet and returns a RegexValue
+/// - A set that only has a class is the class itself
 /// # Example
-/// receives a mutable reference to Chars with "aeiou", a mutable reference to a String with "a", a mutable reference to a bool with false and a mutable reference to a bool with false
-fn process_inside_brackets(
-    chars_iter: &mut Chars,
-    n: &mut String,
-    flag: &mut bool,
-    inside_brackets: &mut bool,
-) -> Result<(), &'static str> {
-    for c in chars_iter.by_ref() {
-        match c {
-            '[' => {
-                *inside_brackets = true;
-                continue;
-            }
-            ']' => {
-                if *inside_brackets {
-                    *inside_brackets = false;
-                    if n.is_empty()
-                        || (*flag && n.len() == 1 && matches!(n.chars().next(), Some('^')))
-                    {
-                        return Err("Empty brackets or invalid caret usage");
-                    }
-                    continue;
-                } else {
-                    break;
-                }
-            }
-            '^' => {
-                *flag = true;
-                continue;
-            }
-            _ => n.push(c),
-        }
-    }
-    if n.is_empty() {
-        return Err("Empty brackets");
+/// receives CharSet { negated: false, items: vec![SetItem::Class(RegexClass::Alpha)] } and returns RegexValue::Class(RegexClass::Alpha)
+fn handle_set(set: CharSet) -> RegexValue {
+    match set.items.as_slice() {
+        [SetItem::Class(class)] if !set.negated => RegexValue::Class(class.clone()),
+        _ => RegexValue::Set(set),
     }
-
-    Ok(())
 }
 
 /// Function to handle the brackets
-/// It receives a mutable reference to Chars and returns a Result with a RegexStep or an error
+/// It receives a mutable reference to Chars right after the '[' and the position of the '[' and returns a Result with a RegexStep or an error
+/// - A ']' right after the '[' or the '[^' is a member of the set, not the end of it
+/// - An item can be a character, a range like a-z, a class like [:alpha:], an equivalence class like [=a=] or a collating symbol like [.hyphen.]
 /// # Example
-/// receives a mutable reference to Chars with "aeiou" and returns Ok(RegexStep { rep: RegexRep::None, val: RegexValue::OneOf(vec!['a', 'e', 'i', 'o', 'u']) })
-pub fn handle_brackets(chars_iter: &mut Chars) -> Result<Option<RegexStep>, &'static str> {
-    let mut n = String::new();
-    let mut flag = false;
-    let mut inside_brackets = false;
-
-    process_inside_brackets(chars_iter, &mut n, &mut flag, &mut inside_brackets)?;
-
-    if n.is_empty() {
-        return Err("Empty brackets");
+/// receives a mutable reference to Chars with "^a-f_]" and returns Ok(RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Set(CharSet { negated: true, items: vec![SetItem::Range('a', 'f'), SetItem::Char('_')] }) })
+pub fn handle_brackets(
+    chars_iter: &mut Chars,
+    position: usize,
+) -> Result<Option<RegexStep>, RegexError> {
+    let negated = chars_iter.as_str().starts_with('^');
+    if negated {
+        chars_iter.next();
     }
-    if flag {
-        handle_not(&mut n.chars())
-    } else {
-        handle_optional(&mut n.chars())
+
+    let mut items = vec![];
+    loop {
+        let c = chars_iter
+            .next()
+            .ok_or(RegexError::UnclosedBrackets { position })?;
+        if c == ']' && !items.is_empty() {
+            break;
+        }
+        items.push(handle_item(c, chars_iter, position)?);
     }
+
+    Ok(Some(RegexStep {
+        rep: RegexRep::Exact(1),
+        val: handle_set(CharSet { negated, items }),
+    }))
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -142,50 +132,50 @@ mod tests {
 
         #[test]
         fn brackets_vowel() {
-            let mut s = "[aeiou]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "aeiou]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
 
         #[test]
         fn brackets_not_vowel() {
-            let mut s = "[^aeiou]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "^aeiou]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
 
         #[test]
         fn brackets_class_digit() {
-            let mut s = "[0-9]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "0-9]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
 
         #[test]
         fn brackets_class_lower() {
-            let mut s = "[a-z]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "a-z]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
 
         #[test]
         fn brackets_class_upper() {
-            let mut s = "[A-Z]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "A-Z]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
 
         #[test]
         fn brackets_metachar_alpha() {
-            let mut s = "[:alpha:]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "[:alpha:]]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
 
         #[test]
         fn brackets_metachar_alnum() {
-            let mut s = "[:alnum:]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "[:alnum:]]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
     }
@@ -195,22 +185,22 @@ mod tests {
 
         #[test]
         fn brackets_empty() {
-            let mut s = "[]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_err(), true);
         }
 
         #[test]
         fn brackets_not_empty() {
-            let mut s = "[^]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "^]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_err(), true);
         }
 
         #[test]
         fn brackets_class_alpha_invalid() {
-            let mut s = "[:alpha]".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "[:alpha]]".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_err(), true);
         }
     }
@@ -220,51 +210,173 @@ mod tests {
 
         #[test]
         fn brackets_vowel_1() {
-            let mut s = "[aeiou]a".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "aeiou]a".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
 
         #[test]
         fn brackets_empty_1() {
-            let mut s = "[]a".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "]a".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_err(), true);
         }
 
         #[test]
         fn brackets_not_empty_1() {
-            let mut s = "[^]a".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "^]a".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_err(), true);
         }
 
         #[test]
         fn brackets_not_vowel_1() {
-            let mut s = "[^aeiou]a".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "^aeiou]a".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
 
         #[test]
         fn brackets_vowel_2() {
-            let mut s = "[aeiou]a".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "aeiou]a".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_ok(), true);
         }
 
         #[test]
         fn brackets_empty_2() {
-            let mut s = "[]a".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "]a".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_err(), true);
         }
 
         #[test]
         fn brackets_not_empty_2() {
-            let mut s = "[^]a".chars();
-            let result = handle_brackets(&mut s);
+            let mut s = "^]a".chars();
+            let result = handle_brackets(&mut s, 0);
             assert_eq!(result.is_err(), true);
         }
     }
+
+    mod errors {
+        use super::*;
+
+        #[test]
+        fn empty_position() {
+            let mut s = "]".chars();
+            let result = handle_brackets(&mut s, 3);
+            assert_eq!(result, Err(RegexError::UnclosedBrackets { position: 3 }));
+        }
+
+        #[test]
+        fn range_after_range() {
+            let result = handle_brackets(&mut "a-c-e]".chars(), 4);
+            assert_eq!(result, Err(RegexError::InvalidRange { position: 4 }));
+            assert!(handle_brackets(&mut "a-c-]".chars(), 0).is_ok());
+            assert!(handle_brackets(&mut "a-c_-e]".chars(), 0).is_ok());
+        }
+
+        #[test]
+        fn invalid_class_position() {
+            let mut s = "[:alfa:]]".chars();
+            let result = handle_brackets(&mut s, 2);
+            assert_eq!(result, Err(RegexError::InvalidClass { position: 2 }));
+        }
+    }
+
+    mod posix {
+        use super::*;
+        use crate::metachars::RegexClass;
+
+        fn set(s: &str) -> RegexValue {
+            handle_brackets(&mut s.chars(), 0).unwrap().unwrap().val
+        }
+
+        #[test]
+        fn several_items() {
+            assert_eq!(
+                set("a-f0-9_]"),
+                RegexValue::Set(CharSet {
+                    negated: false,
+                    items: vec![
+                        SetItem::Range('a', 'f'),
+                        SetItem::Range('0', '9'),
+                        SetItem::Char('_'),
+                    ],
+                })
+            );
+        }
+
+        #[test]
+        fn class_and_range() {
+            assert_eq!(
+                set("[:digit:]x-z]"),
+                RegexValue::Set(CharSet {
+                    negated: false,
+                    items: vec![SetItem::Class(RegexClass::Digit), SetItem::Range('x', 'z')],
+                })
+            );
+        }
+
+        #[test]
+        fn bracket_first() {
+            assert_eq!(
+                set("]a]"),
+                RegexValue::Set(CharSet {
+                    negated: false,
+                    items: vec![SetItem::Char(']'), SetItem::Char('a')],
+                })
+            );
+            assert_eq!(
+                set("^]]"),
+                RegexValue::Set(CharSet {
+                    negated: true,
+                    items: vec![SetItem::Char(']')],
+                })
+            );
+        }
+
+        #[test]
+        fn hyphen_at_edges() {
+            assert_eq!(
+                set("-a-]"),
+                RegexValue::Set(CharSet {
+                    negated: false,
+                    items: vec![SetItem::Char('-'), SetItem::Char('a'), SetItem::Char('-')],
+                })
+            );
+        }
+
+        #[test]
+        fn equivalence_and_collating() {
+            assert_eq!(
+                set("[=a=][.hyphen.]]"),
+                RegexValue::Set(CharSet {
+                    negated: false,
+                    items: vec![SetItem::Equivalence('a'), SetItem::Char('-')],
+                })
+            );
+            assert_eq!(
+                set("[.space.]-~]"),
+                RegexValue::Set(CharSet {
+                    negated: false,
+                    items: vec![SetItem::Range(' ', '~')],
+                })
+            );
+        }
+
+        #[test]
+        fn invalid_range() {
+            let mut s = "z-a]".chars();
+            let result = handle_brackets(&mut s, 4);
+            assert_eq!(result, Err(RegexError::InvalidRange { position: 4 }));
+        }
+
+        #[test]
+        fn invalid_collating() {
+            let mut s = "[.unknown.]]".chars();
+            let result = handle_brackets(&mut s, 0);
+            assert_eq!(result, Err(RegexError::InvalidClass { position: 0 }));
+        }
+    }
 }
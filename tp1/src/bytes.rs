@@ -0,0 +1,273 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::api;
+use crate::captures::Captures;
+use crate::nfa::Program;
+use crate::regex_error::RegexError;
+use crate::regex_match::Match;
+use crate::regex_options::RegexOptions;
+
+/// Struct to represent bytes that may not be valid UTF-8, decoded as text to match them
+/// - Each byte that isn't part of a valid UTF-8 char becomes its own U+FFFD, so . matches any byte
+/// - The offsets of the text are mapped back to the offsets of the bytes
+/// - Valid UTF-8 is borrowed as it is, without copying it
+pub struct Decoded<'a> {
+    /// The text of the bytes
+    text: Cow<'a, str>,
+    /// The byte offset of each offset of the text, None if the bytes are valid UTF-8
+    offsets: Option<Vec<usize>>,
+}
+
+/// Implementation of the Decoded struct
+impl<'a> Decoded<'a> {
+    /// Decodes the bytes
+    /// # Arguments
+    /// * `bytes` - A slice of bytes
+    /// # Returns
+    /// * A Decoded
+    /// # Example
+    /// let decoded = Decoded::new(b"a\xffb");
+    /// assert_eq!(decoded.as_str(), "a\u{FFFD}b");
+    /// assert_eq!(decoded.byte_offset(4), 2);
+    pub fn new(bytes: &'a [u8]) -> Self {
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return Decoded {
+                text: Cow::Borrowed(text),
+                offsets: None,
+            };
+        }
+        let mut text = String::with_capacity(bytes.len() + 8);
+        let mut offsets = Vec::with_capacity(bytes.len() + 8);
+        let mut position = 0;
+        for chunk in bytes.utf8_chunks() {
+            text.push_str(chunk.valid());
+            offsets.extend(position..position + chunk.valid().len());
+            position += chunk.valid().len();
+            for _ in chunk.invalid() {
+                text.push(char::REPLACEMENT_CHARACTER);
+                offsets.extend([position; 3]);
+                position += 1;
+            }
+        }
+        offsets.push(position);
+        Decoded {
+            text: Cow::Owned(text),
+            offsets: Some(offsets),
+        }
+    }
+
+    /// Returns the text of the bytes
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the offset in the bytes of an offset of the text, at the start of a char
+    pub fn byte_offset(&self, offset: usize) -> usize {
+        match &self.offsets {
+            Some(offsets) => offsets[offset],
+            None => offset,
+        }
+    }
+
+    /// Returns a Match of the text as a Match of the bytes
+    pub fn byte_match(&self, m: Match) -> Match {
+        Match {
+            start: self.byte_offset(m.start),
+            end: self.byte_offset(m.end),
+        }
+    }
+
+    /// Returns the Captures of the text as Captures of the bytes
+    pub fn byte_captures(&self, captures: Captures) -> Captures {
+        let slots = (0..captures.len())
+            .flat_map(|group| match captures.get(group) {
+                Some(m) => {
+                    let m = self.byte_match(m);
+                    [Some(m.start), Some(m.end)]
+                }
+                None => [None, None],
+            })
+            .collect();
+        Captures::with_names(slots, captures.names().to_vec())
+    }
+}
+
+/// Struct to represent a compiled regex that matches bytes instead of a str ( -a )
+/// - It uses the same Program as a crate::Regex, the bytes are decoded like in Decoded
+/// - The Matches and Captures have offsets of the bytes
+/// # Example
+/// let regex = Regex::new("a.b").unwrap();
+/// assert_eq!(regex.find(b"xa\xffb"), Ok(Some(Match { start: 1, end: 4 })));
+#[derive(Debug, Clone)]
+pub struct Regex {
+    /// The regex that matches the decoded text
+    regex: api::Regex,
+}
+
+/// Implementation of the Regex struct
+impl Regex {
+    /// Compiles an expression with the default options
+    /// # Arguments
+    /// * `expression` - A string slice that holds the regex expression
+    /// # Returns
+    /// * A Result with a Regex or an error
+    pub fn new(expression: &str) -> Result<Self, RegexError> {
+        api::Regex::new(expression).map(Regex::from)
+    }
+
+    /// Compiles an expression with the given options
+    /// # Arguments
+    /// * `expression` - A string slice that holds the regex expression
+    /// * `options` - A RegexOptions
+    /// # Returns
+    /// * A Result with a Regex or an error
+    pub fn with_options(expression: &str, options: RegexOptions) -> Result<Self, RegexError> {
+        api::Regex::with_options(expression, options).map(Regex::from)
+    }
+
+    /// Returns the expression the regex was created from
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// Returns the compiled regex, the same one of the str regex
+    pub fn program(&self) -> &Program {
+        self.regex.program()
+    }
+
+    /// Checks if the regex matches anywhere in the bytes
+    /// # Arguments
+    /// * `bytes` - A slice of bytes
+    /// # Returns
+    /// * A Result with a boolean, or a BacktrackLimit error
+    pub fn is_match(&self, bytes: &[u8]) -> Result<bool, RegexError> {
+        self.regex.is_match(Decoded::new(bytes).as_str())
+    }
+
+    /// Finds the leftmost match of the regex in the bytes
+    /// # Arguments
+    /// * `bytes` - A slice of bytes
+    /// # Returns
+    /// * A Result with an Option with the Match, or a BacktrackLimit error
+    pub fn find(&self, bytes: &[u8]) -> Result<Option<Match>, RegexError> {
+        let decoded = Decoded::new(bytes);
+        let m = self.regex.find(decoded.as_str())?;
+        Ok(m.map(|m| decoded.byte_match(m)))
+    }
+
+    /// Finds every non-overlapping match of the regex in the bytes, from left to right
+    /// # Arguments
+    /// * `bytes` - A slice of bytes
+    /// # Returns
+    /// * A Result with a vector of Match, or a BacktrackLimit error
+    pub fn find_iter(&self, bytes: &[u8]) -> Result<Vec<Match>, RegexError> {
+        let decoded = Decoded::new(bytes);
+        Ok(self
+            .regex
+            .find_iter(decoded.as_str())?
+            .into_iter()
+            .map(|m| decoded.byte_match(m))
+            .collect())
+    }
+
+    /// Finds the leftmost match of the regex in the bytes, with the bytes matched by each group
+    /// # Arguments
+    /// * `bytes` - A slice of bytes
+    /// # Returns
+    /// * A Result with an Option with the Captures, or a BacktrackLimit error
+    pub fn captures(&self, bytes: &[u8]) -> Result<Option<Captures>, RegexError> {
+        let decoded = Decoded::new(bytes);
+        let captures = self.regex.captures(decoded.as_str())?;
+        Ok(captures.map(|captures| decoded.byte_captures(captures)))
+    }
+}
+
+/// Uses the compiled regex of a str regex to match bytes
+impl From<api::Regex> for Regex {
+    fn from(regex: api::Regex) -> Self {
+        Regex { regex }
+    }
+}
+
+/// Parses a Regex with the default options, so that str::parse can be used
+impl FromStr for Regex {
+    type Err = RegexError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        Regex::new(expression)
+    }
+}
+
+/// Shows the expression the Regex was created from
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.regex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let decoded = Decoded::new(b"ab");
+        assert_eq!(decoded.as_str(), "ab");
+        assert_eq!(decoded.byte_offset(2), 2);
+        let decoded = Decoded::new(b"\xe9t\xc3\xa9\xff\xfe");
+        assert_eq!(decoded.as_str(), "\u{FFFD}t\u{e9}\u{FFFD}\u{FFFD}");
+        let ends: Vec<usize> = decoded
+            .as_str()
+            .char_indices()
+            .map(|(i, c)| decoded.byte_offset(i + c.len_utf8()))
+            .collect();
+        assert_eq!(ends, vec![1, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn any_byte() {
+        let regex = Regex::new("^a.b$").unwrap();
+        assert!(regex.is_match(b"a\xffb").unwrap());
+        assert!(regex.is_match("aéb".as_bytes()).unwrap());
+        assert!(!regex.is_match(b"a\xff\xffb").unwrap());
+        let regex = Regex::new("a.{2}b").unwrap();
+        assert!(regex.is_match(b"a\xff\xfeb").unwrap());
+    }
+
+    #[test]
+    fn byte_offsets() {
+        let regex = Regex::new("[[:digit:]]+").unwrap();
+        let bytes = b"\xe9\xe9 12 caf\xe9 345";
+        let matches: Vec<(usize, usize)> = regex
+            .find_iter(bytes)
+            .unwrap()
+            .iter()
+            .map(|m| (m.start, m.end))
+            .collect();
+        assert_eq!(matches, vec![(3, 5), (11, 14)]);
+        assert_eq!(
+            regex.find(b"x\xff9").unwrap(),
+            Some(Match { start: 2, end: 3 })
+        );
+        assert_eq!(regex.find(b"\xff").unwrap(), None);
+    }
+
+    #[test]
+    fn captures() {
+        let regex = Regex::new("(\\w+)=(\\w*)").unwrap();
+        let captures = regex.captures(b"\xff\xfek=v").unwrap().unwrap();
+        assert_eq!(captures.get(0), Some(Match { start: 2, end: 5 }));
+        assert_eq!(captures.get(2), Some(Match { start: 4, end: 5 }));
+    }
+
+    #[test]
+    fn same_program() {
+        let regex = api::Regex::new("a+").unwrap();
+        let program = regex.program().clone();
+        let bytes = Regex::from(regex);
+        assert_eq!(bytes.program(), &program);
+        assert_eq!(bytes.to_string(), "a+");
+    }
+}
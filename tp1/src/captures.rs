@@ -0,0 +1,257 @@
+use crate::regex_match::Match;
+
+/// Enum to represent a part of an expanded replacement template
+enum Piece<'t> {
+    /// Text of the template that is copied as it is
+    Text(&'t str),
+    /// A group that the template refers to
+    Group(Match),
+}
+
+/// Struct to represent the captures of a match
+/// - The group 0 is the whole match, the other ones are the groups ( ... ) of the regex
+/// - A group that didn't take part in the match, like the second one of (a)|(b), has no Match
+#[derive(Debug, Clone, PartialEq)]
+pub struct Captures {
+    /// The start and end of each group, the group n is in the slots 2n and 2n + 1
+    slots: Vec<Option<usize>>,
+    /// The name of each group, by its number, empty when no group has a name
+    names: Vec<Option<String>>,
+}
+
+/// Implementation of the Captures struct
+impl Captures {
+    /// Creates the captures from the slots saved while matching
+    /// # Arguments
+    /// * `slots` - A vector with the start and end of each group
+    /// # Returns
+    /// * The Captures
+    pub fn new(slots: Vec<Option<usize>>) -> Self {
+        Captures {
+            slots,
+            names: vec![],
+        }
+    }
+
+    /// Creates the captures from the slots saved while matching, with the names of the groups
+    /// # Arguments
+    /// * `slots` - A vector with the start and end of each group
+    /// * `names` - A vector with the name of each group, like Program::names
+    /// # Returns
+    /// * The Captures
+    pub fn with_names(slots: Vec<Option<usize>>, names: Vec<Option<String>>) -> Self {
+        Captures { slots, names }
+    }
+
+    /// Returns the Match of a group
+    /// # Arguments
+    /// * `group` - The number of the group, 0 is the whole match
+    /// # Returns
+    /// * An Option with the Match, None if the group didn't match or doesn't exist
+    /// # Example
+    /// let captures = Captures::new(vec![Some(0), Some(2), Some(1), Some(2)]);
+    /// assert_eq!(captures.get(1), Some(Match { start: 1, end: 2 }));
+    pub fn get(&self, group: usize) -> Option<Match> {
+        match (self.slots.get(2 * group)?, self.slots.get(2 * group + 1)?) {
+            (Some(start), Some(end)) => Some(Match {
+                start: *start,
+                end: *end,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the Match of a named group ( ?P<name> )
+    /// # Arguments
+    /// * `name` - The name of the group
+    /// # Returns
+    /// * An Option with the Match, None if the group didn't match or there is no group with that name
+    /// # Example
+    /// let regex = Regex::new("(?P<key>[a-z]+)=").unwrap();
+    /// assert_eq!(regex.captures("ab=").unwrap().name("key"), Some(Match { start: 0, end: 2 }));
+    pub fn name(&self, name: &str) -> Option<Match> {
+        let group = self
+            .names
+            .iter()
+            .position(|other| other.as_deref() == Some(name))?;
+        self.get(group)
+    }
+
+    /// Returns the name of a group, None if the group has no name
+    pub fn group_name(&self, group: usize) -> Option<&str> {
+        self.names.get(group)?.as_deref()
+    }
+
+    /// Returns the names of the groups, by their number, empty when no group has a name
+    pub fn names(&self) -> &[Option<String>] {
+        &self.names
+    }
+
+    /// Returns the number of groups, counting the whole match
+    pub fn len(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    /// Returns whether there are no groups, not even the whole match
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Expands a replacement template with the text of the groups, adding it to a string
+    /// - `$n` and `${n}` are replaced by the group n, `$0` is the whole match and `$$` is a $
+    /// - `$name` and `${name}` are replaced by the group with that name
+    /// - The reference after $ is the longest run of letters, digits and _, so `${1}a` is needed to follow it with a letter
+    /// - A group that didn't match, or a reference that isn't a group, is replaced by nothing
+    /// - A $ that isn't followed by a reference is kept as it is
+    /// # Arguments
+    /// * `template` - The replacement template
+    /// * `word` - The word where the match was found
+    /// * `result` - The string where the expansion is added
+    /// # Example
+    /// let captures = Captures::new(vec![Some(0), Some(5), Some(0), Some(2)]);
+    /// let mut result = String::new();
+    /// captures.expand("[$1]${0}$$", "ab-cd", &mut result);
+    /// assert_eq!(result, "[ab]ab-cd$");
+    pub fn expand(&self, template: &str, word: &str, result: &mut String) {
+        for piece in self.pieces(template) {
+            match piece {
+                Piece::Text(text) => result.push_str(text),
+                Piece::Group(m) => result.push_str(m.as_str(word)),
+            }
+        }
+    }
+
+    /// Expands a replacement template like expand, for captures whose offsets are the ones of bytes
+    /// - The groups are copied as they are, even if they aren't valid UTF-8
+    /// # Arguments
+    /// * `template` - The replacement template
+    /// * `bytes` - The bytes where the match was found
+    /// * `result` - The bytes where the expansion is added
+    pub fn expand_bytes(&self, template: &str, bytes: &[u8], result: &mut Vec<u8>) {
+        for piece in self.pieces(template) {
+            match piece {
+                Piece::Text(text) => result.extend_from_slice(text.as_bytes()),
+                Piece::Group(m) => result.extend_from_slice(&bytes[m.start..m.end]),
+            }
+        }
+    }
+
+    /// Splits a replacement template into its literal parts and the groups it refers to, in order
+    fn pieces<'t>(&self, template: &'t str) -> Vec<Piece<'t>> {
+        let mut pieces = Vec::new();
+        let mut rest = template;
+        while let Some(dollar) = rest.find('$') {
+            pieces.push(Piece::Text(&rest[..dollar]));
+            rest = &rest[dollar + 1..];
+            if let Some(after) = rest.strip_prefix('$') {
+                pieces.push(Piece::Text("$"));
+                rest = after;
+                continue;
+            }
+            let (reference, after) = match rest.strip_prefix('{') {
+                Some(braced) => match braced.find('}') {
+                    Some(end) => (&braced[..end], &braced[end + 1..]),
+                    None => ("", rest),
+                },
+                None => {
+                    let end = rest
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len());
+                    (&rest[..end], &rest[end..])
+                }
+            };
+            if reference.is_empty() {
+                pieces.push(Piece::Text("$"));
+                continue;
+            }
+            if let Some(m) = self.reference(reference) {
+                pieces.push(Piece::Group(m));
+            }
+            rest = after;
+        }
+        pieces.push(Piece::Text(rest));
+        pieces
+    }
+
+    /// Returns the Match of a reference of a replacement template, a number or a name
+    fn reference(&self, reference: &str) -> Option<Match> {
+        match reference.parse() {
+            Ok(group) => self.get(group),
+            Err(_) => self.name(reference),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups() {
+        let captures = Captures::new(vec![Some(0), Some(3), Some(1), Some(2), None, None]);
+        assert_eq!(captures.len(), 3);
+        assert_eq!(captures.get(0), Some(Match { start: 0, end: 3 }));
+        assert_eq!(captures.get(1), Some(Match { start: 1, end: 2 }));
+        assert_eq!(captures.get(2), None);
+        assert_eq!(captures.get(3), None);
+    }
+
+    fn expand(template: &str) -> String {
+        let captures = Captures::new(vec![Some(0), Some(5), Some(0), Some(2), None, None]);
+        let mut result = String::new();
+        captures.expand(template, "ab-cd", &mut result);
+        result
+    }
+
+    #[test]
+    fn expand_references() {
+        assert_eq!(expand("[$1]"), "[ab]");
+        assert_eq!(expand("$0 ${1}x"), "ab-cd abx");
+        assert_eq!(expand("$1x"), "");
+        assert_eq!(expand("$2$9"), "");
+    }
+
+    #[test]
+    fn expand_dollars() {
+        assert_eq!(expand("$$1"), "$1");
+        assert_eq!(expand("a$"), "a$");
+        assert_eq!(expand("$ $-"), "$ $-");
+        assert_eq!(expand("${1"), "${1");
+    }
+
+    #[test]
+    fn names() {
+        let names = vec![
+            None,
+            Some("key".to_string()),
+            None,
+            Some("value".to_string()),
+        ];
+        let slots = vec![
+            Some(0),
+            Some(3),
+            Some(0),
+            Some(1),
+            None,
+            None,
+            Some(2),
+            Some(3),
+        ];
+        let captures = Captures::with_names(slots, names);
+        assert_eq!(captures.name("key"), Some(Match { start: 0, end: 1 }));
+        assert_eq!(captures.name("other"), None);
+        assert_eq!(captures.group_name(3), Some("value"));
+        assert_eq!(captures.group_name(2), None);
+        let mut result = String::new();
+        captures.expand("$value=${key}$other", "a=b", &mut result);
+        assert_eq!(result, "b=a");
+    }
+
+    #[test]
+    fn expand_bytes() {
+        let captures = Captures::new(vec![Some(0), Some(4), Some(1), Some(3)]);
+        let mut result = Vec::new();
+        captures.expand_bytes("<$1>", b"a\xff\xfeb", &mut result);
+        assert_eq!(result, b"<\xff\xfe>");
+    }
+}
@@ -0,0 +1,192 @@
+use crate::matching::{handle_regex_class, other_cases};
+use crate::metachars::RegexClass;
+use crate::regex_options::RegexOptions;
+
+/// Enum to represent an item of a bracket expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetItem {
+    /// Represents a single character, also the result of a collating symbol like [.hyphen.]
+    Char(char),
+    /// Represents a range of characters, like a-z
+    Range(char, char),
+    /// Represents a class of characters, like [:alpha:]
+    Class(RegexClass),
+    /// Represents an equivalence class, like [=a=], that matches a letter and its accented versions
+    Equivalence(char),
+}
+
+/// Struct to represent the set of characters of a bracket expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharSet {
+    /// Whether the set matches the characters that are not in the items ( [^...] )
+    pub negated: bool,
+    /// The items of the set
+    pub items: Vec<SetItem>,
+}
+
+/// Implementation of the CharSet struct
+impl CharSet {
+    /// Checks if the character is in the set
+    /// - If the options ignore the case, the other cases of the char are looked up before the negation
+    /// # Arguments
+    /// * `c` - A char
+    /// * `options` - A reference to a RegexOptions
+    /// # Returns
+    /// * A boolean
+    /// # Example
+    /// let set = CharSet { negated: false, items: vec![SetItem::Range('a', 'f'), SetItem::Char('_')] };
+    /// assert_eq!(set.contains('c', &RegexOptions::default()), true);
+    /// assert_eq!(set.contains('g', &RegexOptions::default()), false);
+    pub fn contains(&self, c: char, options: &RegexOptions) -> bool {
+        let found = self.has_item(c, options)
+            || (options.ignore_case
+                && other_cases(c)
+                    .into_iter()
+                    .any(|other| self.has_item(other, options)));
+        found != self.negated
+    }
+
+    /// Checks if the character is one of the items, without the negation
+    fn has_item(&self, c: char, options: &RegexOptions) -> bool {
+        self.items.iter().any(|item| match item {
+            SetItem::Char(l) => *l == c,
+            SetItem::Range(start, end) => *start <= c && c <= *end,
+            SetItem::Class(class) => handle_regex_class(class, c, options),
+            SetItem::Equivalence(l) => base_letter(*l) == base_letter(c),
+        })
+    }
+}
+
+/// Returns the letter without accents, so [=a=] matches á, à, â, ä, ã and å
+/// - Characters without accents are returned as they are
+/// # Arguments
+/// * `c` - A char
+/// # Returns
+/// * A char
+/// # Example
+/// assert_eq!(base_letter('ñ'), 'n');
+pub fn base_letter(c: char) -> char {
+    match c {
+        'à'..='å' => 'a',
+        'À'..='Å' => 'A',
+        'ç' => 'c',
+        'Ç' => 'C',
+        'è'..='ë' => 'e',
+        'È'..='Ë' => 'E',
+        'ì'..='ï' => 'i',
+        'Ì'..='Ï' => 'I',
+        'ñ' => 'n',
+        'Ñ' => 'N',
+        'ò'..='ö' | 'ø' => 'o',
+        'Ò'..='Ö' | 'Ø' => 'O',
+        'ù'..='ü' => 'u',
+        'Ù'..='Ü' => 'U',
+        'ý' | 'ÿ' => 'y',
+        'Ý' => 'Y',
+        _ => c,
+    }
+}
+
+/// Returns the character of a collating symbol, like [.hyphen.] or [.a.]
+/// - The names are the ones of the POSIX portable character set
+/// # Arguments
+/// * `name` - A string slice with the name between [. and .]
+/// # Returns
+/// * An Option with the char
+/// # Example
+/// assert_eq!(collating_symbol("hyphen"), Some('-'));
+pub fn collating_symbol(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+    Some(match name {
+        "NUL" => '\0',
+        "tab" => '\t',
+        "newline" => '\n',
+        "carriage-return" => '\r',
+        "space" => ' ',
+        "exclamation-mark" => '!',
+        "quotation-mark" => '"',
+        "number-sign" => '#',
+        "dollar-sign" => '$',
+        "percent-sign" => '%',
+        "ampersand" => '&',
+        "apostrophe" => '\'',
+        "left-parenthesis" => '(',
+        "right-parenthesis" => ')',
+        "asterisk" => '*',
+        "plus-sign" => '+',
+        "comma" => ',',
+        "hyphen" | "hyphen-minus" => '-',
+        "period" | "full-stop" => '.',
+        "slash" | "solidus" => '/',
+        "colon" => ':',
+        "semicolon" => ';',
+        "less-than-sign" => '<',
+        "equals-sign" => '=',
+        "greater-than-sign" => '>',
+        "question-mark" => '?',
+        "commercial-at" => '@',
+        "left-square-bracket" => '[',
+        "backslash" | "reverse-solidus" => '\\',
+        "right-square-bracket" => ']',
+        "circumflex" | "circumflex-accent" => '^',
+        "underscore" | "low-line" => '_',
+        "grave-accent" => '`',
+        "left-brace" | "left-curly-bracket" => '{',
+        "vertical-line" => '|',
+        "right-brace" | "right-curly-bracket" => '}',
+        "tilde" => '~',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains() {
+        let set = CharSet {
+            negated: false,
+            items: vec![
+                SetItem::Range('a', 'f'),
+                SetItem::Class(RegexClass::Digit),
+                SetItem::Char('_'),
+            ],
+        };
+        assert!(set.contains('c', &RegexOptions::default()));
+        assert!(set.contains('7', &RegexOptions::default()));
+        assert!(set.contains('_', &RegexOptions::default()));
+        assert!(!set.contains('g', &RegexOptions::default()));
+    }
+
+    #[test]
+    fn negated() {
+        let set = CharSet {
+            negated: true,
+            items: vec![SetItem::Range('b', 'y')],
+        };
+        assert!(set.contains('a', &RegexOptions::default()));
+        assert!(!set.contains('m', &RegexOptions::default()));
+    }
+
+    #[test]
+    fn equivalence() {
+        let set = CharSet {
+            negated: false,
+            items: vec![SetItem::Equivalence('a')],
+        };
+        assert!(set.contains('a', &RegexOptions::default()));
+        assert!(set.contains('á', &RegexOptions::default()));
+        assert!(!set.contains('e', &RegexOptions::default()));
+    }
+
+    #[test]
+    fn collating() {
+        assert_eq!(collating_symbol("hyphen"), Some('-'));
+        assert_eq!(collating_symbol("x"), Some('x'));
+        assert_eq!(collating_symbol("unknown"), None);
+    }
+}
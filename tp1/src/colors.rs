@@ -0,0 +1,192 @@
+/// Struct to represent the colors of the output, as SGR sequences like "01;31"
+/// - An empty sequence means that the part isn't colored
+/// - The defaults are the ones of GNU grep
+#[derive(Debug, Clone, PartialEq)]
+pub struct Colors {
+    /// The matched text in a selected line ( ms )
+    pub selected_match: String,
+    /// The matched text in a context line ( mc )
+    pub context_match: String,
+    /// The rest of a selected line ( sl )
+    pub selected_line: String,
+    /// The rest of a context line ( cx )
+    pub context_line: String,
+    /// The file names ( fn )
+    pub file_name: String,
+    /// The line numbers ( ln )
+    pub line_number: String,
+    /// The separators ':', '-' and "--" ( se )
+    pub separator: String,
+    /// Whether to leave out the "erase in line" sequence after each part ( ne )
+    pub no_erase: bool,
+    /// Whether sl and cx swap their meanings with -v ( rv )
+    pub reverse: bool,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Colors {
+            selected_match: "01;31".to_string(),
+            context_match: "01;31".to_string(),
+            selected_line: String::new(),
+            context_line: String::new(),
+            file_name: "35".to_string(),
+            line_number: "32".to_string(),
+            separator: "36".to_string(),
+            no_erase: false,
+            reverse: false,
+        }
+    }
+}
+
+/// Implementation of the Colors struct
+impl Colors {
+    /// Creates the colors from the value of GREP_COLORS, like "ms=01;32:fn=34:ne"
+    /// - The capabilities that aren't in the value keep their default
+    /// - mt sets both ms and mc, and the unknown capabilities are ignored
+    /// # Arguments
+    /// * `spec` - A string slice with the value of GREP_COLORS
+    /// # Returns
+    /// * The Colors
+    /// # Example
+    /// let colors = Colors::from_spec("ms=01;32:ne");
+    /// assert_eq!(colors.selected_match, "01;32");
+    /// assert_eq!(colors.no_erase, true);
+    pub fn from_spec(spec: &str) -> Self {
+        let mut colors = Colors::default();
+        for capability in spec.split(':') {
+            let (name, value) = match capability.split_once('=') {
+                Some((name, value)) => (name, value.to_string()),
+                None => (capability, String::new()),
+            };
+            match name {
+                "mt" => {
+                    colors.selected_match = value.clone();
+                    colors.context_match = value;
+                }
+                "ms" => colors.selected_match = value,
+                "mc" => colors.context_match = value,
+                "sl" => colors.selected_line = value,
+                "cx" => colors.context_line = value,
+                "fn" => colors.file_name = value,
+                "ln" => colors.line_number = value,
+                "se" => colors.separator = value,
+                "ne" => colors.no_erase = true,
+                "rv" => colors.reverse = true,
+                _ => {}
+            }
+        }
+        colors
+    }
+
+    /// Returns the colors used when the lines are selected with -v or without it
+    /// - With rv and -v, sl colors the context lines and cx the selected ones, like in GNU grep
+    /// - The colors of the matches don't change, ms is for the selected lines and mc for the context lines
+    /// # Arguments
+    /// * `invert` - Whether the lines are selected with -v
+    /// # Returns
+    /// * The Colors
+    /// # Example
+    /// let colors = Colors::from_spec("sl=1:cx=2:rv").with_invert(true);
+    /// assert_eq!(colors.selected_line, "2");
+    pub fn with_invert(mut self, invert: bool) -> Self {
+        if invert && self.reverse {
+            std::mem::swap(&mut self.selected_line, &mut self.context_line);
+        }
+        self
+    }
+
+    /// Returns the text inside the escape sequences of a color
+    /// # Arguments
+    /// * `sgr` - A string slice with the SGR sequence of the color
+    /// * `text` - A string slice with the text
+    /// # Returns
+    /// * A String
+    /// # Example
+    /// let colors = Colors::default();
+    /// assert_eq!(colors.paint("32", "7"), "\x1b[32m\x1b[K7\x1b[m\x1b[K");
+    pub fn paint(&self, sgr: &str, text: &str) -> String {
+        if sgr.is_empty() || text.is_empty() {
+            return text.to_string();
+        }
+        let erase = if self.no_erase { "" } else { "\x1b[K" };
+        format!("\x1b[{}m{}{}\x1b[m{}", sgr, erase, text, erase)
+    }
+
+    /// Adds bytes inside the escape sequences of a color to the output, for the lines that aren't valid UTF-8 ( -a )
+    /// # Arguments
+    /// * `sgr` - A string slice with the SGR sequence of the color
+    /// * `text` - A slice with the bytes
+    /// * `output` - The bytes of the output
+    /// # Example
+    /// let mut output = Vec::new();
+    /// Colors::default().paint_bytes("32", b"\xff", &mut output);
+    /// assert_eq!(output, b"\x1b[32m\x1b[K\xff\x1b[m\x1b[K");
+    pub fn paint_bytes(&self, sgr: &str, text: &[u8], output: &mut Vec<u8>) {
+        if sgr.is_empty() || text.is_empty() {
+            output.extend_from_slice(text);
+            return;
+        }
+        let erase = if self.no_erase { "" } else { "\x1b[K" };
+        output.extend_from_slice(format!("\x1b[{}m{}", sgr, erase).as_bytes());
+        output.extend_from_slice(text);
+        output.extend_from_slice(format!("\x1b[m{}", erase).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let colors = Colors::from_spec("");
+        assert_eq!(colors, Colors::default());
+    }
+
+    #[test]
+    fn spec() {
+        let colors = Colors::from_spec("mt=01;32:fn=:sl=1:xx=3:ne");
+        assert_eq!(colors.selected_match, "01;32");
+        assert_eq!(colors.context_match, "01;32");
+        assert_eq!(colors.file_name, "");
+        assert_eq!(colors.selected_line, "1");
+        assert_eq!(colors.line_number, "32");
+        assert!(colors.no_erase);
+    }
+
+    #[test]
+    fn reverse() {
+        let colors = Colors::from_spec("sl=1:cx=2:rv");
+        assert!(colors.reverse);
+        assert_eq!(colors.clone().with_invert(false).selected_line, "1");
+        let inverted = colors.with_invert(true);
+        assert_eq!(
+            (inverted.selected_line, inverted.context_line),
+            ("2".to_string(), "1".to_string())
+        );
+        let colors = Colors::from_spec("sl=1:cx=2").with_invert(true);
+        assert_eq!(colors.selected_line, "1");
+    }
+
+    #[test]
+    fn paint() {
+        let colors = Colors::default();
+        assert_eq!(
+            colors.paint("35", "a.txt"),
+            "\x1b[35m\x1b[Ka.txt\x1b[m\x1b[K"
+        );
+        assert_eq!(colors.paint("", "a.txt"), "a.txt");
+        let colors = Colors::from_spec("ne");
+        assert_eq!(colors.paint("35", "a.txt"), "\x1b[35ma.txt\x1b[m");
+    }
+
+    #[test]
+    fn paint_bytes() {
+        let colors = Colors::default();
+        let mut output = Vec::new();
+        colors.paint_bytes("35", b"a\xff", &mut output);
+        colors.paint_bytes("", b"\xfe", &mut output);
+        assert_eq!(output, b"\x1b[35m\x1b[Ka\xff\x1b[m\x1b[K\xfe");
+    }
+}
@@ -0,0 +1,590 @@
+use crate::matching::{find_match, handle_regex_value};
+use crate::nfa::{Inst, Program};
+use crate::regex_error::RegexError;
+use crate::simulation::{handle_word_restriction, is_word_char};
+use crate::type_of_line::RegexRestriction;
+use std::collections::HashMap;
+
+/// The maximum number of states kept in the cache, when it is full the cache is cleared
+pub const MAX_STATES: usize = 4096;
+
+/// Enum to represent what a state knows about the char before it, for the assertions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Prev {
+    /// There is no char before, it is the start of the word
+    Start,
+    /// The char before is part of a word
+    Word,
+    /// The char before isn't part of a word
+    Other,
+}
+
+/// Struct to represent what the closure knows about a position of the word
+struct Look {
+    /// The char before the position
+    prev: Prev,
+    /// Whether the char after the position is part of a word, None while it isn't known
+    next: Option<bool>,
+    /// Whether the position is the end of the word
+    at_end: bool,
+}
+
+/// Struct to represent a set of instructions, that is cleared without visiting every instruction
+/// - It is made once for the size of the program and reused by every closure
+struct SparseSet {
+    /// The instructions in the set, in insertion order
+    dense: Vec<usize>,
+    /// The index in dense of each instruction, only valid if dense has it there
+    sparse: Vec<usize>,
+}
+
+impl SparseSet {
+    fn new(size: usize) -> Self {
+        SparseSet {
+            dense: Vec::with_capacity(size),
+            sparse: vec![0; size],
+        }
+    }
+
+    /// Adds an instruction, and returns whether it was new
+    fn insert(&mut self, pc: usize) -> bool {
+        let index = self.sparse[pc];
+        if index < self.dense.len() && self.dense[index] == pc {
+            return false;
+        }
+        self.sparse[pc] = self.dense.len();
+        self.dense.push(pc);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.dense.clear();
+    }
+}
+
+/// Struct to represent the threads that start a new match at a position, after a char
+/// - They are the same at every position after a char of the same kind, so they are computed once
+struct Start {
+    /// The closure of the first instruction, without the Match instructions, sorted
+    insts: Vec<usize>,
+    /// The Match instructions of the closure, the regexes that match the empty string there
+    matches: Vec<usize>,
+}
+
+/// Struct to represent a transition of the DFA
+struct Step {
+    /// The id of the next state
+    next: usize,
+    /// The Match instructions reached with the char, sorted
+    matches: Vec<usize>,
+}
+
+/// Struct to represent a state of the DFA
+/// - It is the set of NFA instructions that can be active at the same time
+/// - The word assertions depend on the next char, so they are kept pending like $
+/// - The threads of the matches that start at the position aren't in the set, they are the same for
+///   every state and are added when the next char is read, so they don't make the states bigger
+/// - The Match instructions aren't in the set either, they are in the transitions that reach them,
+///   so the matches found before don't make more states
+struct State {
+    /// The instructions that consume a char and the pending assertions, sorted
+    insts: Vec<usize>,
+    /// The char before the state
+    prev: Prev,
+    /// The transition for each ascii char, once it was computed
+    ascii: Vec<Option<Step>>,
+    /// The transition for the other chars, once it was computed
+    others: HashMap<char, Step>,
+}
+
+/// Struct to represent a DFA that is built lazily from a Program
+/// - A state is only built the first time a line reaches it, and the transitions are cached,
+///   so after a few lines every char is a lookup in a table
+/// - The cache is bounded, so a regex with many states can't use all the memory
+/// - It only says whether a line matches, the positions of the match are found with the NFA
+pub struct Dfa {
+    /// The compiled regex
+    program: Program,
+    /// The states built so far
+    states: Vec<State>,
+    /// The id of each state by its instructions and the char before it
+    ids: HashMap<(Vec<usize>, Prev), usize>,
+    /// The id of the state at the start of the line, if it is in the cache
+    start: Option<usize>,
+    /// The Match instructions of the state at the start of the line
+    start_matches: Vec<usize>,
+    /// The threads that start a new match after a word char and after another char
+    starts: HashMap<Prev, Start>,
+    /// The instructions that the new matches reach with a char and the Match instructions before it,
+    /// by the char before it
+    start_steps: HashMap<(Prev, char), (Vec<usize>, Vec<usize>)>,
+    /// The set used by the closures
+    seen: SparseSet,
+}
+
+/// Implementation of the Dfa struct
+impl Dfa {
+    /// Creates a DFA for a Program, without any state yet
+    /// # Arguments
+    /// * `program` - A Program
+    /// # Returns
+    /// * A Dfa
+    /// # Example
+    /// let mut dfa = Dfa::new(Program::new(&Regex::new("ab+").unwrap()));
+    /// assert_eq!(dfa.is_match("xabb"), Ok(true));
+    pub fn new(program: Program) -> Self {
+        let seen = SparseSet::new(program.insts.len());
+        Dfa {
+            program,
+            states: Vec::new(),
+            ids: HashMap::new(),
+            start: None,
+            start_matches: Vec::new(),
+            starts: HashMap::new(),
+            start_steps: HashMap::new(),
+            seen,
+        }
+    }
+
+    /// Returns the Program of the DFA
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Returns the number of states in the cache
+    pub fn cached_states(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Checks if the regex matches anywhere in the word
+    /// - A word without the required literal of the regex is rejected before running the DFA
+    /// - A regex with backreferences or atomic parts can't be a DFA, so it is matched with backtracking
+    /// - The Perl option doesn't change which words match, only where, so it doesn't matter here
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with a boolean, or a BacktrackLimit error
+    /// # Example
+    /// let mut dfa = Dfa::new(Program::new(&Regex::new("^ab$").unwrap()));
+    /// assert_eq!(dfa.is_match("ab"), Ok(true));
+    /// assert_eq!(dfa.is_match("abc"), Ok(false));
+    pub fn is_match(&mut self, word: &str) -> Result<bool, RegexError> {
+        if !self.program.may_match(word) {
+            return Ok(false);
+        }
+        if self.program.has_backrefs || self.program.has_atomic {
+            return Ok(find_match(&self.program, word)?.is_some());
+        }
+        let mut id = self.start_state();
+        if !self.start_matches.is_empty() {
+            return Ok(true);
+        }
+        for c in word.chars() {
+            if self.states[id].insts.is_empty() && self.program.start_of_line {
+                return Ok(false);
+            }
+            let (next, matched) = self.next_state(id, c);
+            if matched.is_some() {
+                return Ok(true);
+            }
+            id = next;
+        }
+        Ok(!self.matches_at_end(id).is_empty())
+    }
+
+    /// Returns the Match instructions that the NFA reaches anywhere in the word, sorted
+    /// - A program with several Match instructions, like the one of a RegexSet, tells which of them matched
+    ///   in a single scan: the Match instructions of every transition are added up
+    /// - A regex with backreferences or atomic parts has a single Match, found with backtracking
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with a vector with the positions of the Match instructions in the program,
+    ///   or a BacktrackLimit error
+    pub fn matches(&mut self, word: &str) -> Result<Vec<usize>, RegexError> {
+        if !self.program.may_match(word) {
+            return Ok(vec![]);
+        }
+        if self.program.has_backrefs || self.program.has_atomic {
+            return match find_match(&self.program, word)? {
+                Some(_) => Ok(vec![self.program.insts.len() - 1]),
+                None => Ok(vec![]),
+            };
+        }
+        let mut id = self.start_state();
+        let mut matched = self.start_matches.clone();
+        for c in word.chars() {
+            if self.states[id].insts.is_empty() && self.program.start_of_line {
+                break;
+            }
+            let (next, found) = self.next_state(id, c);
+            matched.extend(found.unwrap_or_default());
+            id = next;
+        }
+        if !(self.states[id].insts.is_empty() && self.program.start_of_line) {
+            matched.extend(self.matches_at_end(id));
+        }
+        matched.sort_unstable();
+        matched.dedup();
+        Ok(matched)
+    }
+
+    /// Returns the id of the state at the start of the line
+    fn start_state(&mut self) -> usize {
+        if let Some(id) = self.start {
+            return id;
+        }
+        let look = Look {
+            prev: Prev::Start,
+            next: None,
+            at_end: false,
+        };
+        let reached = self.closure(vec![0], &look);
+        let (insts, matches) = self.split_matches(reached);
+        let id = self.add_state(insts, Prev::Start);
+        self.start = Some(id);
+        self.start_matches = matches;
+        id
+    }
+
+    /// Returns the threads that start a new match after a char, computing them the first time
+    fn start_after(&mut self, prev: Prev) -> &Start {
+        if !self.starts.contains_key(&prev) {
+            let look = Look {
+                prev,
+                next: None,
+                at_end: false,
+            };
+            let reached = self.closure(vec![0], &look);
+            let (insts, matches) = self.split_matches(reached);
+            self.starts.insert(prev, Start { insts, matches });
+        }
+        &self.starts[&prev]
+    }
+
+    /// Returns the instructions that the new matches reach with a char after another one, computing them the first time
+    fn start_step(&mut self, prev: Prev, c: char) -> (Vec<usize>, Vec<usize>) {
+        if let Some(step) = self.start_steps.get(&(prev, c)) {
+            return step.clone();
+        }
+        let insts = self.start_after(prev).insts.clone();
+        let step = self.step(insts, prev, c);
+        self.start_steps.insert((prev, c), step.clone());
+        step
+    }
+
+    /// Returns the next state after consuming a char, building it if it isn't in the cache,
+    /// and the Match instructions reached with the char, None if there are none
+    fn next_state(&mut self, id: usize, c: char) -> (usize, Option<Vec<usize>>) {
+        let cached = match c.is_ascii() {
+            true => self.states[id].ascii[c as usize].as_ref(),
+            false => self.states[id].others.get(&c),
+        };
+        if let Some(step) = cached {
+            let matches = (!step.matches.is_empty()).then(|| step.matches.clone());
+            return (step.next, matches);
+        }
+
+        let prev = self.states[id].prev;
+        let (mut targets, mut matches) = self.step(self.states[id].insts.clone(), prev, c);
+        // A new match can start at every position, its threads are added here,
+        // the state at the start of the line already has them
+        if prev != Prev::Start {
+            let (start_targets, start_matches) = self.start_step(prev, c);
+            targets.extend(start_targets);
+            matches.extend(start_matches);
+        }
+        let after = if is_word_char(c, &self.program.options) {
+            Prev::Word
+        } else {
+            Prev::Other
+        };
+        let look = Look {
+            prev: after,
+            next: None,
+            at_end: false,
+        };
+        let reached = self.closure(targets, &look);
+        let (insts, reached_matches) = self.split_matches(reached);
+        matches.extend(reached_matches);
+        let start = self.start_after(after);
+        matches.extend_from_slice(&start.matches);
+        let insts: Vec<usize> = insts
+            .into_iter()
+            .filter(|pc| start.insts.binary_search(pc).is_err())
+            .collect();
+        matches.sort_unstable();
+        matches.dedup();
+
+        let key = (insts, after);
+        let cache_full = !self.ids.contains_key(&key) && self.states.len() >= MAX_STATES;
+        let next = self.add_state(key.0, key.1);
+        let found = (!matches.is_empty()).then(|| matches.clone());
+        if !cache_full {
+            let step = Step { next, matches };
+            match c.is_ascii() {
+                true => self.states[id].ascii[c as usize] = Some(step),
+                false => {
+                    self.states[id].others.insert(c, step);
+                }
+            }
+        }
+        (next, found)
+    }
+
+    /// Returns the instructions after the ones that consume the char, and the Match instructions reached
+    /// before it, now that the next char is known and the pending word assertions can be resolved
+    fn step(&mut self, insts: Vec<usize>, prev: Prev, c: char) -> (Vec<usize>, Vec<usize>) {
+        let look = Look {
+            prev,
+            next: Some(is_word_char(c, &self.program.options)),
+            at_end: false,
+        };
+        let mut targets = Vec::new();
+        let mut matches = Vec::new();
+        for pc in self.closure(insts, &look) {
+            match &self.program.insts[pc] {
+                Inst::Char(val) if handle_regex_value(val, c, &self.program.options) => {
+                    targets.push(pc + 1)
+                }
+                Inst::Match => matches.push(pc),
+                _ => {}
+            }
+        }
+        (targets, matches)
+    }
+
+    /// Splits the Match instructions out of a set of instructions
+    fn split_matches(&self, insts: Vec<usize>) -> (Vec<usize>, Vec<usize>) {
+        insts
+            .into_iter()
+            .partition(|pc| self.program.insts[*pc] != Inst::Match)
+    }
+
+    /// Adds a state to the cache and returns its id
+    /// - If the cache is full, it is cleared first, and the lines go on from the new state
+    fn add_state(&mut self, insts: Vec<usize>, prev: Prev) -> usize {
+        let key = (insts, prev);
+        if let Some(id) = self.ids.get(&key) {
+            return *id;
+        }
+        let (insts, prev) = key;
+        if self.states.len() >= MAX_STATES {
+            self.states.clear();
+            self.ids.clear();
+            self.start = None;
+        }
+        let id = self.states.len();
+        self.ids.insert((insts.clone(), prev), id);
+        self.states.push(State {
+            insts,
+            prev,
+            ascii: (0..128).map(|_| None).collect(),
+            others: HashMap::new(),
+        });
+        id
+    }
+
+    /// Returns the Match instructions that the state reaches at the end of the word, where the pending $ hold
+    fn matches_at_end(&mut self, id: usize) -> Vec<usize> {
+        let prev = self.states[id].prev;
+        let mut insts = self.states[id].insts.clone();
+        if prev != Prev::Start {
+            insts.extend_from_slice(&self.start_after(prev).insts);
+        }
+        let look = Look {
+            prev,
+            next: Some(false),
+            at_end: true,
+        };
+        let reached = self.closure(insts, &look);
+        let (_, matches) = self.split_matches(reached);
+        matches
+    }
+
+    /// Follows the instructions that don't consume chars, and returns the sorted set of the ones reached
+    /// - ^ only holds at the start of the word, and $ is kept pending until the end is known
+    /// - The word assertions are kept pending until the next char is known
+    fn closure(&mut self, pcs: Vec<usize>, look: &Look) -> Vec<usize> {
+        self.seen.clear();
+        let mut insts = Vec::new();
+        let mut stack = pcs;
+        while let Some(pc) = stack.pop() {
+            if !self.seen.insert(pc) {
+                continue;
+            }
+            match &self.program.insts[pc] {
+                Inst::Jump(next) => stack.push(*next),
+                Inst::Split(first, second) => {
+                    stack.push(*second);
+                    stack.push(*first);
+                }
+                Inst::Save(_) | Inst::Atomic | Inst::Commit => stack.push(pc + 1),
+                Inst::Assert(RegexRestriction::StartOfLine) => {
+                    if look.prev == Prev::Start {
+                        stack.push(pc + 1);
+                    }
+                }
+                Inst::Assert(RegexRestriction::EndOfLine) => {
+                    if look.at_end {
+                        stack.push(pc + 1);
+                    } else {
+                        insts.push(pc);
+                    }
+                }
+                Inst::Assert(RegexRestriction::None) => stack.push(pc + 1),
+                Inst::Assert(restriction) => match look.next {
+                    Some(after) => {
+                        if handle_word_restriction(restriction, look.prev == Prev::Word, after) {
+                            stack.push(pc + 1);
+                        }
+                    }
+                    None => insts.push(pc),
+                },
+                Inst::Char(_) | Inst::Backref(_) | Inst::Match => insts.push(pc),
+            }
+        }
+        insts.sort_unstable();
+        insts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Syntax;
+    use crate::regex::Regex;
+
+    fn build(expression: &str) -> Dfa {
+        Dfa::new(Program::new(&Regex::new(expression).unwrap()))
+    }
+
+    #[test]
+    fn unanchored() {
+        let mut dfa = build("ab+c");
+        assert!(dfa.is_match("xxabbbc").unwrap());
+        assert!(dfa.is_match("abc").unwrap());
+        assert!(!dfa.is_match("ac").unwrap());
+        assert!(!dfa.is_match("").unwrap());
+    }
+
+    #[test]
+    fn anchors() {
+        let mut dfa = build("^a*$");
+        assert!(dfa.is_match("").unwrap());
+        assert!(dfa.is_match("aaa").unwrap());
+        assert!(!dfa.is_match("aab").unwrap());
+        let mut dfa = build("b$");
+        assert!(dfa.is_match("ab").unwrap());
+        assert!(!dfa.is_match("ba").unwrap());
+    }
+
+    #[test]
+    fn anchors_in_groups() {
+        let mut dfa = build("(^a|b$)");
+        assert!(dfa.is_match("ax").unwrap());
+        assert!(dfa.is_match("xb").unwrap());
+        assert!(!dfa.is_match("xa").unwrap());
+    }
+
+    #[test]
+    fn empty_match() {
+        let mut dfa = build("x*");
+        assert!(dfa.is_match("").unwrap());
+        assert!(dfa.is_match("abc").unwrap());
+    }
+
+    #[test]
+    fn unicode() {
+        let mut dfa = build("ñ[[:alpha:]]+ú");
+        assert!(dfa.is_match("el ñandú").unwrap());
+        assert!(!dfa.is_match("el nandu").unwrap());
+    }
+
+    #[test]
+    fn word_assertions() {
+        let mut dfa = build("\\bid\\b");
+        assert!(dfa.is_match("x = id").unwrap());
+        assert!(dfa.is_match("id(x)").unwrap());
+        assert!(!dfa.is_match("idx = ids").unwrap());
+        let mut dfa = build("\\<a+\\>$");
+        assert!(dfa.is_match("b aa").unwrap());
+        assert!(!dfa.is_match("baa").unwrap());
+        let mut dfa = build("\\B");
+        assert!(dfa.is_match("").unwrap());
+        assert!(!dfa.is_match("a").unwrap());
+    }
+
+    #[test]
+    fn backreferences() {
+        let mut dfa = build("(a|b)\\1");
+        assert!(dfa.is_match("xbb").unwrap());
+        assert!(!dfa.is_match("abab").unwrap());
+    }
+
+    #[test]
+    fn possessive() {
+        let syntax = Syntax {
+            perl: true,
+            ..Default::default()
+        };
+        let mut dfa = Dfa::new(Program::new(&Regex::with_syntax("a*+a", &syntax).unwrap()));
+        assert!(!dfa.is_match("aaa").unwrap());
+        let mut dfa = Dfa::new(Program::new(&Regex::with_syntax("a*?b", &syntax).unwrap()));
+        assert!(dfa.is_match("aab").unwrap());
+    }
+
+    #[test]
+    fn cache_is_reused() {
+        let mut dfa = build("[a-c]+d");
+        assert!(dfa.is_match("abcd").unwrap());
+        let states = dfa.cached_states();
+        assert!(dfa.is_match("cbad").unwrap());
+        assert_eq!(dfa.cached_states(), states);
+    }
+
+    #[test]
+    fn cache_is_bounded() {
+        // Every position of the last 12 chars is a different state
+        let mut dfa = build("a.{12}$");
+        for i in 0..5000u32 {
+            let word: String = (0..16)
+                .map(|b| if (i >> (b % 13)) & 1 == 1 { 'a' } else { 'b' })
+                .collect();
+            let expected = word.chars().rev().nth(12) == Some('a');
+            assert_eq!(dfa.is_match(&word).unwrap(), expected);
+            assert!(dfa.cached_states() <= MAX_STATES);
+        }
+    }
+
+    #[test]
+    fn same_as_nfa() {
+        let expressions = [
+            "a|ab",
+            "(ab)*c",
+            "[^a]+$",
+            "^(a|b){2,3}",
+            "x?y?z?",
+            "\\ba",
+            "a\\B",
+            "(\\<|c)b",
+            "^\\>",
+        ];
+        let words = [
+            "", "a", "ab", "abab c", "bba", "xyz", "zzz", "ca", " b", "cb",
+        ];
+        for expression in expressions {
+            let program = Program::new(&Regex::new(expression).unwrap());
+            let mut dfa = Dfa::new(program.clone());
+            for word in words {
+                assert_eq!(
+                    dfa.is_match(word).unwrap(),
+                    find_match(&program, word).unwrap().is_some(),
+                    "{} on {:?}",
+                    expression,
+                    word
+                );
+            }
+        }
+    }
+}
@@ -1,25 +1,70 @@
+use crate::char_set::{CharSet, SetItem};
+use crate::metachars::RegexClass;
+use crate::regex_error::RegexError;
 use crate::regex_rep::RegexRep;
 use crate::regex_step::RegexStep;
 use crate::regex_val::RegexValue;
+use crate::type_of_line::handle_word_assertion;
 use std::str::Chars;
 
+/// Function to handle a shorthand class, like \\w
+/// - The lowercase letter is the class and the uppercase one its complement
+/// # Arguments
+/// * `c` - The char after the \\
+/// # Returns
+/// * An Option with the RegexValue, or None if the char isn't a shorthand class
+/// # Example
+/// let result = handle_shorthand_class('d');
+/// assert_eq!(result, Some(RegexValue::Class(RegexClass::Digit)));
+pub fn handle_shorthand_class(c: char) -> Option<RegexValue> {
+    let class = match c.to_ascii_lowercase() {
+        'w' => RegexClass::Word,
+        's' => RegexClass::Space,
+        'd' => RegexClass::Digit,
+        _ => return None,
+    };
+    if c.is_ascii_lowercase() {
+        return Some(RegexValue::Class(class));
+    }
+    Some(RegexValue::Set(CharSet {
+        negated: true,
+        items: vec![SetItem::Class(class)],
+    }))
+}
+
 /// Function to handle the escape sequence
 /// - It receives a mutable reference to Chars and returns a Result with a RegexStep or an error
+/// - \\w, \\W, \\s, \\S, \\d and \\D are classes, any other char is a literal
+/// - \\b, \\B, \\< and \\> are word assertions
+/// - \\1 to \\9 are backreferences, and the group must be opened before them
 /// # Arguments
 /// * `chars_iter` - A mutable reference to Chars
+/// * `groups` - The number of groups opened before the \\
+/// * `position` - The position of the \\ in the expression
 /// # Returns
 /// * A Result with a RegexStep or an error
 /// # Example
-/// let mut chars = "\\a".chars();
-/// let result = handle_escape_sequence(&mut chars);
+/// let mut chars = "a".chars();
+/// let result = handle_escape_sequence(&mut chars, 0, 0);
 /// assert_eq!(result, Ok(Some(RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') })));
-pub fn handle_escape_sequence(chars_iter: &mut Chars) -> Result<Option<RegexStep>, &'static str> {
+pub fn handle_escape_sequence(
+    chars_iter: &mut Chars,
+    groups: usize,
+    position: usize,
+) -> Result<Option<RegexStep>, RegexError> {
     let c = chars_iter
         .next()
-        .ok_or("Se esperaba un caracter después de \\")?;
+        .ok_or(RegexError::MissingEscapedChar { position })?;
+    let val = match c.to_digit(10) {
+        Some(0) | None => handle_shorthand_class(c)
+            .or_else(|| handle_word_assertion(c))
+            .unwrap_or(RegexValue::Literal(c)),
+        Some(group) if group as usize <= groups => RegexValue::Backreference(group as usize),
+        Some(_) => return Err(RegexError::InvalidBackreference { position }),
+    };
     Ok(Some(RegexStep {
         rep: RegexRep::Exact(1),
-        val: RegexValue::Literal(c),
+        val,
     }))
 }
 
@@ -0,0 +1,127 @@
+/// Checks if a name matches a shell glob, like the ones of --include and --exclude
+/// - `*` matches any sequence of characters, `?` matches a single character
+/// - `[...]` matches one of the characters or ranges, and `[!...]` or `[^...]` the other ones
+/// - `\` makes the next character literal
+/// # Arguments
+/// * `pattern` - A string slice with the glob
+/// * `name` - A string slice with the name to check
+/// # Returns
+/// * A boolean
+/// # Example
+/// assert_eq!(matches_glob("*.rs", "main.rs"), true);
+/// assert_eq!(matches_glob("*.rs", "main.c"), false);
+pub fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where to go back when the last * has to match one more character
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, n));
+            p += 1;
+            continue;
+        }
+        if let Some(next) = match_single(&pattern, p, name[n]) {
+            p = next;
+            n += 1;
+            continue;
+        }
+        match star {
+            Some((star_p, star_n)) => {
+                p = star_p;
+                n = star_n + 1;
+                star = Some((star_p, star_n + 1));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Matches a single character with the element of the glob at the position
+/// - It returns the position of the next element if it matches
+fn match_single(pattern: &[char], p: usize, c: char) -> Option<usize> {
+    match pattern.get(p)? {
+        '?' => Some(p + 1),
+        '[' => match match_brackets(pattern, p + 1, c) {
+            Some((true, next)) => Some(next),
+            Some((false, _)) => None,
+            // A [ without its ] is a literal
+            None if c == '[' => Some(p + 1),
+            None => None,
+        },
+        '\\' if p + 1 < pattern.len() => (pattern[p + 1] == c).then_some(p + 2),
+        l => (*l == c).then_some(p + 1),
+    }
+}
+
+/// Matches a character with the brackets of a glob, starting after the [
+/// - It returns whether the character matches and the position after the ], or None if there is no ]
+fn match_brackets(pattern: &[char], start: usize, c: char) -> Option<(bool, usize)> {
+    let mut i = start;
+    let negated = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+    let mut found = false;
+    let mut first = true;
+    loop {
+        let current = *pattern.get(i)?;
+        if current == ']' && !first {
+            return Some((found != negated, i + 1));
+        }
+        first = false;
+        match (pattern.get(i + 1), pattern.get(i + 2)) {
+            (Some('-'), Some(end)) if *end != ']' => {
+                found |= current <= c && c <= *end;
+                i += 3;
+            }
+            _ => {
+                found |= current == c;
+                i += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal() {
+        assert!(matches_glob("main.rs", "main.rs"));
+        assert!(!matches_glob("main.rs", "main.rc"));
+    }
+
+    #[test]
+    fn star() {
+        assert!(matches_glob("*.rs", "main.rs"));
+        assert!(matches_glob("*", ""));
+        assert!(matches_glob("a*b*c", "aXbYbZc"));
+        assert!(!matches_glob("*.rs", "main.rs.bak"));
+    }
+
+    #[test]
+    fn question_mark() {
+        assert!(matches_glob("?.txt", "a.txt"));
+        assert!(!matches_glob("?.txt", "ab.txt"));
+    }
+
+    #[test]
+    fn brackets() {
+        assert!(matches_glob("[abc].txt", "b.txt"));
+        assert!(matches_glob("file[0-9]", "file7"));
+        assert!(!matches_glob("file[!0-9]", "file7"));
+        assert!(matches_glob("[]]", "]"));
+        assert!(matches_glob("a[b", "a[b"));
+    }
+
+    #[test]
+    fn escape() {
+        assert!(matches_glob("\\*.txt", "*.txt"));
+        assert!(!matches_glob("\\*.txt", "a.txt"));
+    }
+}
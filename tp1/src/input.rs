@@ -0,0 +1,454 @@
+use crate::glob::matches_glob;
+use crate::options::Options;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// The name shown for the standard input
+pub const STDIN_NAME: &str = "(standard input)";
+
+/// Returns the message of an io error without the "(os error N)" part
+/// # Arguments
+/// * `error` - A reference to an io::Error
+/// # Returns
+/// * A String
+/// # Example
+/// let error = io::Error::from(io::ErrorKind::NotFound);
+/// assert_eq!(error_message(&error), "entity not found");
+pub fn error_message(error: &io::Error) -> String {
+    let message = error.to_string();
+    match message.find(" (os error") {
+        Some(end) => message[..end].to_string(),
+        None => message,
+    }
+}
+
+/// Struct to read the lines of a file one at a time
+/// - Only the current line is kept in memory, so files of any size can be searched
+/// - The line endings "\n" and "\r\n" are removed, and the last line is kept even without its "\n"
+/// - A file is binary if it has a NUL byte or a line that isn't valid UTF-8, like in GNU grep
+pub struct LineReader<R: BufRead> {
+    /// The buffered reader of the file
+    reader: R,
+    /// The bytes of the current line
+    buffer: Vec<u8>,
+    /// Whether a NUL byte or invalid UTF-8 was found
+    binary: bool,
+    /// The byte offset of the start of the current line in the file
+    offset: usize,
+    /// The number of bytes read, with the line endings
+    read: usize,
+    /// The length of the line ending of the current line, 0, 1 for "\n" or 2 for "\r\n"
+    ending: usize,
+}
+
+/// Implementation of the LineReader struct
+impl<R: BufRead> LineReader<R> {
+    /// Creates a LineReader
+    /// - The first block of the reader is checked for NUL bytes, so a binary file is known before its first line
+    /// # Arguments
+    /// * `reader` - A reader that implements BufRead
+    /// # Returns
+    /// * A Result with the LineReader or the io error
+    /// # Example
+    /// let reader = LineReader::new("a\r\nb".as_bytes()).unwrap();
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let binary = reader.fill_buf()?.contains(&0);
+        Ok(LineReader {
+            reader,
+            buffer: Vec::new(),
+            binary,
+            offset: 0,
+            read: 0,
+            ending: 0,
+        })
+    }
+
+    /// Returns whether the file is binary, as far as it was read
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    /// Returns the byte offset of the start of the current line in the file
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the line ending that was removed from the current line, empty for a last line without it
+    pub fn line_ending(&self) -> &'static [u8] {
+        &b"\r\n"[2 - self.ending..]
+    }
+
+    /// Reads the next line, without its line ending
+    /// - The bytes that aren't valid UTF-8 are replaced with U+FFFD
+    /// # Returns
+    /// * A Result with the line, None at the end of the file, or the io error
+    /// # Example
+    /// let mut reader = LineReader::new("a\r\nb".as_bytes()).unwrap();
+    /// assert_eq!(reader.next_line().unwrap(), Some("a".to_string()));
+    /// assert_eq!(reader.next_line().unwrap(), Some("b".to_string()));
+    /// assert_eq!(reader.next_line().unwrap(), None);
+    pub fn next_line(&mut self) -> io::Result<Option<String>> {
+        let line = self.next_bytes()?;
+        Ok(line.map(|line| String::from_utf8_lossy(&line).to_string()))
+    }
+
+    /// Reads the bytes of the next line as they are, without its line ending
+    /// # Returns
+    /// * A Result with the bytes of the line, None at the end of the file, or the io error
+    /// # Example
+    /// let mut reader = LineReader::new(&b"a\xff\nb"[..]).unwrap();
+    /// assert_eq!(reader.next_bytes().unwrap(), Some(b"a\xff".to_vec()));
+    /// assert_eq!(reader.is_binary(), true);
+    pub fn next_bytes(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.buffer.clear();
+        self.offset = self.read;
+        self.ending = 0;
+        let read = self.reader.read_until(b'\n', &mut self.buffer)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.read += read;
+        if self.buffer.ends_with(b"\n") {
+            self.buffer.pop();
+            self.ending = 1;
+            if self.buffer.ends_with(b"\r") {
+                self.buffer.pop();
+                self.ending = 2;
+            }
+        }
+        if self.buffer.contains(&0) || std::str::from_utf8(&self.buffer).is_err() {
+            self.binary = true;
+        }
+        Ok(Some(self.buffer.clone()))
+    }
+}
+
+/// Opens a file, or the standard input if the path is -, to read its lines
+/// # Arguments
+/// * `path` - A string slice with the path
+/// # Returns
+/// * A Result with the LineReader or the io error
+pub fn open_lines(path: &str) -> io::Result<LineReader<Box<dyn BufRead>>> {
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(BufReader::new(fs::File::open(path)?))
+    };
+    LineReader::new(reader)
+}
+
+/// Create a list of strings from a file, or from the standard input if the path is -
+/// # Arguments
+/// * `path` - A string slice with the path
+/// # Returns
+/// * A Result with the lines of the file or the io error
+pub fn create_list_from_file(path: &str) -> io::Result<Vec<String>> {
+    let mut reader = open_lines(path)?;
+    let mut list: Vec<String> = Vec::new();
+    while let Some(line) = reader.next_line()? {
+        list.push(line);
+    }
+    Ok(list)
+}
+
+/// Checks if the name of a file is selected by --include and --exclude
+fn is_included(path: &str, options: &Options) -> bool {
+    let name = match Path::new(path).file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => return true,
+    };
+    if !options.include.is_empty() && !options.include.iter().any(|g| matches_glob(g, &name)) {
+        return false;
+    }
+    !options.exclude.iter().any(|g| matches_glob(g, &name))
+}
+
+/// Expands the paths of the options into the files to search
+/// - With -r or -R the directories are searched recursively, in the order of the names of their entries
+/// - -r skips the symbolic links found inside the directories, -R follows them
+/// - When -r was given without paths, the files of the current directory are named without ./, like in GNU grep
+/// - The paths that can't be searched are returned as errors with their message, so the other ones are still searched
+/// # Arguments
+/// * `options` - A reference to the Options
+/// # Returns
+/// * A vector with the path of each file or the message of the error
+/// # Example
+/// let options = parse_options(&["-r".to_string(), "a".to_string(), "src".to_string()]).unwrap();
+/// let files = expand_paths(&options);
+/// assert!(files.contains(&Ok("src/main.rs".to_string())));
+pub fn expand_paths(options: &Options) -> Vec<Result<String, String>> {
+    let recursive = options.recursive || options.dereference_recursive;
+    let mut files = Vec::new();
+    let mut ancestors = Vec::new();
+    for path in options.paths.iter() {
+        if path == "-" {
+            files.push(Ok(path.to_string()));
+        } else if recursive && Path::new(path).is_dir() {
+            let walked = files.len();
+            walk_directory(path, options, &mut ancestors, &mut files);
+            if options.current_directory {
+                for Ok(name) | Err(name) in files[walked..].iter_mut() {
+                    if let Some(short) = name.strip_prefix("./") {
+                        *name = short.to_string();
+                    }
+                }
+            }
+        } else if is_included(path, options) {
+            files.push(Ok(path.to_string()));
+        }
+    }
+    files
+}
+
+/// Adds the files of a directory and of its subdirectories
+/// - A directory that is one of its own ancestors is skipped, so the links that make a loop end, like in GNU grep
+/// - A directory reached by several paths that don't make a loop is searched by each of them
+fn walk_directory(
+    directory: &str,
+    options: &Options,
+    ancestors: &mut Vec<PathBuf>,
+    files: &mut Vec<Result<String, String>>,
+) {
+    let canonical = fs::canonicalize(directory).ok();
+    if let Some(canonical) = &canonical {
+        if ancestors.contains(canonical) {
+            files.push(Err(format!(
+                "{}: warning: recursive directory loop",
+                directory
+            )));
+            return;
+        }
+        ancestors.push(canonical.clone());
+    }
+    list_directory(directory, options, ancestors, files);
+    if canonical.is_some() {
+        ancestors.pop();
+    }
+}
+
+/// Adds the files of the entries of a directory, in the order of their names
+fn list_directory(
+    directory: &str,
+    options: &Options,
+    ancestors: &mut Vec<PathBuf>,
+    files: &mut Vec<Result<String, String>>,
+) {
+    let mut entries: Vec<fs::DirEntry> = match fs::read_dir(directory) {
+        Ok(entries) => entries.filter_map(Result::ok).collect(),
+        Err(e) => {
+            files.push(Err(format!("{}: {}", directory, error_message(&e))));
+            return;
+        }
+    };
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = if directory.ends_with('/') {
+            format!("{}{}", directory, entry.file_name().to_string_lossy())
+        } else {
+            format!("{}/{}", directory, entry.file_name().to_string_lossy())
+        };
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_symlink() && !options.dereference_recursive {
+            continue;
+        }
+        if Path::new(&path).is_dir() {
+            walk_directory(&path, options, ancestors, files);
+        } else if is_included(&path, options) {
+            files.push(Ok(path));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::parse_options;
+
+    fn options(args: &[&str]) -> Options {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse_options(&args).unwrap()
+    }
+
+    fn temp_tree(name: &str) -> String {
+        let root = std::env::temp_dir().join(format!("tp1_input_{}", name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), "hola\n").unwrap();
+        fs::write(root.join("b.rs"), "chau\n").unwrap();
+        fs::write(root.join("sub").join("c.txt"), "hola\nmundo\n").unwrap();
+        root.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn lines() {
+        let root = temp_tree("lines");
+        let lines = create_list_from_file(&format!("{}/sub/c.txt", root)).unwrap();
+        assert_eq!(lines, vec!["hola", "mundo"]);
+        assert!(create_list_from_file(&format!("{}/missing", root)).is_err());
+    }
+
+    fn read_all(content: &[u8]) -> (Vec<String>, bool) {
+        let mut reader = LineReader::new(content).unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_line().unwrap() {
+            lines.push(line);
+        }
+        (lines, reader.is_binary())
+    }
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(read_all(b"a\nb\n"), (vec!["a".into(), "b".into()], false));
+        assert_eq!(
+            read_all(b"a\r\nb\r\n"),
+            (vec!["a".into(), "b".into()], false)
+        );
+        assert_eq!(
+            read_all(b"a\n\nb"),
+            (vec!["a".into(), "".into(), "b".into()], false)
+        );
+        assert_eq!(read_all(b""), (vec![], false));
+    }
+
+    #[test]
+    fn binary() {
+        assert!(read_all(b"a\0b\n").1);
+        assert_eq!(read_all(b"a\xffb\n"), (vec!["a\u{fffd}b".into()], true));
+        assert!(!read_all("ñandú\n".as_bytes()).1);
+    }
+
+    #[test]
+    fn raw_bytes() {
+        let mut reader = LineReader::new(&b"a\xe9\r\n\xff"[..]).unwrap();
+        assert_eq!(reader.next_bytes().unwrap(), Some(b"a\xe9".to_vec()));
+        assert!(reader.is_binary());
+        assert_eq!(reader.next_bytes().unwrap(), Some(b"\xff".to_vec()));
+        assert_eq!(reader.next_bytes().unwrap(), None);
+    }
+
+    #[test]
+    fn offsets_and_endings() {
+        let mut reader = LineReader::new(&b"ab\r\n\ncd"[..]).unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_bytes().unwrap() {
+            lines.push((line, reader.offset(), reader.line_ending()));
+        }
+        assert_eq!(
+            lines,
+            vec![
+                (b"ab".to_vec(), 0, &b"\r\n"[..]),
+                (b"".to_vec(), 4, &b"\n"[..]),
+                (b"cd".to_vec(), 5, &b""[..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_after_first_block() {
+        let mut content = vec![b'a'; 20000];
+        content.extend(b"\n\0\n");
+        let mut reader = LineReader::new(BufReader::with_capacity(16, &content[..])).unwrap();
+        assert!(!reader.is_binary());
+        reader.next_line().unwrap();
+        reader.next_line().unwrap();
+        assert!(reader.is_binary());
+    }
+
+    #[test]
+    fn not_recursive() {
+        let root = temp_tree("not_recursive");
+        let files = expand_paths(&options(&["a", &root, "-"]));
+        assert_eq!(files, vec![Ok(root.clone()), Ok("-".to_string())]);
+    }
+
+    #[test]
+    fn recursive() {
+        let root = temp_tree("recursive");
+        let files = expand_paths(&options(&["-r", "a", &root]));
+        assert_eq!(
+            files,
+            vec![
+                Ok(format!("{}/a.txt", root)),
+                Ok(format!("{}/b.rs", root)),
+                Ok(format!("{}/sub/c.txt", root)),
+            ]
+        );
+    }
+
+    #[test]
+    fn include_and_exclude() {
+        let root = temp_tree("globs");
+        let files = expand_paths(&options(&["-r", "--include=*.txt", "a", &root]));
+        assert_eq!(
+            files,
+            vec![
+                Ok(format!("{}/a.txt", root)),
+                Ok(format!("{}/sub/c.txt", root)),
+            ]
+        );
+        let files = expand_paths(&options(&["-r", "--exclude=a*", "a", &root]));
+        assert_eq!(
+            files,
+            vec![
+                Ok(format!("{}/b.rs", root)),
+                Ok(format!("{}/sub/c.txt", root)),
+            ]
+        );
+    }
+
+    #[test]
+    fn same_directory_twice() {
+        let root = temp_tree("twice");
+        let sub = format!("{}/sub", root);
+        let files = expand_paths(&options(&["-r", "a", &sub, &sub]));
+        let file = Ok(format!("{}/c.txt", sub));
+        assert_eq!(files, vec![file.clone(), file]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_to_the_same_directory() {
+        let root = temp_tree("links");
+        std::os::unix::fs::symlink(root.clone() + "/sub", root.clone() + "/x").unwrap();
+        std::os::unix::fs::symlink(root.clone() + "/sub", root.clone() + "/y").unwrap();
+        let files = expand_paths(&options(&["-R", "--include=*.txt", "a", &root]));
+        assert_eq!(
+            files,
+            vec![
+                Ok(format!("{}/a.txt", root)),
+                Ok(format!("{}/sub/c.txt", root)),
+                Ok(format!("{}/x/c.txt", root)),
+                Ok(format!("{}/y/c.txt", root)),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn directory_loop() {
+        let root = temp_tree("loop");
+        std::os::unix::fs::symlink(&root, root.clone() + "/sub/up").unwrap();
+        let files = expand_paths(&options(&["-R", "--include=*.txt", "a", &root]));
+        assert_eq!(
+            files,
+            vec![
+                Ok(format!("{}/a.txt", root)),
+                Ok(format!("{}/sub/c.txt", root)),
+                Err(format!(
+                    "{}/sub/up: warning: recursive directory loop",
+                    root
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_directory() {
+        let files = expand_paths(&options(&["-r", "a", "/nonexistent/tp1"]));
+        assert_eq!(files, vec![Ok("/nonexistent/tp1".to_string())]);
+    }
+}
@@ -0,0 +1,290 @@
+use crate::captures::Captures;
+use std::time::Duration;
+
+/// Struct to represent the statistics of a search, like the ones of ripgrep's --json
+/// - Each file has its own, and the summary adds the ones of every file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// The time the search took
+    pub elapsed: Duration,
+    /// The number of files searched
+    pub searches: usize,
+    /// The number of files with selected lines
+    pub searches_with_match: usize,
+    /// The number of bytes read
+    pub bytes_searched: usize,
+    /// The number of bytes printed, with the line endings of the events
+    pub bytes_printed: usize,
+    /// The number of selected lines
+    pub matched_lines: usize,
+    /// The number of matches in the selected lines
+    pub matches: usize,
+}
+
+/// Implementation of the Stats struct
+impl Stats {
+    /// Adds the statistics of a file to the ones of the whole search
+    /// # Arguments
+    /// * `other` - A reference to the Stats of the file
+    pub fn add(&mut self, other: &Stats) {
+        self.elapsed += other.elapsed;
+        self.searches += other.searches;
+        self.searches_with_match += other.searches_with_match;
+        self.bytes_searched += other.bytes_searched;
+        self.bytes_printed += other.bytes_printed;
+        self.matched_lines += other.matched_lines;
+        self.matches += other.matches;
+    }
+
+    /// Returns the statistics as a JSON object
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"elapsed\":{},\"searches\":{},\"searches_with_match\":{},\"bytes_searched\":{},\"bytes_printed\":{},\"matched_lines\":{},\"matches\":{}}}",
+            duration(self.elapsed),
+            self.searches,
+            self.searches_with_match,
+            self.bytes_searched,
+            self.bytes_printed,
+            self.matched_lines,
+            self.matches
+        )
+    }
+}
+
+/// Returns the "begin" event, printed before the lines of a file
+/// # Arguments
+/// * `path` - The name of the file
+/// # Returns
+/// * A String with the JSON object, without a line ending
+/// # Example
+/// assert_eq!(begin("a.txt"), "{\"type\":\"begin\",\"data\":{\"path\":{\"text\":\"a.txt\"}}}");
+pub fn begin(path: &str) -> String {
+    format!(
+        "{{\"type\":\"begin\",\"data\":{{\"path\":{}}}}}",
+        data(path.as_bytes())
+    )
+}
+
+/// Returns the "match" event of a selected line, or the "context" event of a context line
+/// - The line has its line ending, and its offsets are the ones of its bytes
+/// - Each submatch has the whole match and every group of the regex, by number and by name, with null for the groups that didn't match
+/// - Text that isn't valid UTF-8 is given as {"bytes": base64} instead of {"text": ...}, like in ripgrep
+/// # Arguments
+/// * `kind` - "match" or "context"
+/// * `path` - The name of the file
+/// * `line` - The bytes of the line, with its line ending
+/// * `line_number` - The number of the line, from 1
+/// * `absolute_offset` - The byte offset of the start of the line in the file
+/// * `submatches` - The Captures of each match in the line
+/// # Returns
+/// * A String with the JSON object, without a line ending
+/// # Example
+/// let captures = Captures::new(vec![Some(1), Some(2)]);
+/// let event = line_event("match", "a.txt", b"ab\n", 3, 10, &[captures]);
+/// assert!(event.ends_with("\"line_number\":3,\"absolute_offset\":10,\"submatches\":[{\"match\":{\"text\":\"b\"},\"start\":1,\"end\":2,\"groups\":[]}]}}"));
+pub fn line_event(
+    kind: &str,
+    path: &str,
+    line: &[u8],
+    line_number: usize,
+    absolute_offset: usize,
+    submatches: &[Captures],
+) -> String {
+    let submatches: Vec<String> = submatches
+        .iter()
+        .filter_map(|captures| submatch(captures, line))
+        .collect();
+    format!(
+        "{{\"type\":\"{}\",\"data\":{{\"path\":{},\"lines\":{},\"line_number\":{},\"absolute_offset\":{},\"submatches\":[{}]}}}}",
+        kind,
+        data(path.as_bytes()),
+        data(line),
+        line_number,
+        absolute_offset,
+        submatches.join(",")
+    )
+}
+
+/// Returns the "end" event, printed after the lines of a file
+/// # Arguments
+/// * `path` - The name of the file
+/// * `binary_offset` - The byte offset where the file was found to be binary, if it was
+/// * `stats` - A reference to the Stats of the file
+/// # Returns
+/// * A String with the JSON object, without a line ending
+pub fn end(path: &str, binary_offset: Option<usize>, stats: &Stats) -> String {
+    let binary_offset = match binary_offset {
+        Some(offset) => offset.to_string(),
+        None => "null".to_string(),
+    };
+    format!(
+        "{{\"type\":\"end\",\"data\":{{\"path\":{},\"binary_offset\":{},\"stats\":{}}}}}",
+        data(path.as_bytes()),
+        binary_offset,
+        stats.to_json()
+    )
+}
+
+/// Returns the "summary" event, printed after every file
+/// # Arguments
+/// * `elapsed_total` - The time the whole program took
+/// * `stats` - A reference to the Stats of every file added together
+/// # Returns
+/// * A String with the JSON object, without a line ending
+pub fn summary(elapsed_total: Duration, stats: &Stats) -> String {
+    format!(
+        "{{\"type\":\"summary\",\"data\":{{\"elapsed_total\":{},\"stats\":{}}}}}",
+        duration(elapsed_total),
+        stats.to_json()
+    )
+}
+
+/// Returns the JSON object of a match and its groups, None if it has no whole match
+fn submatch(captures: &Captures, line: &[u8]) -> Option<String> {
+    let m = captures.get(0)?;
+    let groups: Vec<String> = (1..captures.len())
+        .map(|group| {
+            let name = match captures.group_name(group) {
+                Some(name) => string(name),
+                None => "null".to_string(),
+            };
+            let (text, start, end) = match captures.get(group) {
+                Some(g) => (
+                    data(&line[g.start..g.end]),
+                    g.start.to_string(),
+                    g.end.to_string(),
+                ),
+                None => ("null".to_string(), "null".to_string(), "null".to_string()),
+            };
+            format!(
+                "{{\"group\":{},\"name\":{},\"match\":{},\"start\":{},\"end\":{}}}",
+                group, name, text, start, end
+            )
+        })
+        .collect();
+    Some(format!(
+        "{{\"match\":{},\"start\":{},\"end\":{},\"groups\":[{}]}}",
+        data(&line[m.start..m.end]),
+        m.start,
+        m.end,
+        groups.join(",")
+    ))
+}
+
+/// Returns the JSON object of some bytes, {"text": ...} if they are valid UTF-8 and {"bytes": base64} if not
+fn data(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => format!("{{\"text\":{}}}", string(text)),
+        Err(_) => format!("{{\"bytes\":\"{}\"}}", base64(bytes)),
+    }
+}
+
+/// Returns the JSON object of a Duration, with its seconds, its nanoseconds and a text for people
+fn duration(duration: Duration) -> String {
+    format!(
+        "{{\"secs\":{},\"nanos\":{},\"human\":\"{:.6}s\"}}",
+        duration.as_secs(),
+        duration.subsec_nanos(),
+        duration.as_secs_f64()
+    )
+}
+
+/// Returns a JSON string, with its quotes and its escaped characters
+/// # Example
+/// assert_eq!(string("a\"b\n"), "\"a\\\"b\\n\"");
+fn string(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+    result.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Returns the standard base64 of some bytes, with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut result = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |value, (i, b)| value | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => result.push(ALPHABET[(value >> (18 - 6 * i) & 63) as usize] as char),
+                false => result.push('='),
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings() {
+        assert_eq!(string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(string("\t\n\u{1}ñ"), "\"\\t\\n\\u0001ñ\"");
+        assert_eq!(data(b"ab"), "{\"text\":\"ab\"}");
+        assert_eq!(data(b"a\xff"), "{\"bytes\":\"Yf8=\"}");
+    }
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+    }
+
+    #[test]
+    fn match_event() {
+        let names = vec![None, Some("key".to_string()), None];
+        let captures =
+            Captures::with_names(vec![Some(0), Some(3), Some(0), Some(1), None, None], names);
+        let event = line_event("match", "a.txt", b"a=1\n", 2, 8, &[captures]);
+        assert_eq!(
+            event,
+            "{\"type\":\"match\",\"data\":{\"path\":{\"text\":\"a.txt\"},\"lines\":{\"text\":\"a=1\\n\"},\
+             \"line_number\":2,\"absolute_offset\":8,\"submatches\":[{\"match\":{\"text\":\"a=1\"},\"start\":0,\"end\":3,\
+             \"groups\":[{\"group\":1,\"name\":\"key\",\"match\":{\"text\":\"a\"},\"start\":0,\"end\":1},\
+             {\"group\":2,\"name\":null,\"match\":null,\"start\":null,\"end\":null}]}]}}"
+        );
+    }
+
+    #[test]
+    fn end_and_summary() {
+        let stats = Stats {
+            elapsed: Duration::new(1, 5),
+            searches: 1,
+            matched_lines: 2,
+            ..Default::default()
+        };
+        let mut total = Stats::default();
+        total.add(&stats);
+        total.add(&stats);
+        assert_eq!(total.searches, 2);
+        assert_eq!(total.matched_lines, 4);
+        assert!(end("a.txt", None, &stats).starts_with(
+            "{\"type\":\"end\",\"data\":{\"path\":{\"text\":\"a.txt\"},\"binary_offset\":null,\"stats\":{\"elapsed\":{\"secs\":1,\"nanos\":5,"
+        ));
+        let summary = summary(Duration::ZERO, &total);
+        assert!(
+            summary.starts_with("{\"type\":\"summary\",\"data\":{\"elapsed_total\":{\"secs\":0,")
+        );
+        assert!(summary.contains("\"searches\":2,"));
+    }
+}
@@ -1,11 +1,77 @@
-pub mod brackets;
-pub mod exactrep;
-pub mod matching;
-pub mod metachars;
-pub mod range;
-pub mod regex;
-pub mod regex_rep;
-pub mod regex_step;
-pub mod regex_val;
-pub mod regular_expressions;
-pub mod type_of_line;
+pub(crate) mod api;
+pub(crate) mod backtrack;
+pub(crate) mod brackets;
+pub mod bytes;
+pub(crate) mod captures;
+pub(crate) mod char_set;
+pub(crate) mod colors;
+pub(crate) mod dfa;
+pub(crate) mod exactrep;
+pub(crate) mod glob;
+pub(crate) mod input;
+pub(crate) mod json;
+pub(crate) mod matching;
+pub(crate) mod metachars;
+pub(crate) mod nfa;
+pub(crate) mod options;
+pub(crate) mod parser;
+pub(crate) mod posix;
+pub(crate) mod prefilter;
+pub(crate) mod range;
+pub(crate) mod regex;
+pub(crate) mod regex_error;
+pub(crate) mod regex_match;
+pub(crate) mod regex_options;
+pub(crate) mod regex_rep;
+pub(crate) mod regex_set;
+pub(crate) mod regex_step;
+pub(crate) mod regex_val;
+pub(crate) mod regular_expressions;
+pub(crate) mod replace;
+pub(crate) mod simulation;
+pub(crate) mod type_of_line;
+
+pub use api::Regex;
+pub use captures::Captures;
+pub use regex_error::RegexError;
+pub use regex_match::Match;
+pub use regex_options::RegexOptions;
+pub use regex_set::RegexSet;
+
+/// The modules that the binary and the tests use, they aren't part of the API of the library and can change
+#[doc(hidden)]
+pub mod internal {
+    pub mod colors {
+        pub use crate::colors::*;
+    }
+    pub mod dfa {
+        pub use crate::dfa::*;
+    }
+    pub mod input {
+        pub use crate::input::*;
+    }
+    pub mod json {
+        pub use crate::json::*;
+    }
+    pub mod matching {
+        pub use crate::matching::*;
+    }
+    pub mod nfa {
+        pub use crate::nfa::*;
+    }
+    pub mod options {
+        pub use crate::options::*;
+    }
+    pub mod parser {
+        pub use crate::parser::*;
+    }
+    pub mod regex {
+        pub use crate::regex::*;
+    }
+    pub mod regular_expressions {
+        pub use crate::regular_expressions::*;
+    }
+    pub mod replace {
+        pub use crate::replace::*;
+    }
+}
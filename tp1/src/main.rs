@@ -1,97 +1,585 @@
-use matching::compare_regexes_with_expression;
-use regular_expressions::create_regular_expressions;
+use regular_expressions::create_regular_expressions_with;
+use std::collections::VecDeque;
 use std::env;
-use tp1::{
-    matching::{self},
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
+use tp1::bytes::Decoded;
+use tp1::internal::{
+    colors::Colors,
+    input::{create_list_from_file, error_message, expand_paths, open_lines, STDIN_NAME},
+    json::{self, Stats},
+    matching::find_iter,
+    nfa::Program,
+    options::{parse_options, ColorChoice, Options, USAGE},
+    parser::Syntax,
+    regex::Regex,
     regular_expressions,
+    replace::{byte_captures, replace_line, rewrite_file},
 };
+use tp1::{Match, RegexSet};
 
-/// Read a file and return its content as a string
-fn read_file(path: &str) -> String {
-    match std::fs::read_to_string(path) {
-        Ok(f) => f,
-        Err(e) => panic!("Error reading file: {}", e),
+/// Compile the expressions of the options, and the ones of the files given with -f
+/// - An empty expression matches every line, like in GNU grep
+/// - With -P the alternatives of an expression stay in one regex, so the first one that matches wins
+fn compile_patterns(options: &Options) -> Result<Vec<Program>, String> {
+    let mut patterns = options.patterns.clone();
+    for path in options.pattern_files.iter() {
+        match create_list_from_file(path) {
+            Ok(lines) => patterns.extend(lines),
+            Err(e) => return Err(format!("{}: {}", path, error_message(&e))),
+        }
     }
-}
 
-/// Create a list of strings from a file
-fn create_list_from_file(path: &str) -> Vec<String> {
-    let file = read_file(path);
-    let mut list: Vec<String> = Vec::new();
-    let mut word = String::new();
-    for c in file.chars() {
-        if c == '\n' {
-            list.push(word);
-            word = String::new();
-        } else {
-            word.push(c);
+    let syntax = Syntax {
+        perl: options.regex_options.perl,
+        ..Default::default()
+    };
+    let mut programs = Vec::new();
+    for pattern in patterns.iter() {
+        let regexes = match syntax.perl {
+            true if !pattern.is_empty() => Regex::with_syntax(pattern, &syntax).map(|r| vec![r]),
+            _ => create_regular_expressions_with(pattern, &syntax),
+        };
+        let regexes = match regexes {
+            Ok(regexes) => regexes,
+            Err(_) if pattern.is_empty() => vec![Regex {
+                steps: vec![],
+                backtracking: None,
+            }],
+            Err(e) => return Err(e.show(pattern)),
+        };
+        for mut regex in regexes {
+            if options.word_regexp {
+                regex = regex.whole_word();
+            }
+            if options.line_regexp {
+                regex = regex.whole_line();
+            }
+            programs.push(Program::with_options(&regex, options.regex_options.clone()));
         }
     }
-    list
+    Ok(programs)
+}
+
+/// Function that picks one of the colors
+type ColorOf = fn(&Colors) -> &String;
+
+/// Struct to print the output, with colors if they are enabled
+/// - Everything is written through one locked handle of the standard output, and every write returns its error,
+///   so the search stops when the output is closed, like with `| head`
+struct Printer {
+    /// The locked standard output
+    out: io::StdoutLock<'static>,
+    /// The colors, or None if the output isn't colored
+    colors: Option<Colors>,
+    /// Whether a group of lines was printed before, in this file or in another one
+    printed_group: bool,
 }
 
-// Define a new type for the complex type
-type ResultVec =
-    Result<Vec<Result<String, Box<dyn std::error::Error>>>, Box<dyn std::error::Error>>;
+impl Printer {
+    /// Colors a part of the output, if the colors are enabled
+    fn paint(&self, color: ColorOf, text: &str) -> String {
+        match &self.colors {
+            Some(colors) => colors.paint(color(colors), text),
+            None => text.to_string(),
+        }
+    }
+
+    /// Print the name of a file, for -l and -L
+    fn file_name(&mut self, name: &str) -> io::Result<()> {
+        let name = self.paint(|c| &c.file_name, name);
+        writeln!(self.out, "{}", name)
+    }
+
+    /// Print the "--" between groups of lines
+    fn group_separator(&mut self) -> io::Result<()> {
+        let separator = self.paint(|c| &c.separator, "--");
+        writeln!(self.out, "{}", separator)
+    }
+
+    /// Print that a binary file has a selected line, instead of the line
+    fn binary_file(&mut self, name: &str) -> io::Result<()> {
+        writeln!(self.out, "Binary file {} matches", name)
+    }
+
+    /// Print a JSON event as a line, counting its bytes in the statistics of the file
+    fn event(&mut self, event: &str, stats: &mut Stats) -> io::Result<()> {
+        writeln!(self.out, "{}", event)?;
+        stats.bytes_printed += event.len() + 1;
+        Ok(())
+    }
+
+    /// Colors a part of a line, that may not be valid UTF-8, adding it to the output
+    fn paint_bytes(&self, color: ColorOf, text: &[u8], output: &mut Vec<u8>) {
+        match &self.colors {
+            Some(colors) => colors.paint_bytes(color(colors), text, output),
+            None => output.extend_from_slice(text),
+        }
+    }
 
-/// Process the expressions and paths
-fn process_expressions_and_paths(expression: &str, path: &str) -> ResultVec {
-    let regexes = create_regular_expressions(expression)?;
-    let list = create_list_from_file(path);
+    /// Print a line with the name of the file and its number, if the options say so
+    /// - The separator is ':' for the selected lines and '-' for the context lines, like in GNU grep
+    /// - The matches are colored with ms in the selected lines and with mc in the context lines, and the rest of
+    ///   the line too if GREP_COLORS says so
+    /// - The line is printed with its bytes as they are, and the matches are byte offsets of them
+    fn line(
+        &mut self,
+        name: Option<&str>,
+        number: Option<usize>,
+        selected: bool,
+        text: &[u8],
+        matches: &[Match],
+    ) -> io::Result<()> {
+        let separator = self.paint(|c| &c.separator, if selected { ":" } else { "-" });
+        let mut output = String::new();
+        if let Some(name) = name {
+            output.push_str(&self.paint(|c| &c.file_name, name));
+            output.push_str(&separator);
+        }
+        if let Some(number) = number {
+            output.push_str(&self.paint(|c| &c.line_number, &number.to_string()));
+            output.push_str(&separator);
+        }
 
-    let mut result = Vec::new();
-    for s in list.iter() {
-        let word = compare_regexes_with_expression(&regexes, s.to_string()).map_err(|e| e.into());
-        // Use word.is_ok() instead of !word.is_err()
-        if word.is_ok() {
-            result.push(word);
+        let (line_color, match_color): (ColorOf, ColorOf) = if selected {
+            (|c| &c.selected_line, |c| &c.selected_match)
+        } else {
+            (|c| &c.context_line, |c| &c.context_match)
+        };
+        let mut output = output.into_bytes();
+        let mut last = 0;
+        for m in matches.iter().filter(|m| !m.is_empty()) {
+            self.paint_bytes(line_color, &text[last..m.start], &mut output);
+            self.paint_bytes(match_color, &text[m.start..m.end], &mut output);
+            last = m.end;
         }
+        self.paint_bytes(line_color, &text[last..], &mut output);
+        output.push(b'\n');
+        self.out.write_all(&output)
     }
-    Ok(result)
 }
 
-/// Print the results
-fn print_results(results: &[Result<String, Box<dyn std::error::Error>>]) {
-    for s in results.iter().flatten() {
-        println!("{}", s.trim());
+/// Find the matches of a line to color them, with the offsets of its bytes
+/// - Nothing is searched when the output isn't colored
+/// - The context lines and the lines of -v are searched too, their matches are colored with mc like in GNU grep
+/// # Arguments
+/// * `programs` - The compiled expressions
+/// * `bytes` - The bytes of the line
+/// * `printer` - A reference to the Printer
+fn colored_matches(
+    programs: &[Program],
+    bytes: &[u8],
+    printer: &Printer,
+) -> io::Result<Vec<Match>> {
+    if printer.colors.is_none() {
+        return Ok(vec![]);
     }
+    let decoded = Decoded::new(bytes);
+    let matches = find_iter(programs, decoded.as_str()).map_err(io::Error::other)?;
+    Ok(matches.into_iter().map(|m| decoded.byte_match(m)).collect())
 }
 
-/// Parse the arguments
-fn parse_args() -> Result<(String, String), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        return Err("Expected at least two arguments".into());
+/// Search a file and print its selected lines as the options say
+/// - The file is read one line at a time, and the last lines are kept in a ring buffer for -B
+/// - Groups of lines that aren't next to each other are separated by "--" when there is context
+/// - In a binary file, the first selected line prints "Binary file X matches" instead, and the search stops, unless -a is given
+/// - Each line is decoded like in bytes::Decoded, so its bytes are printed as they are
+/// - With --replace the selected lines are printed with their matches replaced, and the replacements are colored
+/// - It returns whether the file has selected lines, also for -L, like GNU grep 3.5 and later
+/// # Arguments
+/// * `programs` - The compiled expressions, to find the matches for -o and --color
+/// * `set` - The expressions joined into a RegexSet, to check if each line matches in a single scan
+/// * `path` - The path of the file, - is the standard input
+/// * `options` - A reference to the Options
+/// * `with_filename` - Whether to print the name of the file before each line
+/// * `printer` - A mutable reference to the Printer
+fn search_file(
+    programs: &[Program],
+    set: &mut RegexSet,
+    path: &str,
+    options: &Options,
+    with_filename: bool,
+    printer: &mut Printer,
+) -> io::Result<bool> {
+    let mut reader = open_lines(path)?;
+    let name = if path == "-" { STDIN_NAME } else { path };
+    let prefix_name = with_filename.then_some(name);
+    let line_number = |number: usize| options.line_number.then_some(number);
+    let prints_lines = !(options.quiet
+        || options.count
+        || options.files_with_matches
+        || options.files_without_match);
+
+    let (lines_after, lines_before) = if options.only_matching {
+        (0, 0)
+    } else {
+        (options.lines_after(), options.lines_before())
+    };
+    let has_context = options.has_context();
+
+    let mut count = 0;
+    let mut number = 0;
+    let mut before: VecDeque<(usize, Vec<u8>)> = VecDeque::with_capacity(lines_before);
+    let mut after_left = 0;
+    let mut last_printed: Option<usize> = None;
+    while let Some(bytes) = reader.next_bytes()? {
+        number += 1;
+        if options.max_count.is_some_and(|max| count >= max) {
+            // Only the trailing context of the last selected line is still printed
+            if !prints_lines || after_left == 0 {
+                break;
+            }
+            let matches = colored_matches(programs, &bytes, printer)?;
+            printer.line(prefix_name, line_number(number), false, &bytes, &matches)?;
+            after_left -= 1;
+            continue;
+        }
+        let decoded = Decoded::new(&bytes);
+        let line = decoded.as_str();
+        let found = set.is_match(line).map_err(io::Error::other)?;
+        if found == options.invert {
+            if !prints_lines {
+                continue;
+            }
+            if after_left > 0 {
+                let matches = colored_matches(programs, &bytes, printer)?;
+                printer.line(prefix_name, line_number(number), false, &bytes, &matches)?;
+                after_left -= 1;
+                last_printed = Some(number);
+            } else if lines_before > 0 {
+                if before.len() == lines_before {
+                    before.pop_front();
+                }
+                before.push_back((number, bytes));
+            }
+            continue;
+        }
+        count += 1;
+        if options.quiet {
+            break;
+        }
+        if !prints_lines {
+            continue;
+        }
+        if reader.is_binary() && !options.text {
+            printer.binary_file(name)?;
+            break;
+        }
+
+        let first = before.front().map_or(number, |(n, _)| *n);
+        if has_context && printer.printed_group && last_printed.is_none_or(|last| first > last + 1)
+        {
+            printer.group_separator()?;
+        }
+        for (n, text) in before.drain(..) {
+            let matches = colored_matches(programs, &text, printer)?;
+            printer.line(prefix_name, line_number(n), false, &text, &matches)?;
+        }
+        if let (Some(template), false) = (&options.replace, options.invert) {
+            let all = byte_captures(programs, &bytes).map_err(io::Error::other)?;
+            if options.only_matching {
+                for captures in all
+                    .iter()
+                    .filter(|c| c.get(0).is_some_and(|m| !m.is_empty()))
+                {
+                    let mut text = Vec::new();
+                    captures.expand_bytes(template, &bytes, &mut text);
+                    let whole = [Match {
+                        start: 0,
+                        end: text.len(),
+                    }];
+                    printer.line(prefix_name, line_number(number), true, &text, &whole)?;
+                }
+            } else {
+                let (replaced, matches) = replace_line(&bytes, template, &all);
+                printer.line(prefix_name, line_number(number), true, &replaced, &matches)?;
+            }
+        } else if options.only_matching {
+            if !options.invert {
+                let matches = find_iter(programs, line).map_err(io::Error::other)?;
+                for m in matches.iter().filter(|m| !m.is_empty()) {
+                    let m = decoded.byte_match(*m);
+                    let text = &bytes[m.start..m.end];
+                    let whole = [Match {
+                        start: 0,
+                        end: text.len(),
+                    }];
+                    printer.line(prefix_name, line_number(number), true, text, &whole)?;
+                }
+            }
+        } else {
+            let matches = colored_matches(programs, &bytes, printer)?;
+            printer.line(prefix_name, line_number(number), true, &bytes, &matches)?;
+        }
+        last_printed = Some(number);
+        after_left = lines_after;
+        printer.printed_group = true;
+    }
+
+    if options.quiet {
+        return Ok(count > 0);
+    }
+    if options.files_without_match {
+        if count == 0 {
+            printer.file_name(name)?;
+        }
+        return Ok(count > 0);
     }
+    if options.files_with_matches {
+        if count > 0 {
+            printer.file_name(name)?;
+        }
+    } else if options.count {
+        printer.line(prefix_name, None, true, count.to_string().as_bytes(), &[])?;
+    }
+    Ok(count > 0)
+}
+
+/// Search a file and print its selected lines as JSON events, for --json
+/// - The events have the shape of the ones of ripgrep's --json: begin, match, context and end
+/// - Like in ripgrep, the begin and end events are only printed for the files with selected lines
+/// - Each match event has the Captures of every match of the line, with the names of the groups
+/// - In a binary file the search stops at the first selected line, and the end event has its offset, unless -a is given
+/// - It returns whether the file has selected lines, and adds its statistics to the ones of the whole search
+/// # Arguments
+/// * `programs` - The compiled expressions, to find the matches of each selected line
+/// * `set` - The expressions joined into a RegexSet, to check if each line matches in a single scan
+/// * `path` - The path of the file, - is the standard input
+/// * `options` - A reference to the Options
+/// * `total` - The statistics of the whole search, for the summary event
+/// * `printer` - A mutable reference to the Printer
+fn search_file_json(
+    programs: &[Program],
+    set: &mut RegexSet,
+    path: &str,
+    options: &Options,
+    total: &mut Stats,
+    printer: &mut Printer,
+) -> io::Result<bool> {
+    let start = Instant::now();
+    let mut reader = open_lines(path)?;
+    let name = if path == "-" { STDIN_NAME } else { path };
+    let lines_before = options.lines_before();
+    let lines_after = options.lines_after();
+
+    let mut stats = Stats {
+        searches: 1,
+        ..Default::default()
+    };
+    let mut begun = false;
+    let mut binary_offset = None;
+    let mut number = 0;
+    let mut before: VecDeque<(usize, usize, Vec<u8>)> = VecDeque::with_capacity(lines_before);
+    let mut after_left = 0;
+    while let Some(mut bytes) = reader.next_bytes()? {
+        number += 1;
+        let offset = reader.offset();
+        let selected = stats.matched_lines < options.max_count.unwrap_or(usize::MAX)
+            && set
+                .is_match(Decoded::new(&bytes).as_str())
+                .map_err(io::Error::other)?
+                != options.invert;
+        let all = match selected && !options.invert {
+            true => byte_captures(programs, &bytes).map_err(io::Error::other)?,
+            false => vec![],
+        };
+        bytes.extend_from_slice(reader.line_ending());
+        stats.bytes_searched = offset + bytes.len();
+
+        if !selected {
+            if after_left > 0 {
+                let event = json::line_event("context", name, &bytes, number, offset, &[]);
+                printer.event(&event, &mut stats)?;
+                after_left -= 1;
+            } else if options
+                .max_count
+                .is_some_and(|max| stats.matched_lines >= max)
+            {
+                break;
+            } else if lines_before > 0 {
+                if before.len() == lines_before {
+                    before.pop_front();
+                }
+                before.push_back((number, offset, bytes));
+            }
+            continue;
+        }
 
-    let expression = &args[1];
-    if expression.is_empty() {
-        return Err("Expression is empty".into());
+        if !begun {
+            printer.event(&json::begin(name), &mut stats)?;
+            begun = true;
+        }
+        if reader.is_binary() && !options.text {
+            binary_offset = Some(offset);
+            break;
+        }
+        stats.matched_lines += 1;
+        for (n, offset, text) in before.drain(..) {
+            let event = json::line_event("context", name, &text, n, offset, &[]);
+            printer.event(&event, &mut stats)?;
+        }
+        stats.matches += all.len();
+        let event = json::line_event("match", name, &bytes, number, offset, &all);
+        printer.event(&event, &mut stats)?;
+        after_left = lines_after;
     }
 
-    let path = &args[2];
-    if path.is_empty() {
-        return Err("Path is empty".into());
+    // A binary file matches even if its matching line isn't printed, like without --json
+    let matched = stats.matched_lines > 0 || binary_offset.is_some();
+    stats.searches_with_match = usize::from(matched);
+    stats.elapsed = start.elapsed();
+    if begun {
+        let event = json::end(name, binary_offset, &stats);
+        printer.event(&event, &mut stats)?;
     }
+    total.add(&stats);
+    Ok(matched)
+}
 
-    Ok((expression.to_string(), path.to_string()))
+/// Replace the matches in the lines of a file and write it again, for --write
+/// - It returns whether a line of the file changed
+/// # Arguments
+/// * `programs` - The compiled expressions
+/// * `path` - The path of the file, the standard input can't be written
+/// * `template` - The replacement template
+/// * `options` - A reference to the Options
+fn write_file(
+    programs: &[Program],
+    path: &str,
+    template: &str,
+    options: &Options,
+) -> Result<bool, String> {
+    if path == "-" {
+        return Err(format!("{}: can't be written", STDIN_NAME));
+    }
+    match rewrite_file(path, programs, template, options.text) {
+        Ok(changed) => Ok(changed > 0),
+        Err(e) => Err(format!("{}: {}", path, error_message(&e))),
+    }
 }
 
 ///  This program implements the egrep command
 ///
 /// # How does it work?
-/// The program receives an expression and a path to a file. It reads the file and compares each line with the expression.
+/// The program receives the options, an expression and the paths to the files. It reads each file and compares each line with the expression.
+/// Without paths it reads the standard input, and with -r it searches the files of the directories.
+/// The files that can't be read are reported, and the other ones are still searched.
 /// It creates a list of regular expressions from the expression and then compares each line with the regular expressions.
 /// If the line matches the regular expressions, it prints the line.
+/// The exit code is 0 if a line was selected, 1 if none was and 2 if there was an error, like in GNU grep.
+fn main() -> ExitCode {
+    let started = Instant::now();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("tp1: {}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let (expression, path) = parse_args()?;
-    if expression.is_empty() || path.is_empty() {
-        return Err("Empty expression or path".into());
+    let programs = match compile_patterns(&options) {
+        Ok(programs) => programs,
+        Err(e) => {
+            eprintln!("tp1: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut set = RegexSet::from_programs(programs.clone());
+    let recursive = options.recursive || options.dereference_recursive;
+    // Like in GNU grep, the names are printed when several files can be searched, not just because of -r
+    let with_filename = options.with_filename.unwrap_or(
+        options.paths.len() > 1
+            || recursive && options.paths.iter().any(|path| Path::new(path).is_dir()),
+    );
+    let mut selected = false;
+    let mut failed = false;
+    let colored = match options.color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            io::stdout().is_terminal() && env::var("TERM").is_ok_and(|term| term != "dumb")
+        }
+    };
+    let mut printer = Printer {
+        out: io::stdout().lock(),
+        colors: colored.then(|| {
+            Colors::from_spec(&env::var("GREP_COLORS").unwrap_or_default())
+                .with_invert(options.invert)
+        }),
+        printed_group: false,
+    };
+    let json = options.json && !options.quiet;
+    let mut total = Stats::default();
+    for file in expand_paths(&options) {
+        let path = match file {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("tp1: {}", e);
+                failed = true;
+                continue;
+            }
+        };
+        let result = match (&options.replace, options.write) {
+            (Some(template), true) => write_file(&programs, &path, template, &options),
+            _ => {
+                let searched = match json {
+                    true => search_file_json(
+                        &programs,
+                        &mut set,
+                        &path,
+                        &options,
+                        &mut total,
+                        &mut printer,
+                    ),
+                    false => search_file(
+                        &programs,
+                        &mut set,
+                        &path,
+                        &options,
+                        with_filename,
+                        &mut printer,
+                    ),
+                };
+                match searched {
+                    // The output was closed, like with | head, so nothing else can be printed
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return ExitCode::SUCCESS,
+                    searched => searched.map_err(|e| format!("{}: {}", path, error_message(&e))),
+                }
+            }
+        };
+        match result {
+            Ok(found) => selected |= found,
+            Err(e) => {
+                eprintln!("tp1: {}", e);
+                failed = true;
+            }
+        }
+        if selected && options.quiet {
+            break;
+        }
     }
 
-    let results = process_expressions_and_paths(&expression, &path)?;
-    print_results(&results);
+    if json {
+        let summary = json::summary(started.elapsed(), &total);
+        match printer.event(&summary, &mut total) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("tp1: write error: {}", error_message(&e));
+                failed = true;
+            }
+            Ok(()) => {}
+        }
+    }
 
-    Ok(())
+    if failed && !(selected && options.quiet) {
+        ExitCode::from(2)
+    } else if selected {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
 }
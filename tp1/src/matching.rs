@@ -1,37 +1,55 @@
+use crate::backtrack::backtrack_at;
+use crate::captures::Captures;
 use crate::metachars::RegexClass;
+use crate::nfa::Program;
+use crate::posix::posix_captures_at;
 use crate::regex::Regex;
-use crate::regex_rep::RegexRep;
-use crate::regex_step::RegexStep;
+use crate::regex_error::RegexError;
+use crate::regex_match::Match;
+use crate::regex_options::RegexOptions;
+use crate::regex_set::RegexSet;
 use crate::regex_val::RegexValue;
+use crate::simulation::leftmost_longest_at;
 use crate::type_of_line::RegexRestriction;
 
-/// Enum to represent the state of the match
-#[derive(Debug, PartialEq)]
-pub enum MatchState {
-    /// The match is not completed
-    NotMatched,
-    /// The match is in progress
-    InProgress,
-    /// The match is at the end of the regex
-    EndOfRegex,
-    /// The match is at the end of the word
-    EndOfWord,
-}
-
 /// Checks the regex class with the actual char of the word
 /// - If the class is Alpha and the char is "a", returns true
 /// - If the class is Upper and the char is "a", returns false
+/// - Unless the options are ascii only, the classes follow the Unicode properties, so "ñ" is Alpha
 /// # Arguments
 /// * `class` - A reference to a RegexClass
 /// * `c` - A char
+/// * `options` - A reference to a RegexOptions
 /// # Returns
 /// * A boolean
 /// # Example
 /// let class = RegexClass::Alpha;
 /// let c = 'a';
-/// let result = handle_regex_class(&class, c);
+/// let result = handle_regex_class(&class, c, &RegexOptions::default());
 /// assert_eq!(result, true);
-pub fn handle_regex_class(class: &RegexClass, c: char) -> bool {
+pub fn handle_regex_class(class: &RegexClass, c: char, options: &RegexOptions) -> bool {
+    if c.is_ascii() || options.ascii {
+        return handle_ascii_class(class, c);
+    }
+    match class {
+        RegexClass::Alpha => c.is_alphabetic(),
+        RegexClass::Alnum => c.is_alphanumeric(),
+        RegexClass::Digit | RegexClass::Xdigit => false,
+        RegexClass::Lower => c.is_lowercase(),
+        RegexClass::Upper => c.is_uppercase(),
+        RegexClass::Punct => !c.is_alphanumeric() && !c.is_whitespace() && !c.is_control(),
+        RegexClass::Space => c.is_whitespace(),
+        RegexClass::Blank => c.is_whitespace() && !matches!(c, '\u{85}' | '\u{2028}' | '\u{2029}'),
+        RegexClass::Cntrl => c.is_control(),
+        RegexClass::Graph => !c.is_control() && !c.is_whitespace(),
+        RegexClass::Print => !c.is_control(),
+        RegexClass::Word => c.is_alphanumeric(),
+    }
+}
+
+/// Checks the regex class with an ascii char
+/// - Any char that isn't ascii doesn't belong to any class
+fn handle_ascii_class(class: &RegexClass, c: char) -> bool {
     match class {
         RegexClass::Alpha => c.is_ascii_alphabetic(),
         RegexClass::Alnum => c.is_ascii_alphanumeric(),
@@ -40,7 +58,109 @@ pub fn handle_regex_class(class: &RegexClass, c: char) -> bool {
         RegexClass::Upper => c.is_ascii_uppercase(),
         RegexClass::Punct => c.is_ascii_punctuation(),
         RegexClass::Space => c.is_ascii_whitespace(),
+        RegexClass::Xdigit => c.is_ascii_hexdigit(),
+        RegexClass::Blank => c == ' ' || c == '\t',
+        RegexClass::Cntrl => c.is_ascii_control(),
+        RegexClass::Graph => c.is_ascii_graphic(),
+        RegexClass::Print => c.is_ascii_graphic() || c == ' ',
+        RegexClass::Word => c.is_ascii_alphanumeric() || c == '_',
+    }
+}
+
+/// The chars whose simple case folding isn't just a lowercase and an uppercase letter, each group is a whole orbit
+/// - Every char of a group folds to the same char, so each one matches all the other ones with -i
+/// - A group with a single char has no other case, even if its lowercase or uppercase is a single char
+const CASE_ORBITS: &[&[char]] = &[
+    &['K', 'k', '\u{212A}'],
+    &['S', 's', '\u{17F}'],
+    &['\u{B5}', '\u{39C}', '\u{3BC}'],
+    &['\u{C5}', '\u{E5}', '\u{212B}'],
+    &['\u{DF}', '\u{1E9E}'],
+    &['\u{130}'],
+    &['\u{131}'],
+    &['\u{1C4}', '\u{1C5}', '\u{1C6}'],
+    &['\u{1C7}', '\u{1C8}', '\u{1C9}'],
+    &['\u{1CA}', '\u{1CB}', '\u{1CC}'],
+    &['\u{1F1}', '\u{1F2}', '\u{1F3}'],
+    &['\u{345}', '\u{399}', '\u{3B9}', '\u{1FBE}'],
+    &['\u{390}', '\u{1FD3}'],
+    &['\u{3B0}', '\u{1FE3}'],
+    &['\u{392}', '\u{3B2}', '\u{3D0}'],
+    &['\u{395}', '\u{3B5}', '\u{3F5}'],
+    &['\u{398}', '\u{3B8}', '\u{3D1}', '\u{3F4}'],
+    &['\u{39A}', '\u{3BA}', '\u{3F0}'],
+    &['\u{3A0}', '\u{3C0}', '\u{3D6}'],
+    &['\u{3A1}', '\u{3C1}', '\u{3F1}'],
+    &['\u{3A3}', '\u{3C2}', '\u{3C3}'],
+    &['\u{3A6}', '\u{3C6}', '\u{3D5}'],
+    &['\u{3A9}', '\u{3C9}', '\u{2126}'],
+    &['\u{412}', '\u{432}', '\u{1C80}'],
+    &['\u{414}', '\u{434}', '\u{1C81}'],
+    &['\u{41E}', '\u{43E}', '\u{1C82}'],
+    &['\u{421}', '\u{441}', '\u{1C83}'],
+    &['\u{422}', '\u{442}', '\u{1C84}', '\u{1C85}'],
+    &['\u{42A}', '\u{44A}', '\u{1C86}'],
+    &['\u{462}', '\u{463}', '\u{1C87}'],
+    &['\u{A64A}', '\u{A64B}', '\u{1C88}'],
+    &['\u{1E60}', '\u{1E61}', '\u{1E9B}'],
+    &['\u{FB05}', '\u{FB06}'],
+];
+
+/// Returns the other cases of a char, using Unicode simple case folding
+/// - The chars of CASE_ORBITS get the rest of their orbit, so "s" also matches "ſ" and "k" the Kelvin sign
+/// - For the other chars the orbit is the char and its lowercase or uppercase, when they are a single char,
+///   so "ß" has no other case but "ẞ"
+/// # Arguments
+/// * `c` - A char
+/// # Returns
+/// * A vector with the other cases of the char
+/// # Example
+/// assert_eq!(other_cases('Ñ'), vec!['ñ']);
+/// assert_eq!(other_cases('k'), vec!['K', '\u{212A}']);
+pub fn other_cases(c: char) -> Vec<char> {
+    if let Some(orbit) = CASE_ORBITS.iter().find(|orbit| orbit.contains(&c)) {
+        return orbit.iter().copied().filter(|other| *other != c).collect();
     }
+    let mut cases = Vec::new();
+    let conversions: [Vec<char>; 2] = [c.to_lowercase().collect(), c.to_uppercase().collect()];
+    for conversion in conversions {
+        if let [other] = conversion[..] {
+            if other != c && !cases.contains(&other) {
+                cases.push(other);
+            }
+        }
+    }
+    cases
+}
+
+/// Checks the regex value with the actual char of the word
+/// - If the options ignore the case, the other cases of the char are checked too
+/// # Arguments
+/// * `val` - A reference to a RegexValue
+/// * `c` - A char
+/// * `options` - A reference to a RegexOptions
+/// # Returns
+/// * A boolean
+/// # Example
+/// let val = RegexValue::Set(CharSet { negated: false, items: vec![SetItem::Char('a'), SetItem::Char('b')] });
+/// let result = handle_regex_value(&val, 'b', &RegexOptions::default());
+/// assert_eq!(result, true);
+pub fn handle_regex_value(val: &RegexValue, c: char, options: &RegexOptions) -> bool {
+    let matches = |c: char| match val {
+        RegexValue::Literal(l) => *l == c,
+        RegexValue::Wildcard => true,
+        RegexValue::Class(class) => handle_regex_class(class, c, options),
+        RegexValue::Set(set) => set.contains(c, options),
+        RegexValue::Group(_)
+        | RegexValue::Capture(..)
+        | RegexValue::NamedCapture(..)
+        | RegexValue::Backreference(_)
+        | RegexValue::Restriction(_) => false,
+    };
+    if matches(c) {
+        return true;
+    }
+    options.ignore_case && other_cases(c).into_iter().any(matches)
 }
 
 /// Checks if the backtracking is the end of the line ( $ )
@@ -87,453 +207,160 @@ pub fn is_start_of_line(backtracking: &Option<Vec<RegexRestriction>>) -> bool {
     false
 }
 
-/// Handles the case when the match is at the end of the expression
-/// # Arguments
-/// * `match_state` - A MatchState
-/// * `regex` - A reference to a Regex
-/// * `word` - A reference to a str
-/// * `input_chars` - A mutable reference to a std::str::Chars
-/// * `result` - A mutable reference to a String
-/// # Returns
-/// * A String
-/// # Example
-/// let match_state = MatchState::EndOfRegex;
-/// let regex = Regex::new("a").unwrap();
-/// let word = "a";
-/// let mut input_chars = word.chars();
-/// let result = "".to_string();
-/// let result = handle_end_of_expression(match_state, &regex, word, &mut input_chars, result);
-/// assert_eq!(result, "a".to_string());
-pub fn handle_end_of_expression(
-    match_state: MatchState,
-    regex: &Regex,
+/// Finds the leftmost-longest match of a compiled regex in a word, from a byte offset
+/// - The NFA simulation is used, unless the program needs backtracking
+/// - The words without the required literal of the regex are skipped without running it
+fn find_program_at(
+    program: &Program,
     word: &str,
-    input_chars: &mut std::str::Chars,
-    result: String,
-) -> String {
-    if match_state == MatchState::EndOfRegex {
-        if is_end_of_line(&regex.backtracking) && input_chars.next().is_some() {
-            return compare_regex_with_expression(regex, &word[1..]);
-        }
-        return result;
+    from: usize,
+) -> Result<Option<(usize, usize)>, RegexError> {
+    if !program.may_match(&word[from..]) {
+        return Ok(None);
     }
-
-    if !is_start_of_line(&regex.backtracking) && input_chars.next().is_some() {
-        return compare_regex_with_expression(regex, &word[1..]);
+    if !program.needs_backtracking() {
+        return Ok(leftmost_longest_at(program, word, from));
     }
-
-    "".to_string()
+    Ok(backtrack_at(program, word, from)?.and_then(|slots| Some((slots[0]?, slots[1]?))))
 }
 
-/// Handles the case when the step is none, this means that the character received should be in this step
+/// Finds the leftmost-longest match of a compiled regex in a word
+/// - The match can start at any position of the word, unless the regex starts with ^
 /// # Arguments
-/// * `step` - A reference to a RegexStep
-/// * `actual_char` - A char
-/// * `match_state` - A mutable reference to a MatchState
+/// * `program` - A reference to a Program
+/// * `word` - A reference to a str
 /// # Returns
-/// * A boolean
+/// * A Result with an Option with the byte offsets where the match starts and ends,
+///   or a BacktrackLimit error
 /// # Example
-/// let step = RegexStep { rep: RegexRep::None, val: RegexValue::Literal('a') };
-/// let actual_char = 'a';
-/// let mut match_state = MatchState::InProgress;
-/// let result = handle_none_case(&step, actual_char, &mut match_state);
-/// assert_eq!(result, false);
-/// let step = RegexStep { rep: RegexRep::None, val: RegexValue::Literal('a') };
-/// let actual_char = 'b';
-/// let mut match_state = MatchState::InProgress;
-/// let result = handle_none_case(&step, actual_char, &mut match_state);
-/// assert_eq!(result, true);
-pub fn handle_none_case(step: &RegexStep, actual_char: char, match_state: &mut MatchState) -> bool {
-    match &step.val {
-        RegexValue::Literal(c) => {
-            if c == &actual_char {
-                *match_state = MatchState::NotMatched;
-                return false;
-            }
-        }
-        RegexValue::Wildcard => {
-            *match_state = MatchState::NotMatched;
-            return false;
-        }
-        RegexValue::Class(class) => {
-            if handle_regex_class(class, actual_char) {
-                *match_state = MatchState::NotMatched;
-                return false;
-            }
-        }
-        RegexValue::OneOf(chars) => {
-            if chars.contains(&actual_char) {
-                *match_state = MatchState::NotMatched;
-                return false;
-            }
-        }
-    }
-    *match_state = MatchState::InProgress;
-    true
+/// let program = Program::new(&Regex::new("a+").unwrap());
+/// let result = find_match(&program, "baab");
+/// assert_eq!(result, Ok(Some((1, 3))));
+pub fn find_match(program: &Program, word: &str) -> Result<Option<(usize, usize)>, RegexError> {
+    find_program_at(program, word, 0)
 }
 
-/// Handles the case when the step is exact, this means that the character received should be in this step
+/// Finds the leftmost-longest match of a compiled regex in a word, with the text matched by each group
 /// # Arguments
-/// * `step` - A reference to a RegexStep
-/// * `actual_char` - A char
-/// * `match_state` - A mutable reference to a MatchState
-/// * `count` - A usize
-/// * `input_chars` - A mutable reference to a std::str::Chars
-/// * `result` - A mutable reference to a String
+/// * `program` - A reference to a Program
+/// * `word` - A reference to a str
 /// # Returns
-/// * A boolean
+/// * A Result with an Option with the Captures, or a BacktrackLimit error
 /// # Example
-/// let step = RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') };
-/// let actual_char = 'a';
-/// let mut match_state = MatchState::InProgress;
-/// let count = 1;
-/// let mut input_chars = "a".chars();
-/// let result = "".to_string();
-/// let result = handle_exact_case(&step, actual_char, &mut match_state, count, &mut input_chars, result);
-/// assert_eq!(result, true);
-pub fn handle_exact_case(
-    step: &RegexStep,
-    mut actual_char: char,
-    match_state: &mut MatchState,
-    count: usize,
-    input_chars: &mut std::str::Chars,
-    result: &mut String,
-) -> bool {
-    for index in 0..count {
-        match &step.val {
-            RegexValue::Literal(c) => {
-                if c != &actual_char {
-                    *match_state = MatchState::NotMatched;
-                    break;
-                }
-            }
-            RegexValue::Wildcard => {}
-            RegexValue::Class(class) => {
-                if !handle_regex_class(class, actual_char) {
-                    *match_state = MatchState::NotMatched;
-                    break;
-                }
-            }
-            RegexValue::OneOf(content) => {
-                if !content.contains(&actual_char) {
-                    *match_state = MatchState::NotMatched;
-                    break;
-                }
-            }
-        }
-
-        *match_state = MatchState::InProgress;
-        result.push(actual_char);
-        if index < count - 1 {
-            actual_char = match input_chars.next() {
-                Some(c) => c,
-                None => {
-                    *match_state = MatchState::EndOfWord;
-                    break;
-                }
-            };
-        }
-    }
-    if *match_state == MatchState::NotMatched {
-        return false;
-    }
-    true
+/// let program = Program::new(&Regex::new("(a+)(b)").unwrap());
+/// let captures = captures(&program, "xaab").unwrap().unwrap();
+/// assert_eq!(captures.get(1), Some(Match { start: 1, end: 3 }));
+pub fn captures(program: &Program, word: &str) -> Result<Option<Captures>, RegexError> {
+    captures_at(program, word, 0)
 }
 
-/// Sets and matches the minimum number of characters that should be matched
-/// - If the minimum number of characters is not matched, it returns None
-pub fn handle_min(
-    step: &RegexStep,
-    actual_char: char,
-    match_state: &mut MatchState,
-    input_chars: &mut std::str::Chars,
-    result: &mut String,
-    min: Option<usize>,
-) -> Option<usize> {
-    match min {
-        Some(min) => {
-            if !handle_exact_case(step, actual_char, match_state, min, input_chars, result) {
-                None
-            } else {
-                Some(min)
-            }
-        }
-        None => Some(0),
-    }
-}
-
-/// Sets the maximum number of characters that can be matched
-/// - If there was specified a maximum number of characters, it returns the difference between the maximum and the minimum
-/// - If there wasn't specified a maximum number of characters, it returns the maximum value of i8
-pub fn handle_max(min_count: usize, max: Option<usize>) -> i8 {
-    match max {
-        Some(max_count) => max_count as i8 - min_count as i8,
-        None => i8::MAX,
-    }
-}
-
-/// Handles the case when the step is none
-/// - The character that's being analize should be saved in the result if it's found
+/// Finds the leftmost-longest match of a compiled regex in a word from a byte offset, with the text matched by each group
+/// - The groups follow the POSIX rules for subexpressions, or the first path that matches when the regex needs backtracking
 /// # Arguments
-/// * `step` - A reference to a RegexStep
-/// * `actual_char` - A char
-/// * `match_state` - A mutable reference to a MatchState
-/// * `count` - A usize
-/// * `input_chars` - A mutable reference to a std::str::Chars
-/// * `result` - A mutable reference to a String
+/// * `program` - A reference to a Program
+/// * `word` - A reference to a str
+/// * `from` - The byte offset where the search starts
 /// # Returns
-/// * A boolean
-/// # Example
-/// let step = RegexStep { rep: RegexRep::None, val: RegexValue::Literal('a') };
-/// let actual_char = 'a';
-/// let mut match_state = MatchState::InProgress;
-/// let count = 1;
-/// let mut input_chars = "a".chars();
-/// let result = "".to_string();
-/// let result = handle_none_case(&step, actual_char, &mut match_state);
-/// assert_eq!(result, false);
-/// let step = RegexStep { rep: RegexRep::None, val: RegexValue::Literal('a') };
-/// let actual_char = 'b';
-/// let mut match_state = MatchState::InProgress;
-/// let count = 1;
-/// let mut input_chars = "b".chars();
-/// let result = "".to_string();
-/// let result = handle_none_case(&step, actual_char, &mut match_state);
-/// assert_eq!(result, true);
-pub fn handle_main_loop(
-    step: &RegexStep,
-    mut new_char: char,
-    max_count: i8,
-    count: &mut i8,
-    input_chars: &mut std::str::Chars,
-    result: &mut String,
-    match_state: &mut MatchState,
-) -> bool {
-    let mut new_result = String::new();
-    while *count <= max_count {
-        if handle_exact_case(step, new_char, match_state, 1, input_chars, &mut new_result) {
-            result.push_str(&new_result);
-            return true;
-        }
-        result.push(new_char);
-        new_char = match input_chars.next() {
-            Some(c) => c,
-            None => {
-                *match_state = MatchState::EndOfWord;
-                return true;
-            }
-        };
-
-        *count += 1;
+/// * A Result with an Option with the Captures, or a BacktrackLimit error
+pub fn captures_at(
+    program: &Program,
+    word: &str,
+    from: usize,
+) -> Result<Option<Captures>, RegexError> {
+    if !program.may_match(&word[from..]) {
+        return Ok(None);
     }
-    true
+    let slots = match program.needs_backtracking() {
+        true => backtrack_at(program, word, from)?,
+        false => posix_captures_at(program, word, from)?,
+    };
+    Ok(slots.map(|slots| Captures::with_names(slots, program.names.clone())))
 }
 
-/// Handles the case when the step is a range
-/// - The character that's being analize should be saved in the result if it's found
-/// - If the range has multiple characters that match, all should be saved in the result
+/// Finds the leftmost-longest match of several compiled regexes in a word, from a byte offset
+/// - Between matches that start at the same position, the longest one wins
 /// # Arguments
-/// * `step` - A reference to a RegexStep
-/// * `steps_iter` - A mutable reference to a `std::slice::Iter<RegexStep>`
-/// * `actual_char` - A char
-/// * `match_state` - A mutable reference to a MatchState
-/// * `input_chars` - A mutable reference to a std::str::Chars
-/// * `result` - A mutable reference to a String
-/// * `min` - An Option of a usize
-/// * `max` - An Option of a usize
+/// * `programs` - A slice of Program
+/// * `word` - A reference to a str
+/// * `from` - The byte offset where the search starts
 /// # Returns
-/// * A boolean
+/// * A Result with an Option with the Match, or a BacktrackLimit error
 /// # Example
-/// let step = RegexStep { rep: RegexRep::Range { min: Some(1), max: Some(2) }, val: RegexValue::Literal('a') };
-/// let mut steps_iter = vec![step].iter();
-/// let actual_char = 'a';
-/// let mut match_state = MatchState::InProgress;
-/// let mut input_chars = "a".chars();
-/// let result = "".to_string();
-/// let min = Some(1);
-/// let max = Some(2);
-/// let result = handle_range_case(&step, &mut steps_iter, actual_char, &mut match_state, &mut input_chars, result, min, max);
-/// assert_eq!(result, true);
-/// let step = RegexStep { rep: RegexRep::Range { min: Some(1), max: Some(2) }, val: RegexValue::Literal('a') };
-/// let mut steps_iter = vec![step].iter();
-/// let actual_char = 'b';
-/// let mut match_state = MatchState::InProgress;
-/// let mut input_chars = "b".chars();
-/// let result = "".to_string();
-/// let min = Some(1);
-/// let max = Some(2);
-/// let result = handle_range_case(&step, &mut steps_iter, actual_char, &mut match_state, &mut input_chars, result, min, max);
-/// assert_eq!(result, false);
-#[allow(clippy::too_many_arguments)]
-pub fn handle_range_case(
-    step: &RegexStep,
-    steps_iter: &mut std::slice::Iter<RegexStep>,
-    actual_char: char,
-    match_state: &mut MatchState,
-    input_chars: &mut std::str::Chars,
-    result: &mut String,
-    min: Option<usize>,
-    max: Option<usize>,
-) -> bool {
-    let min_count = match handle_min(step, actual_char, match_state, input_chars, result, min) {
-        Some(min_count) => min_count,
-        None => return false,
-    };
-
-    let max_count = handle_max(min_count, max);
-    let mut count = 0;
-
-    let step = match steps_iter.next() {
-        Some(s) => s,
-        None => {
-            *match_state = MatchState::EndOfRegex;
-            for c in input_chars.by_ref() {
-                if Some(c) == Some(actual_char) && count < max_count {
-                    result.push(c);
-                    count += 1;
-                } else {
-                    break;
-                }
+/// let programs = vec![Program::new(&Regex::new("ab").unwrap()), Program::new(&Regex::new("abc").unwrap())];
+/// let result = find_at(&programs, "xabc", 0);
+/// assert_eq!(result, Ok(Some(Match { start: 1, end: 4 })));
+pub fn find_at(programs: &[Program], word: &str, from: usize) -> Result<Option<Match>, RegexError> {
+    let mut best: Option<Match> = None;
+    for program in programs {
+        if let Some((start, end)) = find_program_at(program, word, from)? {
+            if best.is_none_or(|b| start < b.start || (start == b.start && end > b.end)) {
+                best = Some(Match { start, end });
             }
-            return true;
-        }
-    };
-
-    let new_char = match input_chars.next() {
-        Some(c) => c,
-        None => {
-            *match_state = MatchState::EndOfWord;
-            return true;
         }
-    };
-
-    handle_main_loop(
-        step,
-        new_char,
-        max_count,
-        &mut count,
-        input_chars,
-        result,
-        match_state,
-    )
+    }
+    Ok(best)
 }
 
-/// Handles the case the step's repetition
-/// - The character that's being analize should be saved in the result if it's found
+/// Finds every non-overlapping match of several compiled regexes in a word, from left to right
+/// - After an empty match the search goes on from the next char
+/// - An empty match right after the previous match is skipped, so "a*" finds only "aaa" in "aaa"
 /// # Arguments
-/// * `step` - A reference to a RegexStep
-/// * `steps_iter` - A mutable reference to a `std::slice::Iter<RegexStep>`
-/// * `actual_char` - A char
-/// * `match_state` - A mutable reference to a MatchState
-/// * `input_chars` - A mutable reference to a std::str::Chars
-/// * `result` - A mutable reference to a String
+/// * `programs` - A slice of Program
+/// * `word` - A reference to a str
 /// # Returns
-/// * A boolean
+/// * A Result with a vector of Match, or a BacktrackLimit error
 /// # Example
-/// let step = RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') };
-/// let mut steps_iter = vec![step].iter();
-/// let actual_char = 'a';
-/// let mut match_state = MatchState::InProgress;
-/// let mut input_chars = "a".chars();
-/// let result = "".to_string();
-/// let result = handle_step_rep(&step, &mut steps_iter, actual_char, &mut match_state, &mut input_chars, result);
-/// assert_eq!(result, true);
-/// let step = RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') };
-/// let mut steps_iter = vec![step].iter();
-/// let actual_char = 'b';
-/// let mut match_state = MatchState::InProgress;
-/// let mut input_chars = "b".chars();
-/// let result = "".to_string();
-/// let result = handle_step_rep(&step, &mut steps_iter, actual_char, &mut match_state, &mut input_chars, result);
-/// assert_eq!(result, false);
-pub fn handle_step_rep(
-    step: &RegexStep,
-    steps_iter: &mut std::slice::Iter<RegexStep>,
-    actual_char: char,
-    match_state: &mut MatchState,
-    input_chars: &mut std::str::Chars,
-    result: &mut String,
-) -> bool {
-    match step.rep {
-        RegexRep::Exact(count) => {
-            handle_exact_case(step, actual_char, match_state, count, input_chars, result)
-        }
-        RegexRep::Range { min, max } => handle_range_case(
-            step,
-            steps_iter,
-            actual_char,
-            match_state,
-            input_chars,
-            result,
-            min,
-            max,
-        ),
-
-        RegexRep::None => {
-            if handle_none_case(step, actual_char, match_state) {
-                result.push(actual_char);
+/// let programs = vec![Program::new(&Regex::new("o").unwrap())];
+/// let result = find_iter(&programs, "foo");
+/// assert_eq!(result, Ok(vec![Match { start: 1, end: 2 }, Match { start: 2, end: 3 }]));
+pub fn find_iter(programs: &[Program], word: &str) -> Result<Vec<Match>, RegexError> {
+    let mut matches: Vec<Match> = Vec::new();
+    let mut from = 0;
+    while from <= word.len() {
+        let Some(found) = find_at(programs, word, from)? else {
+            break;
+        };
+        if found.is_empty() {
+            from = found.end + word[found.end..].chars().next().map_or(1, char::len_utf8);
+            if matches.last().is_some_and(|last| last.end == found.end) {
+                continue;
             }
-            true
+        } else {
+            from = found.end;
         }
+        matches.push(found);
     }
+    Ok(matches)
 }
 
-///  Processes the regex steps
+/// Finds every non-overlapping match of several compiled regexes in a word, with the text matched by each group
+/// - The matches are the ones of find_iter, the groups are the ones of the regex that found each match
 /// # Arguments
-/// * `steps_iter` - A mutable reference to a `std::slice::Iter<RegexStep>`
-/// * `input_chars` - A mutable reference to a std::str::Chars
-/// * `match_state` - A mutable reference to a MatchState
-/// * `result` - A mutable reference to a String
+/// * `programs` - A slice of Program
+/// * `word` - A reference to a str
+/// # Returns
+/// * A Result with a vector of Captures, or a BacktrackLimit error
 /// # Example
-/// let mut steps_iter = vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') }].iter();
-/// let mut input_chars = "a".chars();
-/// let mut match_state = MatchState::InProgress;
-/// let result = "".to_string();
-/// process_regex_steps(&mut steps_iter, &mut input_chars, &mut match_state, &mut result);
-/// assert_eq!(result, "a".to_string());
-/// let mut steps_iter = vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') }].iter();
-/// let mut input_chars = "b".chars();
-/// let mut match_state = MatchState::InProgress;
-/// let result = "".to_string();
-/// process_regex_steps(&mut steps_iter, &mut input_chars, &mut match_state, &mut result);
-/// assert_eq!(result, "".to_string());
-pub fn process_regex_steps(
-    steps_iter: &mut std::slice::Iter<RegexStep>,
-    input_chars: &mut std::str::Chars,
-    match_state: &mut MatchState,
-    result: &mut String,
-) {
-    while *match_state == MatchState::InProgress {
-        let step = match steps_iter.next() {
-            Some(s) => s,
-            None => {
-                *match_state = MatchState::EndOfRegex;
-                break;
-            }
-        };
-
-        let actual_char = match input_chars.next() {
-            Some(c) => c,
-            None => {
-                *match_state = MatchState::EndOfWord;
-                break;
+/// let programs = vec![Program::new(&Regex::new("(a)b").unwrap()), Program::new(&Regex::new("(c)").unwrap())];
+/// let result = captures_iter(&programs, "abc").unwrap();
+/// assert_eq!(result[1].get(1), Some(Match { start: 2, end: 3 }));
+pub fn captures_iter(programs: &[Program], word: &str) -> Result<Vec<Captures>, RegexError> {
+    let mut all: Vec<Captures> = Vec::new();
+    for m in find_iter(programs, word)? {
+        for program in programs {
+            if let Some(captures) = captures_at(program, word, m.start)? {
+                if captures.get(0) == Some(m) {
+                    all.push(captures);
+                    break;
+                }
             }
-        };
-
-        if !handle_step_rep(
-            step,
-            steps_iter,
-            actual_char,
-            match_state,
-            input_chars,
-            result,
-        ) {
-            break;
         }
     }
+    Ok(all)
 }
 
 /// Compares a regex with a word
+/// - It returns the leftmost-longest match, or an empty string if there is none
 /// # Arguments
 /// * `regex` - A reference to a Regex
 /// * `word` - A reference to a str
@@ -549,26 +376,18 @@ pub fn process_regex_steps(
 /// let result = compare_regex_with_expression(&regex, &word);
 /// assert_eq!(result, "".to_string());
 pub fn compare_regex_with_expression(regex: &Regex, word: &str) -> String {
-    let mut result = String::new();
-    let mut match_state: MatchState = MatchState::InProgress;
-    let mut steps_iter = regex.steps.iter();
-    let mut input_chars = word.chars();
-
-    process_regex_steps(
-        &mut steps_iter,
-        &mut input_chars,
-        &mut match_state,
-        &mut result,
-    );
-
-    handle_end_of_expression(match_state, regex, word, &mut input_chars, result)
+    let program = Program::new(regex);
+    match find_match(&program, word) {
+        Ok(Some((start, end))) => word[start..end].to_string(),
+        _ => "".to_string(),
+    }
 }
 
 /// Compares the regexes with a word
-/// - It returns the matched part of the word
+/// - It returns the whole word if any of the regexes matches it, use find_at for the leftmost-longest match
 /// - If the orginial expression had "|", it would receive more than one regex
 /// # Arguments
-/// * `regexes` - A reference to a vector of Regex
+/// * `regexes` - A slice of Regex
 /// * `s` - A String
 /// # Returns
 /// * A Result with a String or an error
@@ -582,17 +401,40 @@ pub fn compare_regex_with_expression(regex: &Regex, word: &str) -> String {
 /// let result = compare_regexes_with_expression(&regexes, s);
 /// assert_eq!(result, Ok("b".to_string()));
 pub fn compare_regexes_with_expression(
-    regexes: &Vec<Regex>,
+    regexes: &[Regex],
     s: String,
 ) -> Result<String, &'static str> {
-    for regex in regexes {
-        let result = compare_regex_with_expression(regex, &s);
-        if !result.is_empty() {
-            return Ok(s);
-        }
-    }
+    compare_regexes_with_options(regexes, s, &RegexOptions::default())
+}
 
-    Err("No match found")
+/// Compares the regexes with a word, using the given options
+/// - It works like compare_regexes_with_expression, but the regexes are compiled with the options
+/// - Only whether the word matches is needed, so the regexes are joined into a RegexSet and run as one DFA
+/// # Arguments
+/// * `regexes` - A slice of Regex
+/// * `s` - A String
+/// * `options` - A reference to a RegexOptions
+/// # Returns
+/// * A Result with a String or an error
+/// # Example
+/// let regexes = vec![Regex::new("hola").unwrap()];
+/// let options = RegexOptions { ignore_case: true, ..Default::default() };
+/// let result = compare_regexes_with_options(&regexes, "HOLA".to_string(), &options);
+/// assert_eq!(result, Ok("HOLA".to_string()));
+pub fn compare_regexes_with_options(
+    regexes: &[Regex],
+    s: String,
+    options: &RegexOptions,
+) -> Result<String, &'static str> {
+    let programs = regexes
+        .iter()
+        .map(|regex| Program::with_options(regex, options.clone()))
+        .collect();
+    match RegexSet::from_programs(programs).is_match(&s) {
+        Ok(true) => Ok(s),
+        Ok(false) => Err("No match found"),
+        Err(_) => Err("Backtracking limit exceeded"),
+    }
 }
 
 #[cfg(test)]
@@ -1170,11 +1012,11 @@ mod tests {
         #[test]
         fn test_2() {
             let regex = regex::Regex::new("ab.*cd").unwrap();
-            /* let word = "abcd".to_string();
+            let word = "abcd".to_string();
             assert_eq!(
                 compare_regex_with_expression(&regex, &word),
                 "abcd".to_string()
-            );*/
+            );
             let word = "abxcd".to_string();
             assert!(!compare_regex_with_expression(&regex, &word).is_empty());
             let word = "abxcdx".to_string();
@@ -1213,4 +1055,607 @@ mod tests {
             assert_eq!(compare_regex_with_expression(&regex, &word), "".to_string());
         }
     }
+
+    mod backtracking {
+        use super::*;
+
+        #[test]
+        fn test_1() {
+            let regex = regex::Regex::new("a*ab").unwrap();
+            let word = "aaab".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), word);
+            let word = "xab".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "ab".to_string()
+            );
+        }
+
+        #[test]
+        fn test_2() {
+            let regex = regex::Regex::new("[[:alpha:]]+ing").unwrap();
+            let word = "singing".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), word);
+            let word = "ing".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), "".to_string());
+        }
+
+        #[test]
+        fn empty_match() {
+            let regexes = vec![regex::Regex::new("a*").unwrap()];
+            let s = "bbb".to_string();
+            assert_eq!(compare_regexes_with_expression(&regexes, s.clone()), Ok(s));
+        }
+    }
+
+    mod unanchored {
+        use super::*;
+
+        #[test]
+        fn test_1() {
+            let regex = regex::Regex::new("hola").unwrap();
+            let word = "dijo hola mundo".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "hola".to_string()
+            );
+        }
+
+        #[test]
+        fn test_2() {
+            let regex = regex::Regex::new("^hola").unwrap();
+            let word = "dijo hola mundo".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), "".to_string());
+        }
+
+        #[test]
+        fn test_3() {
+            let regex = regex::Regex::new("mundo$").unwrap();
+            let word = "dijo hola mundo".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "mundo".to_string()
+            );
+            let word = "mundo hola".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), "".to_string());
+        }
+
+        #[test]
+        fn test_4() {
+            let regex = regex::Regex::new("ol").unwrap();
+            let word = "canción sola".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "ol".to_string()
+            );
+        }
+    }
+
+    mod groups {
+        use super::*;
+
+        #[test]
+        fn test_1() {
+            let regex = regex::Regex::new("(foo|bar)baz").unwrap();
+            let word = "la barbaz".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "barbaz".to_string()
+            );
+            let word = "foobar".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), "".to_string());
+        }
+
+        #[test]
+        fn test_2() {
+            let regex = regex::Regex::new("(ab)+c").unwrap();
+            let word = "xababc".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "ababc".to_string()
+            );
+        }
+
+        #[test]
+        fn test_3() {
+            let regex = regex::Regex::new("a(b|cd)*e").unwrap();
+            let word = "abcdbe".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), word);
+            let word = "ace".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), "".to_string());
+        }
+
+        #[test]
+        fn test_4() {
+            let regex = regex::Regex::new("(^a|b$)").unwrap();
+            let word = "ab".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "a".to_string()
+            );
+            let word = "cb".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "b".to_string()
+            );
+            let word = "ca".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), "".to_string());
+        }
+    }
+
+    mod brackets {
+        use super::*;
+
+        #[test]
+        fn test_1() {
+            let regex = regex::Regex::new("[a-f0-9_]+").unwrap();
+            let word = "pi: 0xff_a9z".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "0".to_string()
+            );
+            let word = "ff_a9z".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "ff_a9".to_string()
+            );
+        }
+
+        #[test]
+        fn test_2() {
+            let regex = regex::Regex::new("[b-y]").unwrap();
+            let word = "a".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), "".to_string());
+            let word = "m".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), word);
+        }
+
+        #[test]
+        fn test_3() {
+            let regex = regex::Regex::new("[^]a]*").unwrap();
+            let word = "xy]z".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "xy".to_string()
+            );
+        }
+
+        #[test]
+        fn test_4() {
+            let regex = regex::Regex::new("can[[=c=]]i[[=o=]]n").unwrap();
+            let word = "una canción".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "canción".to_string()
+            );
+        }
+    }
+
+    mod more_classes {
+        use super::*;
+
+        #[test]
+        fn xdigit() {
+            let regex = regex::Regex::new("0x[[:xdigit:]]+").unwrap();
+            let word = "addr 0x1fA9g".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "0x1fA9".to_string()
+            );
+        }
+
+        #[test]
+        fn blank() {
+            let regex = regex::Regex::new("a[[:blank:]]b").unwrap();
+            assert_eq!(compare_regex_with_expression(&regex, "a\tb"), "a\tb");
+            assert_eq!(compare_regex_with_expression(&regex, "a\nb"), "");
+        }
+
+        #[test]
+        fn cntrl() {
+            let regex = regex::Regex::new("[[:cntrl:]]").unwrap();
+            assert_eq!(compare_regex_with_expression(&regex, "a\x07"), "\x07");
+            assert_eq!(compare_regex_with_expression(&regex, "a b"), "");
+        }
+
+        #[test]
+        fn graph_and_print() {
+            let regex = regex::Regex::new("[[:graph:]]+").unwrap();
+            assert_eq!(compare_regex_with_expression(&regex, " ab c"), "ab");
+            let regex = regex::Regex::new("[[:print:]]+").unwrap();
+            assert_eq!(compare_regex_with_expression(&regex, "\tab c\t"), "ab c");
+        }
+    }
+
+    mod shorthand {
+        use super::*;
+
+        #[test]
+        fn word() {
+            let regex = regex::Regex::new("\\w+").unwrap();
+            assert_eq!(
+                compare_regex_with_expression(&regex, "  user_id9!"),
+                "user_id9"
+            );
+            let regex = regex::Regex::new("\\W+").unwrap();
+            assert_eq!(compare_regex_with_expression(&regex, "ab, cd"), ", ");
+        }
+
+        #[test]
+        fn space() {
+            let regex = regex::Regex::new("a\\s+b").unwrap();
+            assert_eq!(compare_regex_with_expression(&regex, "a \t b"), "a \t b");
+            let regex = regex::Regex::new("\\S+").unwrap();
+            assert_eq!(compare_regex_with_expression(&regex, "  hola "), "hola");
+        }
+
+        #[test]
+        fn digit() {
+            let regex = regex::Regex::new("ERROR \\d+").unwrap();
+            assert_eq!(
+                compare_regex_with_expression(&regex, "[x] ERROR 404 found"),
+                "ERROR 404"
+            );
+        }
+
+        #[test]
+        fn in_group() {
+            let regex = regex::Regex::new("(\\d|\\.)+").unwrap();
+            assert_eq!(compare_regex_with_expression(&regex, "v1.2.3"), "1.2.3");
+        }
+    }
+
+    mod unicode {
+        use super::*;
+
+        #[test]
+        fn literals() {
+            let regex = regex::Regex::new("canción").unwrap();
+            let word = "una canción".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "canción".to_string()
+            );
+            let regex = regex::Regex::new("^ñandú$").unwrap();
+            let word = "ñandú".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), word);
+        }
+
+        #[test]
+        fn wildcard() {
+            let regex = regex::Regex::new("can.i.n").unwrap();
+            let word = "canción".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), word);
+            let regex = regex::Regex::new("^.{5}$").unwrap();
+            let word = "ñandú".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), word);
+        }
+
+        #[test]
+        fn classes() {
+            let regex = regex::Regex::new("[[:alpha:]]+").unwrap();
+            let word = "¿ñandú?".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "ñandú".to_string()
+            );
+            let regex = regex::Regex::new("[[:upper:]][[:lower:]]+").unwrap();
+            let word = "el Ñandú".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "Ñandú".to_string()
+            );
+            let regex = regex::Regex::new("[[:punct:]]").unwrap();
+            let word = "hola¡".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "¡".to_string()
+            );
+        }
+
+        #[test]
+        fn brackets() {
+            let regex = regex::Regex::new("[áéíóú]").unwrap();
+            let word = "canción".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "ó".to_string()
+            );
+            let regex = regex::Regex::new("[^a-z]").unwrap();
+            let word = "abñ".to_string();
+            assert_eq!(
+                compare_regex_with_expression(&regex, &word),
+                "ñ".to_string()
+            );
+        }
+
+        #[test]
+        fn ascii_mode() {
+            let regex = regex::Regex::new("[[:alpha:]]+").unwrap();
+            let program = Program::with_options(
+                &regex,
+                RegexOptions {
+                    ascii: true,
+                    ..Default::default()
+                },
+            );
+            assert_eq!(find_match(&program, "ñandú"), Ok(Some((2, 5))));
+            let program = Program::new(&regex);
+            assert_eq!(find_match(&program, "ñandú"), Ok(Some((0, 7))));
+        }
+    }
+
+    mod ignore_case {
+        use super::*;
+
+        fn find(expression: &str, word: &str) -> Option<(usize, usize)> {
+            let regex = regex::Regex::new(expression).unwrap();
+            let options = RegexOptions {
+                ignore_case: true,
+                ..Default::default()
+            };
+            find_match(&Program::with_options(&regex, options), word).unwrap()
+        }
+
+        #[test]
+        fn literals() {
+            assert_eq!(find("hola", "dijo HoLa"), Some((5, 9)));
+            assert_eq!(find("HOLA", "hola"), Some((0, 4)));
+            assert_eq!(find("hola", "chau"), None);
+        }
+
+        #[test]
+        fn sets_and_ranges() {
+            assert_eq!(find("[a-c]+", "xAbC"), Some((1, 4)));
+            assert_eq!(find("[^a]", "A"), None);
+            assert_eq!(find("[[:lower:]]+", "ABC"), Some((0, 3)));
+        }
+
+        #[test]
+        fn unicode() {
+            assert_eq!(find("ñandú", "ÑANDÚ"), Some((0, 7)));
+            assert_eq!(find("σ", "Σ"), Some((0, 2)));
+        }
+
+        #[test]
+        fn case_sensitive_by_default() {
+            let regex = regex::Regex::new("hola").unwrap();
+            assert_eq!(find_match(&Program::new(&regex), "HOLA"), Ok(None));
+        }
+
+        #[test]
+        fn other_cases_of_char() {
+            assert_eq!(other_cases('Ñ'), vec!['ñ']);
+            assert_eq!(other_cases('a'), vec!['A']);
+            assert_eq!(other_cases('1'), vec![]);
+        }
+
+        #[test]
+        fn case_orbits() {
+            assert_eq!(other_cases('s'), vec!['S', '\u{17F}']);
+            assert_eq!(other_cases('\u{17F}'), vec!['S', 's']);
+            assert_eq!(other_cases('\u{212A}'), vec!['K', 'k']);
+            assert_eq!(other_cases('\u{3C2}'), vec!['\u{3A3}', '\u{3C3}']);
+            assert_eq!(other_cases('\u{131}'), vec![]);
+            assert_eq!(find("s", "\u{17F}"), Some((0, 2)));
+            assert_eq!(find("k", "\u{212A}"), Some((0, 3)));
+            assert_eq!(find("\u{212A}", "K"), Some((0, 1)));
+            assert_eq!(find("[a-z]+", "\u{17F}\u{212A}"), Some((0, 5)));
+            assert_eq!(find("σ", "ς"), Some((0, 2)));
+        }
+    }
+
+    mod spans {
+        use super::*;
+
+        fn find_all(expression: &str, word: &str) -> Vec<(usize, usize)> {
+            let programs = vec![Program::new(&regex::Regex::new(expression).unwrap())];
+            find_iter(&programs, word)
+                .unwrap()
+                .iter()
+                .map(|m| (m.start, m.end))
+                .collect()
+        }
+
+        #[test]
+        fn every_match() {
+            assert_eq!(find_all("ab", "ab xab ab"), vec![(0, 2), (4, 6), (7, 9)]);
+            assert_eq!(find_all("o+", "foo boo"), vec![(1, 3), (5, 7)]);
+            assert_eq!(find_all("x", "abc"), vec![]);
+        }
+
+        #[test]
+        fn empty_matches() {
+            assert_eq!(find_all("a*", "baab"), vec![(0, 0), (1, 3), (4, 4)]);
+            assert_eq!(find_all("a*", "aaa"), vec![(0, 3)]);
+            assert_eq!(find_all("x*", "ñ"), vec![(0, 0), (2, 2)]);
+        }
+
+        #[test]
+        fn anchors() {
+            assert_eq!(find_all("^a", "aaa"), vec![(0, 1)]);
+            assert_eq!(find_all("a$", "aaa"), vec![(2, 3)]);
+        }
+
+        #[test]
+        fn several_programs() {
+            let programs = vec![
+                Program::new(&regex::Regex::new("ab").unwrap()),
+                Program::new(&regex::Regex::new("abc").unwrap()),
+                Program::new(&regex::Regex::new("x").unwrap()),
+            ];
+            let result = find_iter(&programs, "abcxab").unwrap();
+            assert_eq!(
+                result,
+                vec![
+                    Match { start: 0, end: 3 },
+                    Match { start: 3, end: 4 },
+                    Match { start: 4, end: 6 },
+                ]
+            );
+            assert_eq!(result[0].as_str("abcxab"), "abc");
+        }
+
+        #[test]
+        fn captures_of_several_programs() {
+            let programs = vec![
+                Program::new(&regex::Regex::new("(a)(b)?").unwrap()),
+                Program::new(&regex::Regex::new("a(bc)").unwrap()),
+            ];
+            let result = captures_iter(&programs, "abc a").unwrap();
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0].get(1), Some(Match { start: 1, end: 3 }));
+            assert_eq!(result[1].get(2), None);
+        }
+
+        #[test]
+        fn longest_alternative() {
+            let regexes =
+                crate::regular_expressions::create_regular_expressions("ab|abab|b").unwrap();
+            let programs: Vec<Program> = regexes.iter().map(Program::new).collect();
+            assert_eq!(
+                find_at(&programs, "xabab", 0),
+                Ok(Some(Match { start: 1, end: 5 }))
+            );
+            assert_eq!(
+                find_match(
+                    &Program::new(&regex::Regex::new("ab|abab|b").unwrap()),
+                    "xabab"
+                ),
+                Ok(Some((1, 5)))
+            );
+        }
+    }
+
+    mod captures {
+        use super::*;
+
+        fn program(expression: &str) -> Program {
+            Program::new(&regex::Regex::new(expression).unwrap())
+        }
+
+        #[test]
+        fn groups() {
+            let word = "key=value";
+            let found = captures(&program("([a-z]+)=([a-z]+)"), word)
+                .unwrap()
+                .unwrap();
+            assert_eq!(found.len(), 3);
+            assert_eq!(found.get(0).unwrap().as_str(word), "key=value");
+            assert_eq!(found.get(1).unwrap().as_str(word), "key");
+            assert_eq!(found.get(2).unwrap().as_str(word), "value");
+        }
+
+        #[test]
+        fn nested_groups() {
+            let word = "abc";
+            let found = captures(&program("(a(b)c)"), word).unwrap().unwrap();
+            assert_eq!(found.get(1).unwrap().as_str(word), "abc");
+            assert_eq!(found.get(2).unwrap().as_str(word), "b");
+        }
+
+        #[test]
+        fn no_match() {
+            assert_eq!(captures(&program("(a)"), "b"), Ok(None));
+        }
+
+        #[test]
+        fn duplicated_tokens() {
+            let regex = regex::Regex::new("([a-z]+) \\1").unwrap();
+            let word = "path = /usr /usr".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), "");
+            let word = "name = foo foo".to_string();
+            assert_eq!(compare_regex_with_expression(&regex, &word), "foo foo");
+        }
+
+        #[test]
+        fn long_groups_without_backtracking() {
+            let word = format!("{}x", "a".repeat(2000));
+            let found = captures(&program("([a-z]{1,500})x"), &word)
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                found.get(1),
+                Some(Match {
+                    start: 1500,
+                    end: 2000
+                })
+            );
+        }
+
+        #[test]
+        fn backreferences_in_iter() {
+            let programs = vec![program("(.)\\1")];
+            let result = find_iter(&programs, "aabccd").unwrap();
+            assert_eq!(
+                result,
+                vec![Match { start: 0, end: 2 }, Match { start: 3, end: 5 }]
+            );
+        }
+    }
+
+    mod words {
+        use super::*;
+
+        fn find(expression: &str, word: &str) -> Option<(usize, usize)> {
+            find_match(&Program::new(&regex::Regex::new(expression).unwrap()), word).unwrap()
+        }
+
+        #[test]
+        fn boundaries() {
+            assert_eq!(find("\\bid\\b", "idx = id"), Some((6, 8)));
+            assert_eq!(find("\\bid\\b", "idx = ids"), None);
+            assert_eq!(find("\\Bd\\B", "d ad"), None);
+            assert_eq!(find("\\Bd", "d add"), Some((3, 4)));
+            assert_eq!(find("\\b", ""), None);
+            assert_eq!(find("\\B", ""), Some((0, 0)));
+        }
+
+        #[test]
+        fn start_and_end_of_word() {
+            assert_eq!(find("\\<a", "ba a"), Some((3, 4)));
+            assert_eq!(find("a\\>", "ab a"), Some((3, 4)));
+            assert_eq!(find("\\<[a-z]+\\>", "_x abc"), Some((3, 6)));
+            assert_eq!(find("\\>", "  "), None);
+        }
+
+        #[test]
+        fn unicode_words() {
+            assert_eq!(find("\\bñandú\\b", "el ñandú."), Some((3, 10)));
+            assert_eq!(find("\\bandú", "ñandú"), None);
+        }
+
+        #[test]
+        fn whole_word() {
+            let regex = regex::Regex::new("id|i").unwrap().whole_word();
+            assert_eq!(compare_regex_with_expression(&regex, "idx i id"), "i");
+            let regex = regex::Regex::new("^(a)b").unwrap().whole_word();
+            assert_eq!(compare_regex_with_expression(&regex, "ab c"), "ab");
+            assert_eq!(compare_regex_with_expression(&regex, "abc"), "");
+        }
+
+        #[test]
+        fn whole_word_of_chars_out_of_words() {
+            let regex = regex::Regex::new("@").unwrap().whole_word();
+            assert_eq!(compare_regex_with_expression(&regex, " a @ b"), "@");
+            assert_eq!(compare_regex_with_expression(&regex, "@b"), "");
+            let regex = regex::Regex::new("-x").unwrap().whole_word();
+            assert_eq!(compare_regex_with_expression(&regex, "ls -x"), "-x");
+            let regex = regex::Regex::new("a[a-z ]*").unwrap().whole_word();
+            assert_eq!(compare_regex_with_expression(&regex, "ab cd_"), "ab");
+            let regex = regex::Regex::new("a").unwrap().whole_word();
+            let program = Program::new(&regex);
+            assert_eq!(find_match(&program, "ba a"), Ok(Some((3, 4))));
+        }
+
+        #[test]
+        fn whole_line() {
+            let regex = regex::Regex::new("a|ab").unwrap().whole_line();
+            assert_eq!(compare_regex_with_expression(&regex, "ab"), "ab");
+            assert_eq!(compare_regex_with_expression(&regex, "abc"), "");
+            let regex = regex::Regex::new("^a").unwrap().whole_line();
+            assert_eq!(compare_regex_with_expression(&regex, "a"), "a");
+            assert_eq!(compare_regex_with_expression(&regex, "ab"), "");
+        }
+    }
 }
@@ -1,5 +1,7 @@
+use crate::regex_error::RegexError;
+
 /// Enum to represent the different classes of characters
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RegexClass {
     /// Represents the class of alphabetic characters
     Alpha,
@@ -11,22 +13,35 @@ pub enum RegexClass {
     Lower,
     /// Represents the class of uppercase characters
     Upper,
-    /// Represents the class of punctuation characters
-    Space,
     /// Represents the class of space characters
+    Space,
+    /// Represents the class of punctuation characters
     Punct,
+    /// Represents the class of hexadecimal digit characters
+    Xdigit,
+    /// Represents the class of blank characters, space and tab
+    Blank,
+    /// Represents the class of control characters
+    Cntrl,
+    /// Represents the class of visible characters
+    Graph,
+    /// Represents the class of printable characters, the visible ones and space
+    Print,
+    /// Represents the class of word characters, alphanumeric and underscore ( \w )
+    Word,
 }
 
 /// Function to handle metacharacters
 /// - It receives a string and returns a Result with the RegexClass or an error
 /// # Arguments
 /// * `n` - A string that represents a metacharacter
+/// * `position` - The position of the brackets in the expression
 /// # Returns
 /// * A Result with the RegexClass or an error
 /// # Example
-/// let result = handle_metachar(":alpha:".to_string());
+/// let result = handle_metachar(":alpha:".to_string(), 0);
 /// assert_eq!(result, Ok(RegexClass::Alpha));
-pub fn handle_metachar(n: String) -> Result<RegexClass, &'static str> {
+pub fn handle_metachar(n: String, position: usize) -> Result<RegexClass, RegexError> {
     Ok(match n.as_str() {
         ":alpha:" => RegexClass::Alpha,
         ":alnum:" => RegexClass::Alnum,
@@ -35,6 +50,11 @@ pub fn handle_metachar(n: String) -> Result<RegexClass, &'static str> {
         ":upper:" => RegexClass::Upper,
         ":punct:" => RegexClass::Punct,
         ":space:" => RegexClass::Space,
-        _ => return Err("Invalid metacharacter"),
+        ":xdigit:" => RegexClass::Xdigit,
+        ":blank:" => RegexClass::Blank,
+        ":cntrl:" => RegexClass::Cntrl,
+        ":graph:" => RegexClass::Graph,
+        ":print:" => RegexClass::Print,
+        _ => return Err(RegexError::InvalidClass { position }),
     })
 }
@@ -0,0 +1,513 @@
+use crate::matching::{is_end_of_line, is_start_of_line};
+use crate::prefilter::required_literal;
+use crate::regex::Regex;
+use crate::regex_options::RegexOptions;
+use crate::regex_rep::RegexRep;
+use crate::regex_step::RegexStep;
+use crate::regex_val::RegexValue;
+use crate::type_of_line::RegexRestriction;
+
+/// Enum to represent an instruction of the Thompson NFA
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inst {
+    /// Consumes a character that matches the value
+    Char(RegexValue),
+    /// Continues the execution in both instructions
+    Split(usize, usize),
+    /// Continues the execution in the instruction
+    Jump(usize),
+    /// Continues the execution only if the restriction holds at the current position
+    Assert(RegexRestriction),
+    /// Saves the current position in a slot, the group n starts at the slot 2n and ends at 2n + 1
+    Save(usize),
+    /// Consumes the same text that the group matched
+    Backref(usize),
+    /// Starts an atomic part, once it matches the other ways of matching it are discarded
+    Atomic,
+    /// Ends an atomic part, discarding the ways of matching it that weren't tried
+    Commit,
+    /// The regex matched
+    Match,
+}
+
+/// Struct to represent a regex compiled into a Thompson NFA
+/// - Every instruction is a state of the automaton, the start state is the first one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    /// The instructions of the automaton
+    pub insts: Vec<Inst>,
+    /// Whether the match must start at the beginning of the word ( ^ )
+    /// - It lets the simulation stop trying new start positions
+    pub start_of_line: bool,
+    /// The options used to match the characters
+    pub options: RegexOptions,
+    /// The number of capturing groups, the slots are two for each one and two for the whole match
+    pub groups: usize,
+    /// The name of each capturing group, by its number, None for the groups without a name
+    /// - It is empty when no group has a name
+    pub names: Vec<Option<String>>,
+    /// Whether there are backreferences, that the NFA simulation can't match
+    pub has_backrefs: bool,
+    /// Whether there are atomic parts, from possessive quantifiers, that the NFA simulation can't match
+    pub has_atomic: bool,
+    /// A literal that every match contains, used to skip the words that can't match
+    /// - It is None when there is no such literal, or when the case is ignored
+    pub literal: Option<String>,
+    /// The steps of the regex, to decide the groups of a match by the POSIX rules
+    /// - It is empty for the programs that only say which regex matched, like the one of a RegexSet
+    pub steps: Vec<RegexStep>,
+}
+
+/// Implementation of the Program struct
+impl Program {
+    /// Compiles a Regex into a Program with the default options
+    /// # Arguments
+    /// * `regex` - A reference to a Regex
+    /// # Returns
+    /// * A Program
+    /// # Example
+    /// let regex = Regex::new("ab").unwrap();
+    /// let program = Program::new(&regex);
+    /// assert_eq!(program.insts, vec![Inst::Char(RegexValue::Literal('a')), Inst::Char(RegexValue::Literal('b')), Inst::Match]);
+    pub fn new(regex: &Regex) -> Self {
+        Program::with_options(regex, RegexOptions::default())
+    }
+
+    /// Compiles a Regex into a Program with the given options
+    /// # Arguments
+    /// * `regex` - A reference to a Regex
+    /// * `options` - A RegexOptions
+    /// # Returns
+    /// * A Program
+    /// # Example
+    /// let regex = Regex::new("[[:alpha:]]").unwrap();
+    /// let program = Program::with_options(&regex, RegexOptions { ascii: true, ..Default::default() });
+    /// assert_eq!(program.options.ascii, true);
+    pub fn with_options(regex: &Regex, options: RegexOptions) -> Self {
+        let start_of_line = is_start_of_line(&regex.backtracking);
+        let mut insts = Vec::new();
+        if start_of_line {
+            insts.push(Inst::Assert(RegexRestriction::StartOfLine));
+        }
+        compile_steps(&regex.steps, &mut insts);
+        if is_end_of_line(&regex.backtracking) {
+            insts.push(Inst::Assert(RegexRestriction::EndOfLine));
+        }
+        insts.push(Inst::Match);
+
+        let mut groups = 0;
+        let mut has_backrefs = false;
+        let mut has_atomic = false;
+        for inst in insts.iter() {
+            match inst {
+                Inst::Save(slot) => groups = groups.max(slot / 2),
+                Inst::Backref(group) => {
+                    groups = groups.max(*group);
+                    has_backrefs = true;
+                }
+                Inst::Atomic => has_atomic = true,
+                _ => {}
+            }
+        }
+
+        let mut names = Vec::new();
+        group_names(&regex.steps, &mut names);
+        if names.iter().any(Option::is_some) {
+            names.resize(groups + 1, None);
+        } else {
+            names.clear();
+        }
+
+        let literal = match options.ignore_case {
+            true => None,
+            false => required_literal(&regex.steps),
+        };
+
+        Program {
+            insts,
+            start_of_line,
+            options,
+            groups,
+            names,
+            has_backrefs,
+            has_atomic,
+            literal,
+            steps: regex.steps.clone(),
+        }
+    }
+
+    /// Checks if the program must be matched with backtracking instead of the NFA simulation
+    /// - The backreferences and the atomic parts need it, and so do the Perl matches, since the
+    ///   simulation finds the longest match
+    /// # Returns
+    /// * A boolean
+    pub fn needs_backtracking(&self) -> bool {
+        self.has_backrefs || self.has_atomic || self.options.perl
+    }
+
+    /// Checks if the word contains the required literal, a word without it can't match
+    /// - The substring search of str is a two-way search, much faster than running the automaton
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A boolean, true if there is no required literal
+    /// # Example
+    /// let program = Program::new(&Regex::new("ERROR [[:digit:]]+").unwrap());
+    /// assert_eq!(program.may_match("ERROR 42"), true);
+    /// assert_eq!(program.may_match("WARN 42"), false);
+    pub fn may_match(&self, word: &str) -> bool {
+        self.literal
+            .as_ref()
+            .is_none_or(|literal| word.contains(literal.as_str()))
+    }
+}
+
+/// Compiles a list of steps, one after the other
+fn compile_steps(steps: &[RegexStep], insts: &mut Vec<Inst>) {
+    for step in steps {
+        compile_step(step, insts);
+    }
+}
+
+/// Compiles a step according to its repetition
+/// - `Exact(n)` repeats the value n times
+/// - `Range { min, max }` repeats the value min times and then adds max - min optional copies,
+///   or a loop if there is no max
+/// - `Lazy` is a range whose splits prefer to skip the copies, and `Possessive` is a range inside an atomic part
+fn compile_step(step: &RegexStep, insts: &mut Vec<Inst>) {
+    let (min, max) = step.rep.bounds();
+    match step.rep {
+        RegexRep::Exact(count) => {
+            for _ in 0..count {
+                compile_value(&step.val, insts);
+            }
+        }
+        RegexRep::Range { .. } => compile_range(&step.val, min, max, false, insts),
+        RegexRep::Lazy { .. } => compile_range(&step.val, min, max, true, insts),
+        RegexRep::Possessive { .. } => {
+            insts.push(Inst::Atomic);
+            compile_range(&step.val, min, max, false, insts);
+            insts.push(Inst::Commit);
+        }
+    }
+}
+
+/// Compiles min copies of a value and then max - min optional copies, or a loop if there is no max
+fn compile_range(
+    val: &RegexValue,
+    min: usize,
+    max: Option<usize>,
+    lazy: bool,
+    insts: &mut Vec<Inst>,
+) {
+    for _ in 0..min {
+        compile_value(val, insts);
+    }
+    match max {
+        Some(max) => compile_optionals(val, max.saturating_sub(min), lazy, insts),
+        None => compile_loop(val, lazy, insts),
+    }
+}
+
+/// Returns a split between the next instruction and the end, in the order of priority
+fn split(next: usize, end: usize, lazy: bool) -> Inst {
+    match lazy {
+        true => Inst::Split(end, next),
+        false => Inst::Split(next, end),
+    }
+}
+
+/// Compiles a value that must be matched once
+/// - Groups compile all their alternatives, restrictions become assertions
+/// - Capturing groups save their start and end positions around their alternatives
+fn compile_value(val: &RegexValue, insts: &mut Vec<Inst>) {
+    match val {
+        RegexValue::Group(alternatives) => compile_alternatives(alternatives, insts),
+        RegexValue::Capture(index, alternatives)
+        | RegexValue::NamedCapture(index, _, alternatives) => {
+            insts.push(Inst::Save(2 * index));
+            compile_alternatives(alternatives, insts);
+            insts.push(Inst::Save(2 * index + 1));
+        }
+        RegexValue::Backreference(group) => insts.push(Inst::Backref(*group)),
+        RegexValue::Restriction(restriction) => insts.push(Inst::Assert(restriction.clone())),
+        _ => insts.push(Inst::Char(val.clone())),
+    }
+}
+
+/// Compiles the alternatives of a group
+/// - Each alternative but the last one is preceded by a split that can skip it, and
+///   followed by a jump to the end of the group
+fn compile_alternatives(alternatives: &[Vec<RegexStep>], insts: &mut Vec<Inst>) {
+    let mut holes = Vec::new();
+    for (index, steps) in alternatives.iter().enumerate() {
+        if index == alternatives.len() - 1 {
+            compile_steps(steps, insts);
+            break;
+        }
+        let split = insts.len();
+        insts.push(Inst::Split(split + 1, 0));
+        compile_steps(steps, insts);
+        holes.push(insts.len());
+        insts.push(Inst::Jump(0));
+        insts[split] = Inst::Split(split + 1, insts.len());
+    }
+    let end = insts.len();
+    for hole in holes {
+        insts[hole] = Inst::Jump(end);
+    }
+}
+
+/// Compiles `count` optional copies of a value
+/// - Each split skips the rest of the copies, so `a{0,2}` is `(a(a)?)?`
+fn compile_optionals(val: &RegexValue, count: usize, lazy: bool, insts: &mut Vec<Inst>) {
+    let mut holes = Vec::new();
+    for _ in 0..count {
+        holes.push(insts.len());
+        insts.push(Inst::Split(insts.len() + 1, 0));
+        compile_value(val, insts);
+    }
+    let end = insts.len();
+    for hole in holes {
+        insts[hole] = split(hole + 1, end, lazy);
+    }
+}
+
+/// Compiles a value that can be consumed any number of times
+fn compile_loop(val: &RegexValue, lazy: bool, insts: &mut Vec<Inst>) {
+    let hole = insts.len();
+    insts.push(Inst::Split(hole + 1, 0));
+    compile_value(val, insts);
+    insts.push(Inst::Jump(hole));
+    let end = insts.len();
+    insts[hole] = split(hole + 1, end, lazy);
+}
+
+/// Adds the names of the groups of the steps, at the position of their number
+fn group_names(steps: &[RegexStep], names: &mut Vec<Option<String>>) {
+    for step in steps {
+        let alternatives = match &step.val {
+            RegexValue::Group(alternatives) | RegexValue::Capture(_, alternatives) => alternatives,
+            RegexValue::NamedCapture(index, name, alternatives) => {
+                if names.len() <= *index {
+                    names.resize(index + 1, None);
+                }
+                names[*index] = Some(name.clone());
+                alternatives
+            }
+            _ => continue,
+        };
+        for alternative in alternatives {
+            group_names(alternative, names);
+        }
+    }
+}
+
+/// Returns the number of instructions that the steps compile to, without compiling them
+/// - It follows the compilation of each step, so it can be checked before building a huge Program
+/// # Arguments
+/// * `steps` - A slice of RegexStep
+/// # Returns
+/// * A usize, saturated if it doesn't fit
+/// # Example
+/// let regex = Regex::new("a{2,3}").unwrap();
+/// assert_eq!(compiled_size(&regex.steps), 4);
+pub fn compiled_size(steps: &[RegexStep]) -> usize {
+    steps
+        .iter()
+        .fold(0, |size, step| size.saturating_add(step_size(step)))
+}
+
+/// Returns the number of instructions of a step according to its repetition, like compile_step
+fn step_size(step: &RegexStep) -> usize {
+    let value = value_size(&step.val);
+    let (min, max) = step.rep.bounds();
+    if let RegexRep::Exact(count) = step.rep {
+        return count.saturating_mul(value);
+    }
+    let optionals = match max {
+        // A split and a copy for each optional copy
+        Some(max) => max
+            .saturating_sub(min)
+            .saturating_mul(value.saturating_add(1)),
+        // A split, a copy and a jump
+        None => value.saturating_add(2),
+    };
+    let size = min.saturating_mul(value).saturating_add(optionals);
+    match step.rep {
+        // The start and the end of the atomic part
+        RegexRep::Possessive { .. } => size.saturating_add(2),
+        _ => size,
+    }
+}
+
+/// Returns the number of instructions of a value matched once, like compile_value
+fn value_size(val: &RegexValue) -> usize {
+    match val {
+        RegexValue::Group(alternatives) => alternatives_size(alternatives),
+        RegexValue::Capture(_, alternatives) | RegexValue::NamedCapture(_, _, alternatives) => {
+            alternatives_size(alternatives).saturating_add(2)
+        }
+        _ => 1,
+    }
+}
+
+/// Returns the number of instructions of the alternatives of a group, with a split and a jump between them
+fn alternatives_size(alternatives: &[Vec<RegexStep>]) -> usize {
+    alternatives
+        .iter()
+        .fold(2 * alternatives.len().saturating_sub(1), |size, steps| {
+            size.saturating_add(compiled_size(steps))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Syntax;
+
+    #[test]
+    fn literals() {
+        let program = Program::new(&Regex::new("ab").unwrap());
+        assert_eq!(
+            program.insts,
+            vec![
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Char(RegexValue::Literal('b')),
+                Inst::Match,
+            ]
+        );
+        assert!(!program.start_of_line);
+    }
+
+    #[test]
+    fn any() {
+        let program = Program::new(&Regex::new("a*").unwrap());
+        assert_eq!(
+            program.insts,
+            vec![
+                Inst::Split(1, 3),
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Jump(0),
+                Inst::Match,
+            ]
+        );
+    }
+
+    #[test]
+    fn range() {
+        let program = Program::new(&Regex::new("a{1,3}").unwrap());
+        assert_eq!(
+            program.insts,
+            vec![
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Split(2, 5),
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Split(4, 5),
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Match,
+            ]
+        );
+    }
+
+    #[test]
+    fn alternatives() {
+        let program = Program::new(&Regex::new("a|bc").unwrap());
+        assert_eq!(
+            program.insts,
+            vec![
+                Inst::Split(1, 3),
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Jump(5),
+                Inst::Char(RegexValue::Literal('b')),
+                Inst::Char(RegexValue::Literal('c')),
+                Inst::Match,
+            ]
+        );
+    }
+
+    #[test]
+    fn lazy_and_possessive() {
+        let syntax = Syntax {
+            perl: true,
+            ..Default::default()
+        };
+        let program = Program::new(&Regex::with_syntax("a*?", &syntax).unwrap());
+        assert_eq!(
+            program.insts,
+            vec![
+                Inst::Split(3, 1),
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Jump(0),
+                Inst::Match,
+            ]
+        );
+        let program = Program::new(&Regex::with_syntax("a?+", &syntax).unwrap());
+        assert_eq!(
+            program.insts,
+            vec![
+                Inst::Atomic,
+                Inst::Split(2, 3),
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Commit,
+                Inst::Match,
+            ]
+        );
+        assert!(program.has_atomic);
+        assert!(program.needs_backtracking());
+    }
+
+    #[test]
+    fn size() {
+        let syntax = Syntax {
+            perl: true,
+            ..Default::default()
+        };
+        let expressions = [
+            "ab",
+            "a{2,3}",
+            "(a|bc)*d",
+            "x{2}(y|z){1,}",
+            "(a(b)?){3}",
+            "a*+b+?",
+        ];
+        for expression in expressions {
+            let regex = Regex::with_syntax(expression, &syntax).unwrap();
+            let program = Program::new(&regex);
+            // The program ends with a Match
+            assert_eq!(compiled_size(&regex.steps), program.insts.len() - 1);
+        }
+    }
+
+    #[test]
+    fn captures() {
+        let program = Program::new(&Regex::new("(a)\\1").unwrap());
+        assert_eq!(
+            program.insts,
+            vec![
+                Inst::Save(2),
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Save(3),
+                Inst::Backref(1),
+                Inst::Match,
+            ]
+        );
+        assert_eq!(program.groups, 1);
+        assert!(program.has_backrefs);
+    }
+
+    #[test]
+    fn restrictions() {
+        let program = Program::new(&Regex::new("^a$").unwrap());
+        assert_eq!(
+            program.insts,
+            vec![
+                Inst::Assert(RegexRestriction::StartOfLine),
+                Inst::Char(RegexValue::Literal('a')),
+                Inst::Assert(RegexRestriction::EndOfLine),
+                Inst::Match,
+            ]
+        );
+        assert!(program.start_of_line);
+    }
+}
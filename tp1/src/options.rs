@@ -0,0 +1,481 @@
+use crate::regex_options::RegexOptions;
+
+/// Enum to represent when the output is colored ( --color=WHEN )
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ColorChoice {
+    /// Never color the output
+    #[default]
+    Never,
+    /// Always color the output
+    Always,
+    /// Color the output only if it goes to a terminal
+    Auto,
+}
+
+/// Struct to represent the options of the command line, like the ones of GNU grep
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    /// The expressions given with -e, or the first argument
+    pub patterns: Vec<String>,
+    /// The files given with -f, each line of them is an expression
+    pub pattern_files: Vec<String>,
+    /// The paths to search in, - is the standard input
+    pub paths: Vec<String>,
+    /// Whether the path is the current directory because -r was given without paths, so ./ isn't shown
+    pub current_directory: bool,
+    /// Searches the files of the directories ( -r )
+    pub recursive: bool,
+    /// Searches the files of the directories, following every symbolic link ( -R )
+    pub dereference_recursive: bool,
+    /// Searches only the files whose name matches one of these globs ( --include )
+    pub include: Vec<String>,
+    /// Skips the files whose name matches one of these globs ( --exclude )
+    pub exclude: Vec<String>,
+    /// Selects the lines that don't match ( -v )
+    pub invert: bool,
+    /// Prints only the number of selected lines ( -c )
+    pub count: bool,
+    /// Prints the number of each line ( -n )
+    pub line_number: bool,
+    /// Prints only the names of the files with selected lines ( -l )
+    pub files_with_matches: bool,
+    /// Prints only the names of the files without selected lines ( -L )
+    pub files_without_match: bool,
+    /// Prints only the matched part of the lines ( -o )
+    pub only_matching: bool,
+    /// Selects only the matches that are whole words ( -w )
+    pub word_regexp: bool,
+    /// Selects only the matches that are the whole line ( -x )
+    pub line_regexp: bool,
+    /// Searches the binary files like text, printing their lines instead of "Binary file X matches" ( -a )
+    pub text: bool,
+    /// Prints nothing, only the exit code matters ( -q )
+    pub quiet: bool,
+    /// Stops reading a file after this number of selected lines ( -m NUM )
+    pub max_count: Option<usize>,
+    /// Whether to print the file name before each line ( -H / -h ), by default only with several files
+    pub with_filename: Option<bool>,
+    /// The number of lines to print after each selected line ( -A NUM )
+    pub after_context: Option<usize>,
+    /// The number of lines to print before each selected line ( -B NUM )
+    pub before_context: Option<usize>,
+    /// The number of lines to print around each selected line, unless -A or -B say otherwise ( -C NUM )
+    pub context: Option<usize>,
+    /// When to color the matches, the file names and the line numbers
+    pub color: ColorChoice,
+    /// The options used to match the expressions
+    pub regex_options: RegexOptions,
+    /// The template that replaces the matches in the printed lines ( --replace )
+    pub replace: Option<String>,
+    /// Writes the replacements to the files instead of printing them ( --write )
+    pub write: bool,
+    /// Prints the matches as JSON Lines, with the events of ripgrep's --json ( --json )
+    pub json: bool,
+}
+
+/// Implementation of the Options struct
+impl Options {
+    /// Returns the number of lines to print after each selected line
+    /// # Returns
+    /// * A usize
+    /// # Example
+    /// let options = Options { context: Some(2), after_context: Some(1), ..Default::default() };
+    /// assert_eq!(options.lines_after(), 1);
+    pub fn lines_after(&self) -> usize {
+        self.after_context.or(self.context).unwrap_or(0)
+    }
+
+    /// Returns the number of lines to print before each selected line
+    /// # Returns
+    /// * A usize
+    /// # Example
+    /// let options = Options { context: Some(2), after_context: Some(1), ..Default::default() };
+    /// assert_eq!(options.lines_before(), 2);
+    pub fn lines_before(&self) -> usize {
+        self.before_context.or(self.context).unwrap_or(0)
+    }
+
+    /// Returns whether a context option was given, even with 0 lines, so the groups of lines are separated by "--"
+    /// # Returns
+    /// * A boolean
+    /// # Example
+    /// let options = Options { after_context: Some(0), ..Default::default() };
+    /// assert!(options.has_context());
+    pub fn has_context(&self) -> bool {
+        self.context.is_some() || self.after_context.is_some() || self.before_context.is_some()
+    }
+}
+
+/// The usage message of the program
+pub const USAGE: &str = "Usage: tp1 [OPTION]... PATTERNS [FILE]...";
+
+/// Function to parse the arguments of the command line, without the name of the program
+/// - Short options can be grouped, like -in, and their value can be attached, like -m2
+/// - Long options receive their value after a = or as the next argument, like --max-count=2
+/// - Everything after -- is taken as an argument
+/// - If there is no -e or -f, the first argument is the expression
+/// - Without paths the standard input is searched, or the current directory with -r
+/// # Arguments
+/// * `args` - A slice of Strings
+/// # Returns
+/// * A Result with the Options or the message of the error
+/// # Example
+/// let args = vec!["-in".to_string(), "hola".to_string(), "file.txt".to_string()];
+/// let options = parse_options(&args).unwrap();
+/// assert_eq!(options.patterns, vec!["hola".to_string()]);
+/// assert_eq!(options.line_number, true);
+pub fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut arguments: Vec<String> = Vec::new();
+    let mut args_iter = args.iter();
+
+    while let Some(arg) = args_iter.next() {
+        if arg == "--" {
+            arguments.extend(args_iter.by_ref().cloned());
+        } else if let Some(long) = arg.strip_prefix("--") {
+            handle_long_option(long, &mut args_iter, &mut options)?;
+        } else if arg.len() > 1 && arg.starts_with('-') {
+            handle_short_options(&arg[1..], &mut args_iter, &mut options)?;
+        } else {
+            arguments.push(arg.to_string());
+        }
+    }
+
+    let mut arguments = arguments.into_iter();
+    if options.patterns.is_empty() && options.pattern_files.is_empty() {
+        match arguments.next() {
+            Some(pattern) => options.patterns.push(pattern),
+            None => return Err("Expected an expression".to_string()),
+        }
+    }
+    if options.write && options.replace.is_none() {
+        return Err("Option '--write' requires '--replace'".to_string());
+    }
+    if options.json
+        && (options.count
+            || options.files_with_matches
+            || options.files_without_match
+            || options.write)
+    {
+        return Err("Option '--json' can't be used with -c, -l, -L or --write".to_string());
+    }
+    options.paths = arguments.collect();
+    if options.paths.is_empty() {
+        options.current_directory = options.recursive || options.dereference_recursive;
+        let default = if options.current_directory { "." } else { "-" };
+        options.paths.push(default.to_string());
+    }
+    Ok(options)
+}
+
+/// Function to handle a group of short options, like -in or -m2
+fn handle_short_options(
+    flags: &str,
+    args_iter: &mut std::slice::Iter<String>,
+    options: &mut Options,
+) -> Result<(), String> {
+    for (i, flag) in flags.char_indices() {
+        if matches!(flag, 'e' | 'f' | 'm' | 'A' | 'B' | 'C') {
+            let attached = &flags[i + flag.len_utf8()..];
+            let value = if attached.is_empty() {
+                next_value(&flag.to_string(), args_iter)?
+            } else {
+                attached.to_string()
+            };
+            return handle_value(&flag.to_string(), value, options);
+        }
+        handle_flag(&flag.to_string(), options)?;
+    }
+    Ok(())
+}
+
+/// Function to handle a long option, like --count or --max-count=2
+fn handle_long_option(
+    long: &str,
+    args_iter: &mut std::slice::Iter<String>,
+    options: &mut Options,
+) -> Result<(), String> {
+    let (name, attached) = match long.split_once('=') {
+        Some((name, value)) => (name, Some(value.to_string())),
+        None => (long, None),
+    };
+    if name == "color" || name == "colour" {
+        return handle_color(attached.as_deref(), options);
+    }
+    let short = match name {
+        "regexp" => "e",
+        "file" => "f",
+        "max-count" => "m",
+        "after-context" => "A",
+        "before-context" => "B",
+        "context" => "C",
+        "include" | "exclude" | "replace" => name,
+        _ => {
+            if attached.is_some() {
+                return Err(format!("Option '--{}' doesn't allow an argument", name));
+            }
+            return handle_flag(name, options);
+        }
+    };
+    let value = match attached {
+        Some(value) => value,
+        None => next_value(name, args_iter)?,
+    };
+    handle_value(short, value, options)
+}
+
+/// Function to handle --color, whose value is optional
+fn handle_color(value: Option<&str>, options: &mut Options) -> Result<(), String> {
+    options.color = match value {
+        None | Some("auto") | Some("tty") | Some("if-tty") => ColorChoice::Auto,
+        Some("always") | Some("yes") | Some("force") => ColorChoice::Always,
+        Some("never") | Some("no") | Some("none") => ColorChoice::Never,
+        Some(value) => return Err(format!("Invalid argument '{}' for '--color'", value)),
+    };
+    Ok(())
+}
+
+/// Function to get the value of an option from the next argument
+fn next_value(name: &str, args_iter: &mut std::slice::Iter<String>) -> Result<String, String> {
+    match args_iter.next() {
+        Some(value) => Ok(value.to_string()),
+        None => Err(format!("Option '{}' requires an argument", name)),
+    }
+}
+
+/// Function to handle an option with a value, by its short name
+fn handle_value(name: &str, value: String, options: &mut Options) -> Result<(), String> {
+    match name {
+        "e" => options.patterns.push(value),
+        "f" => options.pattern_files.push(value),
+        "include" => options.include.push(value),
+        "exclude" => options.exclude.push(value),
+        "replace" => options.replace = Some(value),
+        "m" => match value.parse::<usize>() {
+            Ok(max) => options.max_count = Some(max),
+            Err(_) => return Err(format!("Invalid max count '{}'", value)),
+        },
+        _ => {
+            let Ok(lines) = value.parse::<usize>() else {
+                return Err(format!("{}: invalid context length argument", value));
+            };
+            match name {
+                "A" => options.after_context = Some(lines),
+                "B" => options.before_context = Some(lines),
+                _ => options.context = Some(lines),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Function to handle an option without a value, by its short or long name
+fn handle_flag(name: &str, options: &mut Options) -> Result<(), String> {
+    match name {
+        "v" | "invert-match" => options.invert = true,
+        "c" | "count" => options.count = true,
+        "n" | "line-number" => options.line_number = true,
+        "l" | "files-with-matches" => options.files_with_matches = true,
+        "L" | "files-without-match" => options.files_without_match = true,
+        "o" | "only-matching" => options.only_matching = true,
+        "q" | "quiet" | "silent" => options.quiet = true,
+        "a" | "text" => options.text = true,
+        "H" | "with-filename" => options.with_filename = Some(true),
+        "h" | "no-filename" => options.with_filename = Some(false),
+        "i" | "ignore-case" => options.regex_options.ignore_case = true,
+        "ascii" => options.regex_options.ascii = true,
+        "w" | "word-regexp" => options.word_regexp = true,
+        "P" | "perl-regexp" => options.regex_options.perl = true,
+        "x" | "line-regexp" => options.line_regexp = true,
+        "r" | "recursive" => options.recursive = true,
+        "R" | "dereference-recursive" => options.dereference_recursive = true,
+        "write" => options.write = true,
+        "json" => options.json = true,
+        _ if name.len() == 1 => return Err(format!("Invalid option -- '{}'", name)),
+        _ => return Err(format!("Unrecognized option '--{}'", name)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse_options(&args)
+    }
+
+    #[test]
+    fn positional() {
+        let options = parse(&["hola", "a.txt", "b.txt"]).unwrap();
+        assert_eq!(options.patterns, vec!["hola"]);
+        assert_eq!(options.paths, vec!["a.txt", "b.txt"]);
+        assert!(!options.invert);
+    }
+
+    #[test]
+    fn grouped_flags() {
+        let options = parse(&["-vcn", "hola", "a.txt"]).unwrap();
+        assert!(options.invert);
+        assert!(options.count);
+        assert!(options.line_number);
+    }
+
+    #[test]
+    fn flags_after_arguments() {
+        let options = parse(&["hola", "a.txt", "-i", "--only-matching"]).unwrap();
+        assert!(options.regex_options.ignore_case);
+        assert!(options.only_matching);
+        assert_eq!(options.paths, vec!["a.txt"]);
+    }
+
+    #[test]
+    fn ascii() {
+        assert!(parse(&["--ascii", "a", "f"]).unwrap().regex_options.ascii);
+        assert!(!parse(&["a", "f"]).unwrap().regex_options.ascii);
+    }
+
+    #[test]
+    fn max_count() {
+        assert_eq!(parse(&["-m", "2", "a", "f"]).unwrap().max_count, Some(2));
+        assert_eq!(parse(&["-m3", "a", "f"]).unwrap().max_count, Some(3));
+        assert_eq!(
+            parse(&["--max-count=4", "a", "f"]).unwrap().max_count,
+            Some(4)
+        );
+        assert!(parse(&["-m", "x", "a", "f"]).is_err());
+    }
+
+    #[test]
+    fn patterns() {
+        let options = parse(&["-e", "hola", "-echau", "--regexp=-x", "a.txt"]).unwrap();
+        assert_eq!(options.patterns, vec!["hola", "chau", "-x"]);
+        assert_eq!(options.paths, vec!["a.txt"]);
+        let options = parse(&["-f", "patterns.txt", "a.txt"]).unwrap();
+        assert_eq!(options.pattern_files, vec!["patterns.txt"]);
+        assert!(options.patterns.is_empty());
+    }
+
+    #[test]
+    fn filename() {
+        assert_eq!(parse(&["a", "f"]).unwrap().with_filename, None);
+        assert_eq!(parse(&["-H", "a", "f"]).unwrap().with_filename, Some(true));
+        assert_eq!(
+            parse(&["-Hh", "a", "f"]).unwrap().with_filename,
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn word_and_line() {
+        let options = parse(&["-wx", "a", "f"]).unwrap();
+        assert!(options.word_regexp);
+        assert!(options.line_regexp);
+        let options = parse(&["--word-regexp", "a", "f"]).unwrap();
+        assert!(options.word_regexp);
+        assert!(!options.line_regexp);
+    }
+
+    #[test]
+    fn perl() {
+        assert!(parse(&["-P", "a", "f"]).unwrap().regex_options.perl);
+        assert!(
+            parse(&["--perl-regexp", "a", "f"])
+                .unwrap()
+                .regex_options
+                .perl
+        );
+        assert!(!parse(&["a", "f"]).unwrap().regex_options.perl);
+    }
+
+    #[test]
+    fn replace() {
+        let options = parse(&["--replace", "$1", "(a)", "f"]).unwrap();
+        assert_eq!(options.replace, Some("$1".to_string()));
+        assert_eq!(options.paths, vec!["f"]);
+        let options = parse(&["--replace=x", "--write", "a", "f"]).unwrap();
+        assert_eq!(options.replace, Some("x".to_string()));
+        assert!(options.write);
+        assert!(parse(&["--write", "a", "f"]).is_err());
+        assert!(parse(&["-a", "a", "f"]).unwrap().text);
+    }
+
+    #[test]
+    fn json() {
+        assert!(parse(&["--json", "a", "f"]).unwrap().json);
+        assert!(parse(&["--json", "-n", "-C1", "a", "f"]).is_ok());
+        assert!(parse(&["--json", "-c", "a", "f"]).is_err());
+        assert!(parse(&["--json=yes", "a", "f"]).is_err());
+    }
+
+    #[test]
+    fn double_dash() {
+        let options = parse(&["--", "-v", "a.txt"]).unwrap();
+        assert_eq!(options.patterns, vec!["-v"]);
+        assert!(!options.invert);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse(&["-z", "a", "f"]),
+            Err("Invalid option -- 'z'".to_string())
+        );
+        assert_eq!(
+            parse(&["--zeta", "a", "f"]),
+            Err("Unrecognized option '--zeta'".to_string())
+        );
+        assert_eq!(
+            parse(&["a", "f", "-e"]),
+            Err("Option 'e' requires an argument".to_string())
+        );
+        assert_eq!(parse(&[]), Err("Expected an expression".to_string()));
+    }
+
+    #[test]
+    fn context() {
+        let options = parse(&["-A", "1", "-B2", "a", "f"]).unwrap();
+        assert_eq!((options.lines_after(), options.lines_before()), (1, 2));
+        let options = parse(&["-A1", "--context=3", "a", "f"]).unwrap();
+        assert_eq!((options.lines_after(), options.lines_before()), (1, 3));
+        assert!(options.has_context());
+        assert!(parse(&["-C0", "a", "f"]).unwrap().has_context());
+        assert!(!parse(&["a", "f"]).unwrap().has_context());
+        assert_eq!(
+            parse(&["-C", "x", "a", "f"]),
+            Err("x: invalid context length argument".to_string())
+        );
+    }
+
+    #[test]
+    fn color() {
+        assert_eq!(parse(&["a", "f"]).unwrap().color, ColorChoice::Never);
+        assert_eq!(
+            parse(&["--color", "a", "f"]).unwrap().color,
+            ColorChoice::Auto
+        );
+        assert_eq!(
+            parse(&["--colour=always", "a", "f"]).unwrap().color,
+            ColorChoice::Always
+        );
+        assert!(parse(&["--color=blue", "a", "f"]).is_err());
+    }
+
+    #[test]
+    fn default_paths() {
+        assert_eq!(parse(&["a"]).unwrap().paths, vec!["-"]);
+        assert_eq!(parse(&["-r", "a"]).unwrap().paths, vec!["."]);
+        assert!(parse(&["-r", "a"]).unwrap().current_directory);
+        assert!(!parse(&["-r", "a", "."]).unwrap().current_directory);
+        assert_eq!(parse(&["-r", "a", "src"]).unwrap().paths, vec!["src"]);
+    }
+
+    #[test]
+    fn globs() {
+        let options = parse(&["-R", "--include=*.rs", "--exclude", "main.rs", "a"]).unwrap();
+        assert!(options.dereference_recursive);
+        assert_eq!(options.include, vec!["*.rs"]);
+        assert_eq!(options.exclude, vec!["main.rs"]);
+    }
+}
@@ -0,0 +1,742 @@
+use crate::brackets::handle_brackets;
+use crate::exactrep::{handle_escape_sequence, handle_wildcard};
+use crate::nfa::compiled_size;
+use crate::range::{
+    handle_any, handle_exact_plus, handle_quantifier_mode, handle_range, handle_zero_or_one,
+};
+use crate::regex_error::RegexError;
+use crate::regex_rep::RegexRep;
+use crate::regex_step::RegexStep;
+use crate::regex_val::RegexValue;
+use crate::type_of_line::{handle_end_of_line, handle_start_of_line};
+use std::str::Chars;
+
+/// The default limit of instructions that an expression can compile to
+pub const DEFAULT_SIZE_LIMIT: usize = 100_000;
+
+/// Struct to represent the syntax accepted by the parser
+#[derive(Debug, Clone, PartialEq)]
+pub struct Syntax {
+    /// The maximum number of instructions that the expression can compile to
+    /// - The repetitions multiply the size, so a short expression like (a{1000}){1000} can be huge
+    pub size_limit: usize,
+    /// Whether the Perl extensions are accepted ( -P ), the lazy quantifiers like a*? and the possessive ones like a*+
+    pub perl: bool,
+}
+
+impl Default for Syntax {
+    fn default() -> Self {
+        Syntax {
+            size_limit: DEFAULT_SIZE_LIMIT,
+            perl: false,
+        }
+    }
+}
+
+/// Function to parse an expression
+/// - It's a recursive descent parser, the grammar is:
+///   - expression = alternatives
+///   - alternatives = concatenation ( '|' concatenation )*
+///   - concatenation = ( atom quantifier? )*
+///   - atom = '(' name? alternatives ')' | '[' brackets ']' | '\' char | '.' | '^' | '$' | char
+///   - name = '?P<' identifier '>' | '?<' identifier '>'
+/// - It receives a string and returns a Result with the alternatives of the expression, each one a vector of RegexStep
+/// - It uses the default Syntax
+/// # Arguments
+/// * `expression` - A string slice that holds the regex expression
+/// # Returns
+/// * A Result with a vector of alternatives or an error
+/// # Example
+/// let result = parse_expression("a|b");
+/// assert_eq!(result, Ok(vec![vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') }], vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('b') }]]));
+pub fn parse_expression(expression: &str) -> Result<Vec<Vec<RegexStep>>, RegexError> {
+    parse_expression_with(expression, &Syntax::default())
+}
+
+/// Function to parse an expression with a Syntax
+/// - In the Perl syntax a quantifier can be followed by ? to make it lazy or by + to make it possessive
+/// # Arguments
+/// * `expression` - A string slice that holds the regex expression
+/// * `syntax` - A reference to a Syntax
+/// # Returns
+/// * A Result with a vector of alternatives or an error
+/// # Example
+/// let syntax = Syntax { size_limit: 50, ..Default::default() };
+/// let result = parse_expression_with("a{1,100}", &syntax);
+/// assert_eq!(result, Err(RegexError::TooBig { limit: 50 }));
+pub fn parse_expression_with(
+    expression: &str,
+    syntax: &Syntax,
+) -> Result<Vec<Vec<RegexStep>>, RegexError> {
+    let mut chars_iter = expression.chars();
+    let mut groups = Vec::new();
+    let alternatives = parse_alternatives(&mut chars_iter, expression.len(), &mut groups, syntax)?;
+    if !chars_iter.as_str().is_empty() {
+        return Err(RegexError::UnmatchedParen {
+            position: offset(&chars_iter, expression.len()),
+        });
+    }
+    let size = alternatives.iter().fold(0usize, |size, steps| {
+        size.saturating_add(compiled_size(steps))
+    });
+    if size > syntax.size_limit {
+        return Err(RegexError::TooBig {
+            limit: syntax.size_limit,
+        });
+    }
+    Ok(alternatives)
+}
+
+/// Function to get the byte offset in the expression of the next character of the iterator
+fn offset(chars_iter: &Chars, length: usize) -> usize {
+    length - chars_iter.as_str().len()
+}
+
+/// Function to parse the alternatives separated by '|'
+/// - It stops at the end of the expression or at a ')' that isn't consumed
+/// - `groups` has the names of the groups opened so far, to number them and to find the repeated names
+fn parse_alternatives(
+    chars_iter: &mut Chars,
+    length: usize,
+    groups: &mut Vec<Option<String>>,
+    syntax: &Syntax,
+) -> Result<Vec<Vec<RegexStep>>, RegexError> {
+    let mut alternatives = vec![parse_concatenation(chars_iter, length, groups, syntax)?];
+    while chars_iter.as_str().starts_with('|') {
+        chars_iter.next();
+        alternatives.push(parse_concatenation(chars_iter, length, groups, syntax)?);
+    }
+    Ok(alternatives)
+}
+
+/// Function to parse a concatenation of steps
+/// - Quantifiers modify the last step, so they apply to any atom, including groups and brackets
+/// - A step can only have one quantifier, so a** and a{2}+ are errors, unless the Perl syntax
+///   makes the quantifier lazy or possessive, like a*? or a*+
+/// - It stops before a '|' or a ')'
+fn parse_concatenation(
+    chars_iter: &mut Chars,
+    length: usize,
+    groups: &mut Vec<Option<String>>,
+    syntax: &Syntax,
+) -> Result<Vec<RegexStep>, RegexError> {
+    let mut steps: Vec<RegexStep> = vec![];
+    let mut repeated = false;
+    let mut modified = false;
+    while let Some(c) = chars_iter.clone().next() {
+        if c == '|' || c == ')' {
+            break;
+        }
+        let position = offset(chars_iter, length);
+        chars_iter.next();
+        let quantifier = matches!(c, '?' | '*' | '+' | '{');
+        if quantifier && repeated {
+            if syntax.perl && !modified && (c == '?' || c == '+') {
+                handle_quantifier_mode(&mut steps, c);
+                modified = true;
+                continue;
+            }
+            return Err(RegexError::RepeatedQuantifier {
+                quantifier: c,
+                position,
+            });
+        }
+        repeated = quantifier;
+        modified = false;
+        let step = match c {
+            '(' => handle_group(chars_iter, length, position, groups, syntax)?,
+            '.' => handle_wildcard(),
+            '?' => handle_zero_or_one(&mut steps, position)?,
+            '*' => handle_any(&mut steps, position)?,
+            '+' => handle_exact_plus(&mut steps, position)?,
+            '{' => handle_range(chars_iter, &mut steps, position)?,
+            '[' => handle_brackets(chars_iter, position)?,
+            '^' => handle_start_of_line()?,
+            '$' => handle_end_of_line()?,
+            '\\' => handle_escape_sequence(chars_iter, groups.len(), position)?,
+            _ => Some(RegexStep {
+                rep: RegexRep::Exact(1),
+                val: RegexValue::Literal(c),
+            }),
+        };
+
+        if let Some(p) = step {
+            steps.push(p);
+        }
+    }
+    Ok(steps)
+}
+
+/// Function to handle a group
+/// - It receives a mutable reference to Chars right after the '(' and returns a Result with a RegexStep or an error
+/// - Every group captures, and takes the next number
+/// - A group that starts with ?P<name> or ?<name> can also be referred to by its name, made of letters, digits and _
+/// # Arguments
+/// * `chars_iter` - A mutable reference to Chars
+/// * `length` - The length of the whole expression
+/// * `position` - The position of the '(' in the expression
+/// * `groups` - The names of the groups opened before this one, None for the groups without a name
+/// * `syntax` - A reference to the Syntax of the expression
+/// # Returns
+/// * A Result with a RegexStep or an error
+/// # Example
+/// let mut chars = "a|b)".chars();
+/// let result = handle_group(&mut chars, 5, 0, &mut vec![], &Syntax::default());
+/// assert_eq!(result, Ok(Some(RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Capture(1, vec![vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') }], vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('b') }]]) })));
+fn handle_group(
+    chars_iter: &mut Chars,
+    length: usize,
+    position: usize,
+    groups: &mut Vec<Option<String>>,
+    syntax: &Syntax,
+) -> Result<Option<RegexStep>, RegexError> {
+    let name = handle_group_name(chars_iter, length, groups)?;
+    groups.push(name.clone());
+    let index = groups.len();
+    let alternatives = parse_alternatives(chars_iter, length, groups, syntax)?;
+    if chars_iter.next() != Some(')') {
+        return Err(RegexError::UnclosedGroup { position });
+    }
+    let val = match name {
+        Some(name) => RegexValue::NamedCapture(index, name, alternatives),
+        None => RegexValue::Capture(index, alternatives),
+    };
+    Ok(Some(RegexStep {
+        rep: RegexRep::Exact(1),
+        val,
+    }))
+}
+
+/// Function to handle the name of a group, right after its '('
+/// - It consumes ?P<name> or ?<name>, and nothing if the group has no name
+/// - The name starts with a letter or _, like in Perl, and can't be the name of another group
+fn handle_group_name(
+    chars_iter: &mut Chars,
+    length: usize,
+    groups: &[Option<String>],
+) -> Result<Option<String>, RegexError> {
+    let rest = chars_iter.as_str();
+    let Some(prefix) = ["?P<", "?<"].into_iter().find(|p| rest.starts_with(p)) else {
+        return Ok(None);
+    };
+    let position = offset(chars_iter, length) + prefix.len();
+    let rest = &rest[prefix.len()..];
+    let Some(end) = rest.find('>') else {
+        return Err(RegexError::InvalidGroupName { position });
+    };
+    let name = &rest[..end];
+    let valid = name
+        .chars()
+        .enumerate()
+        .all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+    if name.is_empty() || !valid {
+        return Err(RegexError::InvalidGroupName { position });
+    }
+    if groups.iter().flatten().any(|other| other == name) {
+        return Err(RegexError::DuplicateGroupName { position });
+    }
+    *chars_iter = rest[end + 1..].chars();
+    Ok(Some(name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::char_set::{CharSet, SetItem};
+    use crate::type_of_line::RegexRestriction;
+
+    fn literal(c: char) -> RegexStep {
+        RegexStep {
+            rep: RegexRep::Exact(1),
+            val: RegexValue::Literal(c),
+        }
+    }
+
+    mod valid {
+        use super::*;
+
+        #[test]
+        fn alternatives() {
+            let result = parse_expression("ab|c");
+            assert_eq!(
+                result,
+                Ok(vec![vec![literal('a'), literal('b')], vec![literal('c')]])
+            );
+        }
+
+        #[test]
+        fn group() {
+            let result = parse_expression("(foo|bar)baz");
+            assert_eq!(
+                result,
+                Ok(vec![vec![
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Capture(
+                            1,
+                            vec![
+                                vec![literal('f'), literal('o'), literal('o')],
+                                vec![literal('b'), literal('a'), literal('r')],
+                            ]
+                        ),
+                    },
+                    literal('b'),
+                    literal('a'),
+                    literal('z'),
+                ]])
+            );
+        }
+
+        #[test]
+        fn nested_groups() {
+            let result = parse_expression("(a(b|c))");
+            assert_eq!(
+                result,
+                Ok(vec![vec![RegexStep {
+                    rep: RegexRep::Exact(1),
+                    val: RegexValue::Capture(
+                        1,
+                        vec![vec![
+                            literal('a'),
+                            RegexStep {
+                                rep: RegexRep::Exact(1),
+                                val: RegexValue::Capture(
+                                    2,
+                                    vec![vec![literal('b')], vec![literal('c')]]
+                                ),
+                            },
+                        ]]
+                    ),
+                }]])
+            );
+        }
+
+        #[test]
+        fn quantified_group() {
+            let result = parse_expression("(ab)+");
+            assert_eq!(
+                result,
+                Ok(vec![vec![RegexStep {
+                    rep: RegexRep::Range {
+                        min: Some(1),
+                        max: None,
+                    },
+                    val: RegexValue::Capture(1, vec![vec![literal('a'), literal('b')]]),
+                }]])
+            );
+        }
+
+        #[test]
+        fn bounded_group_and_brackets() {
+            let result = parse_expression("(a){2,3}[ab]{2}");
+            assert_eq!(
+                result,
+                Ok(vec![vec![
+                    RegexStep {
+                        rep: RegexRep::Range {
+                            min: Some(2),
+                            max: Some(3),
+                        },
+                        val: RegexValue::Capture(1, vec![vec![literal('a')]]),
+                    },
+                    RegexStep {
+                        rep: RegexRep::Exact(2),
+                        val: RegexValue::Set(CharSet {
+                            negated: false,
+                            items: vec![SetItem::Char('a'), SetItem::Char('b')],
+                        }),
+                    },
+                ]])
+            );
+        }
+
+        #[test]
+        fn lazy_and_possessive() {
+            let syntax = Syntax {
+                perl: true,
+                ..Default::default()
+            };
+            let result = parse_expression_with("a*?b{2}+", &syntax);
+            assert_eq!(
+                result,
+                Ok(vec![vec![
+                    RegexStep {
+                        rep: RegexRep::Lazy {
+                            min: None,
+                            max: None,
+                        },
+                        val: RegexValue::Literal('a'),
+                    },
+                    RegexStep {
+                        rep: RegexRep::Possessive {
+                            min: Some(2),
+                            max: Some(2),
+                        },
+                        val: RegexValue::Literal('b'),
+                    },
+                ]])
+            );
+            assert_eq!(
+                parse_expression_with("a+??", &syntax),
+                Err(RegexError::RepeatedQuantifier {
+                    quantifier: '?',
+                    position: 3
+                })
+            );
+        }
+
+        #[test]
+        fn escaped_bar() {
+            let result = parse_expression("a\\|b");
+            assert_eq!(
+                result,
+                Ok(vec![vec![literal('a'), literal('|'), literal('b')]])
+            );
+        }
+
+        #[test]
+        fn bar_in_brackets() {
+            let result = parse_expression("[a|b]");
+            assert_eq!(
+                result,
+                Ok(vec![vec![RegexStep {
+                    rep: RegexRep::Exact(1),
+                    val: RegexValue::Set(CharSet {
+                        negated: false,
+                        items: vec![SetItem::Char('a'), SetItem::Char('|'), SetItem::Char('b')],
+                    }),
+                }]])
+            );
+        }
+
+        #[test]
+        fn empty_alternative() {
+            let result = parse_expression("a|");
+            assert_eq!(result, Ok(vec![vec![literal('a')], vec![]]));
+        }
+
+        #[test]
+        fn anchors_in_group() {
+            let result = parse_expression("(^a|b$)");
+            assert_eq!(
+                result,
+                Ok(vec![vec![RegexStep {
+                    rep: RegexRep::Exact(1),
+                    val: RegexValue::Capture(
+                        1,
+                        vec![
+                            vec![
+                                RegexStep {
+                                    rep: RegexRep::Exact(1),
+                                    val: RegexValue::Restriction(RegexRestriction::StartOfLine),
+                                },
+                                literal('a'),
+                            ],
+                            vec![
+                                literal('b'),
+                                RegexStep {
+                                    rep: RegexRep::Exact(1),
+                                    val: RegexValue::Restriction(RegexRestriction::EndOfLine),
+                                },
+                            ],
+                        ]
+                    ),
+                }]])
+            );
+        }
+
+        #[test]
+        fn backreference() {
+            let result = parse_expression("(a)\\1");
+            assert_eq!(
+                result,
+                Ok(vec![vec![
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Capture(1, vec![vec![literal('a')]]),
+                    },
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Backreference(1),
+                    },
+                ]])
+            );
+        }
+
+        #[test]
+        fn word_assertions() {
+            let result = parse_expression("\\<a\\b");
+            assert_eq!(
+                result,
+                Ok(vec![vec![
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Restriction(RegexRestriction::StartOfWord),
+                    },
+                    literal('a'),
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Restriction(RegexRestriction::WordBoundary),
+                    },
+                ]])
+            );
+        }
+
+        #[test]
+        fn groups_numbered_in_order() {
+            let result = parse_expression("(a)|(b)");
+            assert_eq!(
+                result,
+                Ok(vec![
+                    vec![RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Capture(1, vec![vec![literal('a')]]),
+                    }],
+                    vec![RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Capture(2, vec![vec![literal('b')]]),
+                    }],
+                ])
+            );
+        }
+
+        #[test]
+        fn named_groups() {
+            let result = parse_expression("(?P<key>a)(b)(?<value>c)\\3");
+            assert_eq!(
+                result,
+                Ok(vec![vec![
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::NamedCapture(
+                            1,
+                            "key".to_string(),
+                            vec![vec![literal('a')]]
+                        ),
+                    },
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Capture(2, vec![vec![literal('b')]]),
+                    },
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::NamedCapture(
+                            3,
+                            "value".to_string(),
+                            vec![vec![literal('c')]]
+                        ),
+                    },
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Backreference(3),
+                    },
+                ]])
+            );
+        }
+
+        #[test]
+        fn unicode_literals() {
+            let result = parse_expression("ñandú");
+            assert_eq!(
+                result,
+                Ok(vec![vec![
+                    literal('ñ'),
+                    literal('a'),
+                    literal('n'),
+                    literal('d'),
+                    literal('ú'),
+                ]])
+            );
+        }
+
+        #[test]
+        fn punctuation_literals() {
+            let result = parse_expression("a-b_c:");
+            assert_eq!(
+                result,
+                Ok(vec![vec![
+                    literal('a'),
+                    literal('-'),
+                    literal('b'),
+                    literal('_'),
+                    literal('c'),
+                    literal(':'),
+                ]])
+            );
+        }
+    }
+
+    mod not_valid {
+        use super::*;
+
+        #[test]
+        fn unclosed_group() {
+            assert_eq!(
+                parse_expression("a(b(c)"),
+                Err(RegexError::UnclosedGroup { position: 1 })
+            );
+        }
+
+        #[test]
+        fn invalid_group_name() {
+            for expression in ["(?P<>a)", "(?P<1a>a)", "a(?<b-c>d)", "(?P<ab"] {
+                assert!(
+                    matches!(
+                        parse_expression(expression),
+                        Err(RegexError::InvalidGroupName { .. })
+                    ),
+                    "{}",
+                    expression
+                );
+            }
+            assert_eq!(
+                parse_expression("a(?P<1>b)"),
+                Err(RegexError::InvalidGroupName { position: 5 })
+            );
+        }
+
+        #[test]
+        fn duplicate_group_name() {
+            assert_eq!(
+                parse_expression("(?P<x>a)(?P<x>b)"),
+                Err(RegexError::DuplicateGroupName { position: 12 })
+            );
+        }
+
+        #[test]
+        fn unmatched_paren() {
+            assert_eq!(
+                parse_expression("ab)"),
+                Err(RegexError::UnmatchedParen { position: 2 })
+            );
+        }
+
+        #[test]
+        fn quantifier_on_anchor() {
+            assert_eq!(
+                parse_expression("^*a"),
+                Err(RegexError::NothingToRepeat {
+                    quantifier: '*',
+                    position: 1
+                })
+            );
+        }
+
+        #[test]
+        fn quantifier_at_start_of_group() {
+            assert_eq!(
+                parse_expression("ab(+a)"),
+                Err(RegexError::NothingToRepeat {
+                    quantifier: '+',
+                    position: 3
+                })
+            );
+        }
+
+        #[test]
+        fn missing_escaped_char() {
+            assert_eq!(
+                parse_expression("ab\\"),
+                Err(RegexError::MissingEscapedChar { position: 2 })
+            );
+        }
+
+        #[test]
+        fn invalid_backreference() {
+            assert_eq!(
+                parse_expression("(a)\\2"),
+                Err(RegexError::InvalidBackreference { position: 3 })
+            );
+            assert_eq!(
+                parse_expression("\\1(a)"),
+                Err(RegexError::InvalidBackreference { position: 0 })
+            );
+        }
+
+        #[test]
+        fn invalid_repetition() {
+            assert_eq!(
+                parse_expression("a{x}"),
+                Err(RegexError::InvalidRepetition { position: 1 })
+            );
+        }
+
+        #[test]
+        fn unclosed_repetition() {
+            assert_eq!(
+                parse_expression("ab{2"),
+                Err(RegexError::UnclosedRepetition { position: 2 })
+            );
+            assert_eq!(
+                parse_expression("a{"),
+                Err(RegexError::UnclosedRepetition { position: 1 })
+            );
+        }
+
+        #[test]
+        fn invalid_repetition_range() {
+            assert_eq!(
+                parse_expression("a{3,2}"),
+                Err(RegexError::InvalidRepetitionRange { position: 1 })
+            );
+        }
+
+        #[test]
+        fn repeated_quantifier() {
+            assert_eq!(
+                parse_expression("a**"),
+                Err(RegexError::RepeatedQuantifier {
+                    quantifier: '*',
+                    position: 2
+                })
+            );
+            assert_eq!(
+                parse_expression("(a){2}+"),
+                Err(RegexError::RepeatedQuantifier {
+                    quantifier: '+',
+                    position: 6
+                })
+            );
+        }
+
+        #[test]
+        fn lazy_without_perl() {
+            assert_eq!(
+                parse_expression("a*?"),
+                Err(RegexError::RepeatedQuantifier {
+                    quantifier: '?',
+                    position: 2
+                })
+            );
+        }
+
+        #[test]
+        fn too_big() {
+            assert_eq!(
+                parse_expression("a{1,100000}"),
+                Err(RegexError::TooBig {
+                    limit: DEFAULT_SIZE_LIMIT
+                })
+            );
+            assert_eq!(
+                parse_expression("((a{1000}){1000}){1000}"),
+                Err(RegexError::TooBig {
+                    limit: DEFAULT_SIZE_LIMIT
+                })
+            );
+            let syntax = Syntax {
+                size_limit: 10,
+                ..Default::default()
+            };
+            assert_eq!(
+                parse_expression_with("a{1,10}", &syntax),
+                Err(RegexError::TooBig { limit: 10 })
+            );
+            let syntax = Syntax {
+                size_limit: 20,
+                ..Default::default()
+            };
+            assert!(parse_expression_with("a{1,10}", &syntax).is_ok());
+        }
+    }
+}
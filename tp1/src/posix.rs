@@ -0,0 +1,543 @@
+use crate::matching::handle_regex_value;
+use crate::nfa::Program;
+use crate::regex_error::RegexError;
+use crate::regex_step::RegexStep;
+use crate::regex_val::RegexValue;
+use crate::simulation::{handle_restriction, leftmost_longest_at};
+
+/// The most positions that the sets of a search for the groups can hold, added up, before it gives up
+/// - Every step of the regex can keep a set with the positions of the match, so their number grows with
+///   the length of the match times the size of the regex
+pub const SUBMATCH_LIMIT: usize = 1 << 24;
+
+/// The size from which a set of positions keeps a bit for each position, the smaller ones are searched
+const SMALL_SET: usize = 16;
+
+/// Struct to represent a set of positions of the match, byte offsets of the word
+/// - It keeps the insertion order, and a bit for each position of the match once it isn't small
+struct Positions {
+    /// The start of the match, the position of the first bit
+    origin: usize,
+    /// The number of positions of the match
+    len: usize,
+    /// The positions in insertion order
+    dense: Vec<usize>,
+    /// Whether each position is in the set, empty while the set is small
+    bits: Vec<u64>,
+}
+
+impl Positions {
+    fn contains(&self, position: usize) -> bool {
+        if self.bits.is_empty() {
+            return self.dense.contains(&position);
+        }
+        let offset = position - self.origin;
+        self.bits[offset / 64] & (1 << (offset % 64)) != 0
+    }
+
+    fn insert(&mut self, position: usize) -> bool {
+        if self.contains(position) {
+            return false;
+        }
+        if self.bits.is_empty() && self.dense.len() >= SMALL_SET {
+            self.bits = vec![0; self.len.div_ceil(64)];
+            for index in 0..self.dense.len() {
+                self.set_bit(self.dense[index]);
+            }
+        }
+        if !self.bits.is_empty() {
+            self.set_bit(position);
+        }
+        self.dense.push(position);
+        true
+    }
+
+    fn set_bit(&mut self, position: usize) {
+        let offset = position - self.origin;
+        self.bits[offset / 64] |= 1 << (offset % 64);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+}
+
+/// Struct to represent the search of the groups of a match, once its start and end are known
+/// - The sets of positions only hold positions of the match, since every step matches inside it
+struct Parser<'a> {
+    /// The compiled regex, for its options
+    program: &'a Program,
+    /// The word where the match was found
+    word: &'a str,
+    /// The start of the match
+    start: usize,
+    /// The end of the match
+    end: usize,
+    /// The start and end of each group, like the slots of Captures
+    slots: Vec<Option<usize>>,
+    /// The positions added to the sets so far, to give up at SUBMATCH_LIMIT
+    work: usize,
+}
+
+impl Parser<'_> {
+    fn spend(&mut self, work: usize) -> Result<(), RegexError> {
+        self.work += work;
+        if self.work > SUBMATCH_LIMIT {
+            return Err(RegexError::BacktrackLimit {
+                limit: SUBMATCH_LIMIT,
+            });
+        }
+        Ok(())
+    }
+
+    fn empty(&self) -> Positions {
+        Positions {
+            origin: self.start,
+            len: self.end - self.start + 1,
+            dense: vec![],
+            bits: vec![],
+        }
+    }
+
+    fn single(&self, position: usize) -> Positions {
+        let mut set = self.empty();
+        set.insert(position);
+        set
+    }
+
+    fn add(&mut self, set: &mut Positions, position: usize) -> Result<bool, RegexError> {
+        let words = set.bits.len();
+        let added = set.insert(position);
+        self.spend(usize::from(added) + set.bits.len() - words)?;
+        Ok(added)
+    }
+
+    fn copy(&mut self, set: &Positions) -> Result<Positions, RegexError> {
+        let mut copy = self.empty();
+        for &position in set.dense.iter() {
+            self.add(&mut copy, position)?;
+        }
+        Ok(copy)
+    }
+
+    /// Moves a set of positions over a value once, forward to where it can end or backward to where it can start
+    fn over_value(
+        &mut self,
+        val: &RegexValue,
+        from: &Positions,
+        forward: bool,
+    ) -> Result<Positions, RegexError> {
+        let mut to = self.empty();
+        match val {
+            RegexValue::Group(alternatives)
+            | RegexValue::Capture(_, alternatives)
+            | RegexValue::NamedCapture(_, _, alternatives) => {
+                for alternative in alternatives {
+                    let reached = self.over_steps(alternative, from, forward)?;
+                    for &position in reached.dense.iter() {
+                        self.add(&mut to, position)?;
+                    }
+                }
+            }
+            RegexValue::Restriction(restriction) => {
+                for &position in from.dense.iter() {
+                    if handle_restriction(restriction, self.word, position, &self.program.options) {
+                        self.add(&mut to, position)?;
+                    }
+                }
+            }
+            RegexValue::Backreference(_) => {}
+            _ => {
+                for &position in from.dense.iter() {
+                    let c = match forward {
+                        true if position < self.end => self.word[position..].chars().next(),
+                        false if position > self.start => self.word[..position].chars().next_back(),
+                        _ => None,
+                    };
+                    let Some(c) = c.filter(|&c| handle_regex_value(val, c, &self.program.options))
+                    else {
+                        continue;
+                    };
+                    let next = match forward {
+                        true => position + c.len_utf8(),
+                        false => position - c.len_utf8(),
+                    };
+                    self.add(&mut to, next)?;
+                }
+            }
+        }
+        Ok(to)
+    }
+
+    /// Moves a set of positions over a step, with every number of repetitions it allows
+    fn over_step(
+        &mut self,
+        step: &RegexStep,
+        from: &Positions,
+        forward: bool,
+    ) -> Result<Positions, RegexError> {
+        let (min, max) = step.rep.bounds();
+        let mut reached = self.copy(from)?;
+        for _ in 0..min {
+            reached = self.over_value(&step.val, &reached, forward)?;
+            if reached.is_empty() {
+                return Ok(reached);
+            }
+        }
+        let mut frontier = self.copy(&reached)?;
+        let mut count = min;
+        while !frontier.is_empty() && max.is_none_or(|max| count < max) {
+            let next = self.over_value(&step.val, &frontier, forward)?;
+            frontier = self.empty();
+            for &position in next.dense.iter() {
+                if self.add(&mut reached, position)? {
+                    self.add(&mut frontier, position)?;
+                }
+            }
+            count += 1;
+        }
+        Ok(reached)
+    }
+
+    /// Moves a set of positions over a sequence of steps
+    fn over_steps(
+        &mut self,
+        steps: &[RegexStep],
+        from: &Positions,
+        forward: bool,
+    ) -> Result<Positions, RegexError> {
+        let mut reached = self.copy(from)?;
+        let steps: Vec<&RegexStep> = match forward {
+            true => steps.iter().collect(),
+            false => steps.iter().rev().collect(),
+        };
+        for step in steps {
+            if reached.is_empty() {
+                break;
+            }
+            reached = self.over_step(step, &reached, forward)?;
+        }
+        Ok(reached)
+    }
+
+    /// Matches the steps from a position, each one as long as it can be while the next ones still reach the tail
+    /// - The groups that aren't repeated nor have alternatives are transparent, their steps are decided one by one
+    /// - Returns the position where the steps end
+    fn parse_steps(
+        &mut self,
+        steps: &[RegexStep],
+        position: usize,
+        tail: &Positions,
+    ) -> Result<usize, RegexError> {
+        let mut tails = vec![self.copy(tail)?];
+        for step in steps.iter().skip(1).rev() {
+            let before = self.over_step(step, &tails[tails.len() - 1], false)?;
+            tails.push(before);
+        }
+        let mut position = position;
+        for step in steps {
+            let tail = tails.pop().unwrap_or_else(|| self.empty());
+            position = self.parse_step(step, position, &tail)?;
+        }
+        Ok(position)
+    }
+
+    fn parse_step(
+        &mut self,
+        step: &RegexStep,
+        position: usize,
+        tail: &Positions,
+    ) -> Result<usize, RegexError> {
+        if step.rep.bounds() == (1, Some(1)) {
+            match &step.val {
+                RegexValue::Capture(index, alternatives)
+                | RegexValue::NamedCapture(index, _, alternatives)
+                    if alternatives.len() == 1 =>
+                {
+                    let end = self.parse_steps(&alternatives[0], position, tail)?;
+                    self.save(*index, position, end);
+                    return Ok(end);
+                }
+                RegexValue::Group(alternatives) if alternatives.len() == 1 => {
+                    return self.parse_steps(&alternatives[0], position, tail);
+                }
+                _ => {}
+            }
+        }
+        let from = self.single(position);
+        let ends = self.over_step(step, &from, true)?;
+        let end = ends
+            .dense
+            .iter()
+            .copied()
+            .filter(|&end| tail.contains(end))
+            .max()
+            .unwrap_or(position);
+        if has_captures(&step.val) {
+            self.parse_repetition(step, position, end)?;
+        }
+        Ok(end)
+    }
+
+    /// Decides the repetitions of a step that matches from start to end, each one as long as it can be
+    /// - Only the groups of the last repetition are kept, the ones of the repetitions before it are reset
+    /// - The required repetitions can be empty, the other ones only when there is nothing left to match,
+    ///   and a repetition with no other one matches empty if it can, like (a*)* on x
+    fn parse_repetition(
+        &mut self,
+        step: &RegexStep,
+        start: usize,
+        end: usize,
+    ) -> Result<(), RegexError> {
+        let (min, max) = step.rep.bounds();
+        if (min, max) == (1, Some(1)) {
+            return self.parse_value(&step.val, start, end);
+        }
+
+        // The fewest optional repetitions that go from each position to the end
+        let mut distances = vec![usize::MAX; end - start + 1];
+        self.spend(distances.len())?;
+        distances[end - start] = 0;
+        let mut frontier = self.single(end);
+        let mut count = 0;
+        while !frontier.is_empty() && max.is_none_or(|max| count < max - min) {
+            let next = self.over_value(&step.val, &frontier, false)?;
+            count += 1;
+            frontier = self.empty();
+            for &position in next.dense.iter().filter(|&&position| position >= start) {
+                if distances[position - start] == usize::MAX {
+                    distances[position - start] = count;
+                    self.add(&mut frontier, position)?;
+                }
+            }
+        }
+
+        // The positions from which each number of required repetitions and then the optional ones go to the end
+        let mut optional = self.empty();
+        for (offset, &distance) in distances.iter().enumerate() {
+            if distance != usize::MAX {
+                self.add(&mut optional, start + offset)?;
+            }
+        }
+        let mut required = vec![optional];
+        for _ in 1..min {
+            let before = self.over_value(&step.val, &required[required.len() - 1], false)?;
+            required.push(before);
+        }
+
+        let mut last = None;
+        let mut position = start;
+        let mut count = 0;
+        loop {
+            if position == end {
+                let more = count == 0 || count < min || max.is_some_and(|max| count < max);
+                if more && max != Some(0) {
+                    let from = self.single(end);
+                    if self.over_value(&step.val, &from, true)?.contains(end) {
+                        last = Some((end, end));
+                    }
+                }
+                break;
+            }
+            let from = self.single(position);
+            let ends = self.over_value(&step.val, &from, true)?;
+            let next = ends
+                .dense
+                .iter()
+                .copied()
+                .filter(|&next| next <= end)
+                .filter(|&next| match count + 1 < min {
+                    true => required[min - count - 1].contains(next),
+                    false => match max {
+                        Some(max) => count < max && distances[next - start] < max - count,
+                        None => distances[next - start] != usize::MAX,
+                    },
+                })
+                .max();
+            let Some(next) = next else {
+                break;
+            };
+            last = Some((position, next));
+            position = next;
+            count += 1;
+        }
+
+        match last {
+            Some((start, end)) => self.parse_value(&step.val, start, end),
+            None => Ok(()),
+        }
+    }
+
+    /// Decides the groups of a value that matches from start to end, the first alternative that does wins
+    fn parse_value(
+        &mut self,
+        val: &RegexValue,
+        start: usize,
+        end: usize,
+    ) -> Result<(), RegexError> {
+        let alternatives = match val {
+            RegexValue::Capture(index, alternatives)
+            | RegexValue::NamedCapture(index, _, alternatives) => {
+                self.save(*index, start, end);
+                alternatives
+            }
+            RegexValue::Group(alternatives) => alternatives,
+            _ => return Ok(()),
+        };
+        if !alternatives
+            .iter()
+            .flatten()
+            .any(|step| has_captures(&step.val))
+        {
+            return Ok(());
+        }
+        let tail = self.single(end);
+        for alternative in alternatives {
+            let from = self.single(start);
+            if alternatives.len() == 1 || self.over_steps(alternative, &from, true)?.contains(end) {
+                self.parse_steps(alternative, start, &tail)?;
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn save(&mut self, group: usize, start: usize, end: usize) {
+        if 2 * group + 1 < self.slots.len() {
+            self.slots[2 * group] = Some(start);
+            self.slots[2 * group + 1] = Some(end);
+        }
+    }
+}
+
+/// Checks if a value has capturing groups, that the parser has to decide
+fn has_captures(val: &RegexValue) -> bool {
+    match val {
+        RegexValue::Capture(..) | RegexValue::NamedCapture(..) => true,
+        RegexValue::Group(alternatives) => alternatives
+            .iter()
+            .flatten()
+            .any(|step| has_captures(&step.val)),
+        _ => false,
+    }
+}
+
+/// Finds the leftmost-longest match of a compiled regex from a byte offset, with the groups decided by the
+/// POSIX rules for subexpressions
+/// - The match is found by the NFA simulation, then the steps are matched inside it from left to right,
+///   each one as long as it can be while the rest of the regex still matches
+/// - A repeated group keeps what its last repetition matched, and the groups inside it that didn't take
+///   part in that repetition are unset
+/// - It can't match backreferences nor atomic parts, those regexes are matched with backtracking
+/// # Arguments
+/// * `program` - A reference to a Program
+/// * `word` - A reference to a str
+/// * `from` - The byte offset where the search starts
+/// # Returns
+/// * A Result with an Option with the slots of the groups, or a BacktrackLimit error
+/// # Example
+/// let program = Program::new(&Regex::new("(a|ab)(c|bcd)(d*)").unwrap());
+/// let slots = posix_captures_at(&program, "abcd", 0).unwrap().unwrap();
+/// assert_eq!(slots[2..], [Some(0), Some(2), Some(2), Some(3), Some(3), Some(4)]);
+pub fn posix_captures_at(
+    program: &Program,
+    word: &str,
+    from: usize,
+) -> Result<Option<Vec<Option<usize>>>, RegexError> {
+    let Some((start, end)) = leftmost_longest_at(program, word, from) else {
+        return Ok(None);
+    };
+    let mut parser = Parser {
+        program,
+        word,
+        start,
+        end,
+        slots: vec![None; 2 * (program.groups + 1)],
+        work: 0,
+    };
+    parser.slots[0] = Some(start);
+    parser.slots[1] = Some(end);
+    let tail = parser.single(end);
+    parser.parse_steps(&program.steps, start, &tail)?;
+    Ok(Some(parser.slots))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::Regex;
+
+    fn groups(expression: &str, word: &str) -> Option<Vec<Option<(usize, usize)>>> {
+        let program = Program::new(&Regex::new(expression).unwrap());
+        let slots = posix_captures_at(&program, word, 0).unwrap()?;
+        Some(slots.chunks(2).map(|slot| slot[0].zip(slot[1])).collect())
+    }
+
+    #[test]
+    fn alternatives_as_long_as_they_can_be() {
+        assert_eq!(
+            groups("(a|ab)(c|bcd)(d*)", "abcd"),
+            Some(vec![Some((0, 4)), Some((0, 2)), Some((2, 3)), Some((3, 4))])
+        );
+        assert_eq!(
+            groups("(a|ab)(bcd|c)", "abcd"),
+            Some(vec![Some((0, 4)), Some((0, 1)), Some((1, 4))])
+        );
+    }
+
+    #[test]
+    fn repetitions_before_what_follows() {
+        assert_eq!(
+            groups("(ab|a|c|bcd)*(d*)", "ababcd"),
+            Some(vec![Some((0, 6)), Some((3, 6)), Some((6, 6))])
+        );
+        assert_eq!(
+            groups("(a*)(a*)", "aaa"),
+            Some(vec![Some((0, 3)), Some((0, 3)), Some((3, 3))])
+        );
+    }
+
+    #[test]
+    fn empty_repetitions() {
+        assert_eq!(groups("(a*)*", "-"), Some(vec![Some((0, 0)), Some((0, 0))]));
+        assert_eq!(
+            groups("(a*){2}(x)", "ax"),
+            Some(vec![Some((0, 2)), Some((1, 1)), Some((1, 2))])
+        );
+        assert_eq!(groups("(a)*", "b"), Some(vec![Some((0, 0)), None]));
+    }
+
+    #[test]
+    fn last_repetition_resets_the_groups() {
+        assert_eq!(
+            groups("((..)|(.)){2}", "aaa"),
+            Some(vec![Some((0, 3)), Some((2, 3)), None, Some((2, 3))])
+        );
+        assert_eq!(
+            groups("((..)|(.))*", "aaaaa"),
+            Some(vec![Some((0, 5)), Some((4, 5)), None, Some((4, 5))])
+        );
+    }
+
+    #[test]
+    fn groups_of_the_alternative_that_matched() {
+        assert_eq!(
+            groups("(a)|(b)", "b"),
+            Some(vec![Some((0, 1)), None, Some((0, 1))])
+        );
+        assert_eq!(
+            groups("x(a|(b))y", "xay"),
+            Some(vec![Some((0, 3)), Some((1, 2)), None])
+        );
+    }
+
+    #[test]
+    fn from_offset_and_no_match() {
+        let program = Program::new(&Regex::new("(b+)").unwrap());
+        assert_eq!(
+            posix_captures_at(&program, "bab", 1),
+            Ok(Some(vec![Some(2), Some(3), Some(2), Some(3)]))
+        );
+        assert_eq!(groups("(x)", "ab"), None);
+    }
+}
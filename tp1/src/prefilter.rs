@@ -0,0 +1,238 @@
+use crate::regex_step::RegexStep;
+use crate::regex_val::RegexValue;
+use std::collections::VecDeque;
+
+/// Returns the longest literal that every match of the steps must contain
+/// - A run of literals that must be matched one after the other is a required literal, like "ERROR " in ERROR [[:digit:]]+
+/// - A group with a single alternative is part of the run, a group with several ones ends it
+/// - A literal repeated at least once adds its minimum copies and ends the run, since more copies can follow
+/// # Arguments
+/// * `steps` - A slice of RegexStep
+/// # Returns
+/// * An Option with the literal, None if there is no required literal
+/// # Example
+/// let regex = Regex::new("ERROR [[:digit:]]+").unwrap();
+/// assert_eq!(required_literal(&regex.steps), Some("ERROR ".to_string()));
+pub fn required_literal(steps: &[RegexStep]) -> Option<String> {
+    let mut best = String::new();
+    let mut current = String::new();
+    add_steps(steps, &mut current, &mut best);
+    end_run(&mut current, &mut best);
+    (!best.is_empty()).then_some(best)
+}
+
+/// Ends the current run of literals, keeping it if it is the longest one
+fn end_run(current: &mut String, best: &mut String) {
+    if current.chars().count() > best.chars().count() {
+        *best = current.clone();
+    }
+    current.clear();
+}
+
+/// Adds the literals of the steps to the current run
+fn add_steps(steps: &[RegexStep], current: &mut String, best: &mut String) {
+    for step in steps {
+        let (min, max) = step.rep.bounds();
+        let exact = max == Some(min);
+        match &step.val {
+            RegexValue::Literal(c) => {
+                for _ in 0..min {
+                    current.push(*c);
+                }
+                if !exact {
+                    end_run(current, best);
+                }
+            }
+            RegexValue::Group(alternatives)
+            | RegexValue::Capture(_, alternatives)
+            | RegexValue::NamedCapture(_, _, alternatives)
+                if alternatives.len() == 1 =>
+            {
+                if min == 0 {
+                    end_run(current, best);
+                    continue;
+                }
+                if !exact || min > 1 {
+                    end_run(current, best);
+                    add_steps(&alternatives[0], current, best);
+                    end_run(current, best);
+                    continue;
+                }
+                add_steps(&alternatives[0], current, best);
+            }
+            RegexValue::Restriction(_) => {}
+            _ => end_run(current, best),
+        }
+    }
+}
+
+/// Struct to represent the required literals of several regexes, searched together in one scan of a word
+/// - It is an Aho-Corasick automaton: a trie of the literals, where a byte that doesn't continue a literal
+///   follows the link to the longest suffix that is the start of another one
+/// - A word without any of the literals can't match any of the regexes
+/// # Example
+/// let literals = Literals::new(&["ERROR", "timeout"]);
+/// assert_eq!(literals.is_in("a timeout here"), true);
+/// assert_eq!(literals.is_in("WARN"), false);
+pub struct Literals {
+    /// The children of each node of the trie, by their byte, sorted
+    children: Vec<Vec<(u8, usize)>>,
+    /// The node of the longest proper suffix of each node that is also in the trie
+    fail: Vec<usize>,
+    /// Whether a literal ends at each node, or at the end of one of its suffixes
+    ends: Vec<bool>,
+}
+
+/// Implementation of the Literals struct
+impl Literals {
+    /// Builds the automaton of the literals
+    /// # Arguments
+    /// * `literals` - A slice with the literals
+    /// # Returns
+    /// * A Literals
+    pub fn new<S: AsRef<str>>(literals: &[S]) -> Self {
+        let mut trie = Literals {
+            children: vec![vec![]],
+            fail: vec![0],
+            ends: vec![false],
+        };
+        for literal in literals {
+            trie.add(literal.as_ref().as_bytes());
+        }
+        trie.link();
+        trie
+    }
+
+    /// Adds a literal to the trie
+    fn add(&mut self, literal: &[u8]) {
+        let mut node = 0;
+        for byte in literal {
+            node = match self.child(node, *byte) {
+                Some(child) => child,
+                None => {
+                    let child = self.children.len();
+                    self.children.push(vec![]);
+                    self.fail.push(0);
+                    self.ends.push(false);
+                    let children = &mut self.children[node];
+                    let index = children.partition_point(|(b, _)| b < byte);
+                    children.insert(index, (*byte, child));
+                    child
+                }
+            };
+        }
+        self.ends[node] = true;
+    }
+
+    /// Sets the suffix link of every node, from the root down, so the link of the parent is known first
+    fn link(&mut self) {
+        let mut queue: VecDeque<usize> = self.children[0].iter().map(|(_, c)| *c).collect();
+        while let Some(node) = queue.pop_front() {
+            for (byte, child) in self.children[node].clone() {
+                let mut fail = self.fail[node];
+                let link = loop {
+                    if let Some(next) = self.child(fail, byte) {
+                        break next;
+                    }
+                    if fail == 0 {
+                        break 0;
+                    }
+                    fail = self.fail[fail];
+                };
+                self.fail[child] = link;
+                self.ends[child] |= self.ends[link];
+                queue.push_back(child);
+            }
+        }
+    }
+
+    /// Returns the child of a node by its byte
+    fn child(&self, node: usize, byte: u8) -> Option<usize> {
+        let children = &self.children[node];
+        children
+            .binary_search_by_key(&byte, |(b, _)| *b)
+            .ok()
+            .map(|index| children[index].1)
+    }
+
+    /// Checks if any of the literals is in the word
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A boolean
+    pub fn is_in(&self, word: &str) -> bool {
+        if self.ends[0] {
+            return true;
+        }
+        let mut node = 0;
+        for byte in word.bytes() {
+            node = loop {
+                if let Some(next) = self.child(node, byte) {
+                    break next;
+                }
+                if node == 0 {
+                    break 0;
+                }
+                node = self.fail[node];
+            };
+            if self.ends[node] {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::Regex;
+
+    fn literal(expression: &str) -> Option<String> {
+        required_literal(&Regex::new(expression).unwrap().steps)
+    }
+
+    #[test]
+    fn runs() {
+        assert_eq!(literal("ERROR [[:digit:]]+"), Some("ERROR ".to_string()));
+        assert_eq!(literal("timeout$"), Some("timeout".to_string()));
+        assert_eq!(literal("a.bcd"), Some("bcd".to_string()));
+        assert_eq!(literal("^ab{3}c"), Some("abbbc".to_string()));
+    }
+
+    #[test]
+    fn repetitions() {
+        assert_eq!(literal("ab+c"), Some("ab".to_string()));
+        assert_eq!(literal("ab*cd"), Some("cd".to_string()));
+        assert_eq!(literal("x?"), None);
+    }
+
+    #[test]
+    fn groups() {
+        assert_eq!(literal("a(bc)d"), Some("abcd".to_string()));
+        assert_eq!(literal("(abc)+d"), Some("abc".to_string()));
+        assert_eq!(literal("(ab|cd)ef"), Some("ef".to_string()));
+        assert_eq!(literal("(abc)?d"), Some("d".to_string()));
+    }
+
+    #[test]
+    fn no_literal() {
+        assert_eq!(literal("a|b"), None);
+        assert_eq!(literal("[[:alpha:]]+"), None);
+        assert_eq!(literal(".*"), None);
+    }
+
+    #[test]
+    fn several_literals() {
+        let literals = Literals::new(&["he", "she", "his", "hers"]);
+        assert!(literals.is_in("ushers"));
+        assert!(literals.is_in("ahishe"));
+        assert!(!literals.is_in("hxsx"));
+        assert!(!literals.is_in(""));
+        let literals = Literals::new(&["abcd", "bc"]);
+        assert!(literals.is_in("xabcx"));
+        assert!(!literals.is_in("abd"));
+        assert!(Literals::new(&["ñandú"]).is_in("el ñandú"));
+        assert!(!Literals::new::<&str>(&[]).is_in("a"));
+    }
+}
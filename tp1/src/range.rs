@@ -1,26 +1,43 @@
+use crate::regex_error::RegexError;
 use crate::regex_rep::RegexRep;
 use crate::regex_step::RegexStep;
+use crate::regex_val::RegexValue;
 use std::str::Chars;
 
+/// Function to get the last step, the one a quantifier applies to
+/// - Restrictions like ^ can't be repeated, so they are not returned
+fn last_step(steps: &mut [RegexStep]) -> Option<&mut RegexStep> {
+    steps
+        .last_mut()
+        .filter(|last| !matches!(last.val, RegexValue::Restriction(_)))
+}
+
 /// Function to handle the any metacharacter
 /// - It receives a mutable reference to a vector of RegexStep and returns a Result with a RegexStep or an error
 /// # Arguments
 /// * `steps` - A mutable reference to a vector of RegexStep
+/// * `position` - The position of the metacharacter in the expression
 /// # Returns
 /// * A Result with a RegexStep or an error
 /// # Example
 /// let mut steps = vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') }];
-/// let result = handle_any(&mut steps);
+/// let result = handle_any(&mut steps, 1);
 /// assert_eq!(result, Ok(None));
 /// assert_eq!(steps, vec![RegexStep { rep: RegexRep::Range { min: None, max: None }, val: RegexValue::Literal('a') }]);
-pub fn handle_any(steps: &mut [RegexStep]) -> Result<Option<RegexStep>, &'static str> {
-    if let Some(last) = steps.last_mut() {
+pub fn handle_any(
+    steps: &mut [RegexStep],
+    position: usize,
+) -> Result<Option<RegexStep>, RegexError> {
+    if let Some(last) = last_step(steps) {
         last.rep = RegexRep::Range {
             min: None,
             max: None,
         };
     } else {
-        return Err("Unexpected '*' character");
+        return Err(RegexError::NothingToRepeat {
+            quantifier: '*',
+            position,
+        });
     }
     Ok(None)
 }
@@ -29,21 +46,28 @@ pub fn handle_any(steps: &mut [RegexStep]) -> Result<Option<RegexStep>, &'static
 /// - It receives a mutable reference to a vector of RegexStep and returns a Result with a RegexStep or an error
 /// # Arguments
 /// * `steps` - A mutable reference to a vector of RegexStep
+/// * `position` - The position of the metacharacter in the expression
 /// # Returns
 /// * A Result with a RegexStep or an error
 /// # Example
 /// let mut steps = vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') }];
-/// let result = handle_zero_or_one(&mut steps);
+/// let result = handle_zero_or_one(&mut steps, 1);
 /// assert_eq!(result, Ok(None));
 /// assert_eq!(steps, vec![RegexStep { rep: RegexRep::Range { min: None, max: Some(1) }, val: RegexValue::Literal('a') }]);
-pub fn handle_zero_or_one(steps: &mut [RegexStep]) -> Result<Option<RegexStep>, &'static str> {
-    if let Some(last) = steps.last_mut() {
+pub fn handle_zero_or_one(
+    steps: &mut [RegexStep],
+    position: usize,
+) -> Result<Option<RegexStep>, RegexError> {
+    if let Some(last) = last_step(steps) {
         last.rep = RegexRep::Range {
             min: None,
             max: Some(1),
         };
     } else {
-        return Err("Unexpected '?' character");
+        return Err(RegexError::NothingToRepeat {
+            quantifier: '?',
+            position,
+        });
     }
     Ok(None)
 }
@@ -52,84 +76,127 @@ pub fn handle_zero_or_one(steps: &mut [RegexStep]) -> Result<Option<RegexStep>,
 /// - It receives a mutable reference to a vector of RegexStep and returns a Result with a RegexStep or an error
 /// # Arguments
 /// * `steps` - A mutable reference to a vector of RegexStep
+/// * `position` - The position of the metacharacter in the expression
 /// # Returns
 /// * A Result with a RegexStep or an error
 /// # Example
 /// let mut steps = vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') }];
-/// let result = handle_exact_plus(&mut steps);
+/// let result = handle_exact_plus(&mut steps, 1);
 /// assert_eq!(result, Ok(None));
 /// assert_eq!(steps, vec![RegexStep { rep: RegexRep::Range { min: Some(1), max: None }, val: RegexValue::Literal('a') }]);
-pub fn handle_exact_plus(steps: &mut [RegexStep]) -> Result<Option<RegexStep>, &'static str> {
-    if let Some(last) = steps.last_mut() {
+pub fn handle_exact_plus(
+    steps: &mut [RegexStep],
+    position: usize,
+) -> Result<Option<RegexStep>, RegexError> {
+    if let Some(last) = last_step(steps) {
         last.rep = RegexRep::Range {
             min: Some(1),
             max: None,
         };
     } else {
-        return Err("Unexpected '+' character");
+        return Err(RegexError::NothingToRepeat {
+            quantifier: '+',
+            position,
+        });
     }
     Ok(None)
 }
 
 /// Function to handle the range metacharacter
 /// - It receives a mutable reference to Chars and a mutable reference to a vector of RegexStep and returns a Result with a RegexStep or an error
+/// - The repetition must be closed with }, and its minimum can't be greater than its maximum
 /// # Arguments
 /// * `chars_iter` - A mutable reference to Chars
 /// * `steps` - A mutable reference to a vector of RegexStep
+/// * `position` - The position of the metacharacter in the expression
 /// # Returns
 /// * A Result with a RegexStep or an error
 /// # Example
 /// let mut chars = "1,2}".chars();
 /// let mut steps = vec![RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Literal('a') }];
-/// let result = handle_range(&mut chars, &mut steps);
+/// let result = handle_range(&mut chars, &mut steps, 1);
 /// assert_eq!(result, Ok(None));
 /// assert_eq!(steps, vec![RegexStep { rep: RegexRep::Range { min: Some(1), max: Some(2) }, val: RegexValue::Literal('a') }]);
 pub fn handle_range(
     chars_iter: &mut Chars,
     steps: &mut [RegexStep],
-) -> Result<Option<RegexStep>, &'static str> {
+    position: usize,
+) -> Result<Option<RegexStep>, RegexError> {
+    let invalid = |_| RegexError::InvalidRepetition { position };
     let mut n = String::new();
+    let mut closed = false;
     for c in chars_iter.by_ref() {
         if c == '}' {
+            closed = true;
             break;
         }
         n.push(c);
     }
+    if !closed {
+        return Err(RegexError::UnclosedRepetition { position });
+    }
     let parts: Vec<&str> = n.split(',').collect();
-    if let Some(last) = steps.last_mut() {
+    if let Some(last) = last_step(steps) {
         match parts.len() {
             1 => {
-                let exact = parts[0]
-                    .parse::<usize>()
-                    .map_err(|_| "Failed to parse exact repetition")?;
+                let exact = parts[0].parse::<usize>().map_err(invalid)?;
                 last.rep = RegexRep::Exact(exact);
             }
             2 => {
                 let min = if parts[0].is_empty() {
                     None
                 } else {
-                    Some(
-                        parts[0]
-                            .parse::<usize>()
-                            .map_err(|_| "Failed to parse min repetition")?,
-                    )
+                    Some(parts[0].parse::<usize>().map_err(invalid)?)
                 };
                 let max = if parts[1].is_empty() {
                     None
                 } else {
-                    Some(
-                        parts[1]
-                            .parse::<usize>()
-                            .map_err(|_| "Failed to parse max repetition")?,
-                    )
+                    Some(parts[1].parse::<usize>().map_err(invalid)?)
                 };
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min > max {
+                        return Err(RegexError::InvalidRepetitionRange { position });
+                    }
+                }
                 last.rep = RegexRep::Range { min, max };
             }
-            _ => return Err("Invalid repetition syntax"),
+            _ => return Err(RegexError::InvalidRepetition { position }),
         }
     } else {
-        return Err("Unexpected '{' character");
+        return Err(RegexError::NothingToRepeat {
+            quantifier: '{',
+            position,
+        });
     }
 
     Ok(None)
 }
+
+/// Function to make the quantifier of the last step lazy, with ?, or possessive, with +
+/// - A lazy repetition of an exact number of times is the same repetition
+/// # Arguments
+/// * `steps` - A mutable reference to a vector of RegexStep, whose last step has a quantifier
+/// * `c` - The char after the quantifier
+/// # Example
+/// let mut steps = vec![RegexStep { rep: RegexRep::Range { min: None, max: None }, val: RegexValue::Literal('a') }];
+/// handle_quantifier_mode(&mut steps, '?');
+/// assert_eq!(steps, vec![RegexStep { rep: RegexRep::Lazy { min: None, max: None }, val: RegexValue::Literal('a') }]);
+pub fn handle_quantifier_mode(steps: &mut [RegexStep], c: char) {
+    if let Some(last) = last_step(steps) {
+        last.rep = match (&last.rep, c) {
+            (RegexRep::Range { min, max }, '?') => RegexRep::Lazy {
+                min: *min,
+                max: *max,
+            },
+            (RegexRep::Exact(count), '+') => RegexRep::Possessive {
+                min: Some(*count),
+                max: Some(*count),
+            },
+            (RegexRep::Range { min, max }, _) => RegexRep::Possessive {
+                min: *min,
+                max: *max,
+            },
+            (rep, _) => rep.clone(),
+        };
+    }
+}
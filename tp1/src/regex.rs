@@ -1,10 +1,9 @@
-use crate::brackets::handle_brackets;
-use crate::exactrep::{handle_escape_sequence, handle_wildcard};
-use crate::range::{handle_any, handle_exact_plus, handle_range, handle_zero_or_one};
+use crate::parser::{parse_expression_with, Syntax};
+use crate::regex_error::RegexError;
 use crate::regex_rep::RegexRep;
 use crate::regex_step::RegexStep;
 use crate::regex_val::RegexValue;
-use crate::type_of_line::{handle_end_of_line, handle_start_of_line, RegexRestriction};
+use crate::type_of_line::RegexRestriction;
 
 /// Struct to represent a regex
 #[derive(Debug, PartialEq)]
@@ -18,6 +17,7 @@ pub struct Regex {
 /// Implementation of the Regex struct
 impl Regex {
     /// Creates a new Regex from a string
+    /// - If the expression has alternatives, the steps are a single group with all of them
     /// # Arguments
     /// * `expression` - A string slice that holds the regex expression
     /// # Returns
@@ -25,51 +25,122 @@ impl Regex {
     /// # Example
     /// let regex = Regex::new("a.b");
     /// assert_eq!(regex.is_ok(), true);
-    pub fn new(expression: &str) -> Result<Self, &str> {
-        if !expression.is_ascii() {
-            return Err("The expression is not ascii");
+    pub fn new(expression: &str) -> Result<Self, RegexError> {
+        Regex::with_syntax(expression, &Syntax::default())
+    }
+
+    /// Creates a new Regex from a string, parsed with a Syntax
+    /// # Arguments
+    /// * `expression` - A string slice that holds the regex expression
+    /// * `syntax` - A reference to a Syntax
+    /// # Returns
+    /// * A Result with a Regex or an error
+    /// # Example
+    /// let syntax = Syntax { size_limit: 100, ..Default::default() };
+    /// let regex = Regex::with_syntax("a{1,1000}", &syntax);
+    /// assert_eq!(regex, Err(RegexError::TooBig { limit: 100 }));
+    pub fn with_syntax(expression: &str, syntax: &Syntax) -> Result<Self, RegexError> {
+        let mut alternatives = parse_expression_with(expression, syntax)?;
+        if alternatives.len() == 1 {
+            return Ok(Regex::from_steps(alternatives.remove(0)));
+        }
+        Ok(Regex::from_steps(vec![RegexStep {
+            val: RegexValue::Group(alternatives),
+            rep: RegexRep::Exact(1),
+        }]))
+    }
+
+    /// Creates a new Regex from the steps of a parsed expression
+    /// - A ^ at the start and a $ at the end restrict the whole regex, so they are moved to the backtracking
+    /// # Arguments
+    /// * `steps` - A vector of RegexStep
+    /// # Returns
+    /// * A Regex
+    /// # Example
+    /// let steps = vec![RegexStep { val: RegexValue::Restriction(RegexRestriction::StartOfLine), rep: RegexRep::Exact(1) }];
+    /// let regex = Regex::from_steps(steps);
+    /// assert_eq!(regex, Regex { steps: vec![], backtracking: Some(vec![RegexRestriction::StartOfLine]) });
+    pub fn from_steps(mut steps: Vec<RegexStep>) -> Self {
+        let mut backtracking = vec![];
+        if steps
+            .first()
+            .is_some_and(|step| step.val == RegexValue::Restriction(RegexRestriction::StartOfLine))
+        {
+            steps.remove(0);
+            backtracking.push(RegexRestriction::StartOfLine);
+        }
+        if steps
+            .last()
+            .is_some_and(|step| step.val == RegexValue::Restriction(RegexRestriction::EndOfLine))
+        {
+            steps.pop();
+            backtracking.push(RegexRestriction::EndOfLine);
         }
-        let mut backtracking = Some(vec![]);
 
-        let mut steps: Vec<RegexStep> = vec![];
-        let mut chars_iter = expression.chars();
-        while let Some(c) = chars_iter.next() {
-            let step = match c {
-                '.' => handle_wildcard(),
-                'a'..='z' | 'A'..='Z' | '0'..='9' | ' ' => Some(RegexStep {
-                    rep: RegexRep::Exact(1),
-                    val: RegexValue::Literal(c),
-                }),
-                '?' => handle_zero_or_one(&mut steps)?,
-                '*' => handle_any(&mut steps)?,
-                '+' => handle_exact_plus(&mut steps)?,
-                '{' => handle_range(&mut chars_iter, &mut steps)?,
-                '[' => handle_brackets(&mut chars_iter)?,
-                '^' => handle_start_of_line(&mut backtracking)?,
-                '$' => handle_end_of_line(&mut backtracking)?,
-                '\\' => handle_escape_sequence(&mut chars_iter)?,
-                _ => return Err("Invalid character"),
-            };
+        Regex {
+            steps,
+            backtracking: if backtracking.is_empty() {
+                None
+            } else {
+                Some(backtracking)
+            },
+        }
+    }
 
-            if let Some(p) = step {
-                steps.push(p);
-            }
+    /// Restricts the regex to the matches that are whole words ( -w )
+    /// - Like in GNU grep, the chars before and after the match can't be word chars, the line edges are fine,
+    ///   so a match that starts or ends with a char that isn't part of a word, like @, can pass too
+    /// - A match that fails the check is retried shorter and at later starts, since the check is part of the regex
+    /// - The steps are put in a group that doesn't capture, so the groups keep their numbers
+    /// # Returns
+    /// * A Regex
+    /// # Example
+    /// let regex = Regex::new("id").unwrap().whole_word();
+    /// assert_eq!(compare_regex_with_expression(&regex, "idx id"), "id");
+    pub fn whole_word(self) -> Self {
+        let restriction = |restriction| RegexStep {
+            val: RegexValue::Restriction(restriction),
+            rep: RegexRep::Exact(1),
+        };
+        Regex {
+            steps: vec![
+                restriction(RegexRestriction::NotAfterWordChar),
+                Regex::group(self.steps),
+                restriction(RegexRestriction::NotBeforeWordChar),
+            ],
+            backtracking: self.backtracking,
         }
+    }
 
-        if backtracking.as_ref().map_or(true, Vec::is_empty) {
-            backtracking = None;
+    /// Restricts the regex to the matches that are the whole line, like ^(regex)$ ( -x )
+    /// # Returns
+    /// * A Regex
+    /// # Example
+    /// let regex = Regex::new("a|ab").unwrap().whole_line();
+    /// assert_eq!(compare_regex_with_expression(&regex, "ab"), "ab");
+    pub fn whole_line(self) -> Self {
+        Regex {
+            steps: vec![Regex::group(self.steps)],
+            backtracking: Some(vec![
+                RegexRestriction::StartOfLine,
+                RegexRestriction::EndOfLine,
+            ]),
         }
+    }
 
-        Ok(Regex {
-            steps,
-            backtracking,
-        })
+    /// Returns a step with a group that doesn't capture, with the steps as its only alternative
+    fn group(steps: Vec<RegexStep>) -> RegexStep {
+        RegexStep {
+            val: RegexValue::Group(vec![steps]),
+            rep: RegexRep::Exact(1),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::char_set::{CharSet, SetItem};
 
     mod regex_new {
         use crate::metachars::RegexClass;
@@ -324,7 +395,14 @@ mod tests {
                 Regex {
                     steps: vec![
                         RegexStep {
-                            val: RegexValue::OneOf(vec!['a', 'b', 'c']),
+                            val: RegexValue::Set(CharSet {
+                                negated: false,
+                                items: vec![
+                                    SetItem::Char('a'),
+                                    SetItem::Char('b'),
+                                    SetItem::Char('c')
+                                ],
+                            }),
                             rep: RegexRep::Exact(1),
                         },
                         RegexStep {
@@ -345,8 +423,15 @@ mod tests {
                 Regex {
                     steps: vec![
                         RegexStep {
-                            val: RegexValue::OneOf(vec!['a', 'b', 'c']),
-                            rep: RegexRep::None,
+                            val: RegexValue::Set(CharSet {
+                                negated: true,
+                                items: vec![
+                                    SetItem::Char('a'),
+                                    SetItem::Char('b'),
+                                    SetItem::Char('c')
+                                ],
+                            }),
+                            rep: RegexRep::Exact(1),
                         },
                         RegexStep {
                             val: RegexValue::Literal('d'),
@@ -363,8 +448,11 @@ mod tests {
                 Regex {
                     steps: vec![
                         RegexStep {
-                            val: RegexValue::Class(RegexClass::Lower),
-                            rep: RegexRep::None,
+                            val: RegexValue::Set(CharSet {
+                                negated: true,
+                                items: vec![SetItem::Range('a', 'z')],
+                            }),
+                            rep: RegexRep::Exact(1),
                         },
                         RegexStep {
                             val: RegexValue::Literal('d'),
@@ -445,6 +533,63 @@ mod tests {
             );
         }
 
+        #[test]
+        fn alternatives() {
+            let regex = Regex::new("a|b").unwrap();
+            assert_eq!(
+                regex,
+                Regex {
+                    steps: vec![RegexStep {
+                        val: RegexValue::Group(vec![
+                            vec![RegexStep {
+                                val: RegexValue::Literal('a'),
+                                rep: RegexRep::Exact(1),
+                            }],
+                            vec![RegexStep {
+                                val: RegexValue::Literal('b'),
+                                rep: RegexRep::Exact(1),
+                            }],
+                        ]),
+                        rep: RegexRep::Exact(1),
+                    }],
+                    backtracking: None,
+                }
+            );
+        }
+
+        #[test]
+        fn group() {
+            let regex = Regex::new("^(ab)*$").unwrap();
+            assert_eq!(
+                regex,
+                Regex {
+                    steps: vec![RegexStep {
+                        val: RegexValue::Capture(
+                            1,
+                            vec![vec![
+                                RegexStep {
+                                    val: RegexValue::Literal('a'),
+                                    rep: RegexRep::Exact(1),
+                                },
+                                RegexStep {
+                                    val: RegexValue::Literal('b'),
+                                    rep: RegexRep::Exact(1),
+                                },
+                            ]]
+                        ),
+                        rep: RegexRep::Range {
+                            min: None,
+                            max: None,
+                        },
+                    }],
+                    backtracking: Some(vec![
+                        RegexRestriction::StartOfLine,
+                        RegexRestriction::EndOfLine
+                    ]),
+                }
+            );
+        }
+
         #[test]
         fn escape_sequence() {
             let regex = Regex::new("\\.").unwrap();
@@ -547,7 +692,10 @@ mod tests {
                             rep: RegexRep::Exact(1),
                         },
                         RegexStep {
-                            val: RegexValue::OneOf(vec!['b', 'c']),
+                            val: RegexValue::Set(CharSet {
+                                negated: false,
+                                items: vec![SetItem::Char('b'), SetItem::Char('c')],
+                            }),
                             rep: RegexRep::Exact(1),
                         },
                         RegexStep {
@@ -612,7 +760,16 @@ mod tests {
                             rep: RegexRep::Exact(1),
                         },
                         RegexStep {
-                            val: RegexValue::OneOf(vec!['a', 'e', 'i', 'o', 'u']),
+                            val: RegexValue::Set(CharSet {
+                                negated: false,
+                                items: vec![
+                                    SetItem::Char('a'),
+                                    SetItem::Char('e'),
+                                    SetItem::Char('i'),
+                                    SetItem::Char('o'),
+                                    SetItem::Char('u')
+                                ],
+                            }),
                             rep: RegexRep::Exact(1),
                         },
                         RegexStep {
@@ -693,8 +850,17 @@ mod tests {
                             rep: RegexRep::Exact(1),
                         },
                         RegexStep {
-                            val: RegexValue::OneOf(vec!['a', 'e', 'i', 'o', 'u']),
-                            rep: RegexRep::None,
+                            val: RegexValue::Set(CharSet {
+                                negated: true,
+                                items: vec![
+                                    SetItem::Char('a'),
+                                    SetItem::Char('e'),
+                                    SetItem::Char('i'),
+                                    SetItem::Char('o'),
+                                    SetItem::Char('u')
+                                ],
+                            }),
+                            rep: RegexRep::Exact(1),
                         },
                         RegexStep {
                             val: RegexValue::Literal(' '),
@@ -0,0 +1,178 @@
+use std::fmt;
+
+/// Enum to represent the errors found while parsing a regex
+/// - Every error keeps the byte offset of the expression where it was found
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegexError {
+    /// Represents an empty expression
+    EmptyExpression,
+    /// Represents a \ at the end of the expression
+    MissingEscapedChar { position: usize },
+    /// Represents a quantifier without a step to repeat, like *a or (+a)
+    NothingToRepeat { quantifier: char, position: usize },
+    /// Represents a repetition that can't be parsed, like a{x}
+    InvalidRepetition { position: usize },
+    /// Represents a { without its }, like a{2
+    UnclosedRepetition { position: usize },
+    /// Represents a repetition whose minimum is greater than its maximum, like a{3,2}
+    InvalidRepetitionRange { position: usize },
+    /// Represents a quantifier right after another one, like a** or a{2}+
+    RepeatedQuantifier { quantifier: char, position: usize },
+    /// Represents a regex that compiles to more instructions than the limit, like (a{1000}){1000}
+    TooBig { limit: usize },
+    /// Represents a [ without its ], like [] or [^]
+    UnclosedBrackets { position: usize },
+    /// Represents a range whose end is before its start, like [z-a]
+    InvalidRange { position: usize },
+    /// Represents an unknown class, equivalence class or collating symbol, like [[:alfa:]]
+    InvalidClass { position: usize },
+    /// Represents a ( without its )
+    UnclosedGroup { position: usize },
+    /// Represents a ) without its (
+    UnmatchedParen { position: usize },
+    /// Represents a backreference to a group that doesn't exist, like \2 in (a)\2
+    InvalidBackreference { position: usize },
+    /// Represents a group name that isn't valid, like (?P<1a>a) or (?P<a
+    InvalidGroupName { position: usize },
+    /// Represents a group name used by another group, like (?P<a>x)(?P<a>y)
+    DuplicateGroupName { position: usize },
+    /// Represents a search that explores more states than the limit, like ([a-z]{3,})\1 in a long line
+    BacktrackLimit { limit: usize },
+}
+
+/// Implementation of the RegexError enum
+impl RegexError {
+    /// Returns the byte offset of the expression where the error was found
+    /// # Returns
+    /// * A usize
+    /// # Example
+    /// let error = RegexError::UnclosedGroup { position: 2 };
+    /// assert_eq!(error.position(), 2);
+    pub fn position(&self) -> usize {
+        match self {
+            RegexError::EmptyExpression
+            | RegexError::TooBig { .. }
+            | RegexError::BacktrackLimit { .. } => 0,
+            RegexError::MissingEscapedChar { position }
+            | RegexError::NothingToRepeat { position, .. }
+            | RegexError::InvalidRepetition { position }
+            | RegexError::UnclosedRepetition { position }
+            | RegexError::InvalidRepetitionRange { position }
+            | RegexError::RepeatedQuantifier { position, .. }
+            | RegexError::UnclosedBrackets { position }
+            | RegexError::InvalidRange { position }
+            | RegexError::InvalidClass { position }
+            | RegexError::UnclosedGroup { position }
+            | RegexError::UnmatchedParen { position }
+            | RegexError::InvalidBackreference { position }
+            | RegexError::InvalidGroupName { position }
+            | RegexError::DuplicateGroupName { position } => *position,
+        }
+    }
+
+    /// Shows the error with the expression and a caret under the column where it was found
+    /// # Arguments
+    /// * `expression` - A string slice that holds the regex expression
+    /// # Returns
+    /// * A String
+    /// # Example
+    /// let error = RegexError::UnclosedGroup { position: 2 };
+    /// let result = error.show("ab(cd");
+    /// assert_eq!(result, "Unclosed group\n    ab(cd\n      ^");
+    pub fn show(&self, expression: &str) -> String {
+        let position = self.position().min(expression.len());
+        let column = expression
+            .char_indices()
+            .take_while(|(i, _)| *i < position)
+            .count();
+        format!("{}\n    {}\n    {}^", self, expression, " ".repeat(column))
+    }
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegexError::EmptyExpression => write!(f, "Empty expression"),
+            RegexError::MissingEscapedChar { .. } => {
+                write!(f, "Expected a character after \\")
+            }
+            RegexError::NothingToRepeat { quantifier, .. } => {
+                write!(
+                    f,
+                    "Unexpected '{}' character, nothing to repeat",
+                    quantifier
+                )
+            }
+            RegexError::InvalidRepetition { .. } => write!(f, "Invalid repetition syntax"),
+            RegexError::UnclosedRepetition { .. } => write!(f, "Unclosed repetition"),
+            RegexError::InvalidRepetitionRange { .. } => {
+                write!(
+                    f,
+                    "Invalid repetition range, the minimum is greater than the maximum"
+                )
+            }
+            RegexError::RepeatedQuantifier { quantifier, .. } => {
+                write!(
+                    f,
+                    "Unexpected '{}' character, the step is already repeated",
+                    quantifier
+                )
+            }
+            RegexError::TooBig { limit } => write!(
+                f,
+                "Regular expression too big, it has more than {} instructions",
+                limit
+            ),
+            RegexError::UnclosedBrackets { .. } => write!(f, "Unclosed brackets"),
+            RegexError::InvalidRange { .. } => write!(f, "Invalid range end"),
+            RegexError::InvalidClass { .. } => write!(f, "Invalid character class"),
+            RegexError::UnclosedGroup { .. } => write!(f, "Unclosed group"),
+            RegexError::UnmatchedParen { .. } => write!(f, "Unmatched ')' character"),
+            RegexError::InvalidBackreference { .. } => write!(f, "Invalid back reference"),
+            RegexError::InvalidGroupName { .. } => write!(f, "Invalid group name"),
+            RegexError::DuplicateGroupName { .. } => write!(f, "Duplicate group name"),
+            RegexError::BacktrackLimit { limit } => write!(
+                f,
+                "Backtracking limit exceeded, the search explored more than {} states",
+                limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn show_caret() {
+        let error = RegexError::UnclosedGroup { position: 2 };
+        assert_eq!(error.show("ab(cd"), "Unclosed group\n    ab(cd\n      ^");
+    }
+
+    #[test]
+    fn show_caret_at_start() {
+        let error = RegexError::NothingToRepeat {
+            quantifier: '*',
+            position: 0,
+        };
+        assert_eq!(
+            error.show("*a"),
+            "Unexpected '*' character, nothing to repeat\n    *a\n    ^"
+        );
+    }
+
+    #[test]
+    fn show_caret_after_multibyte() {
+        let error = RegexError::UnclosedGroup { position: 3 };
+        assert_eq!(error.show("añ(b"), "Unclosed group\n    añ(b\n      ^");
+    }
+
+    #[test]
+    fn is_error() {
+        let error: Box<dyn std::error::Error> = Box::new(RegexError::EmptyExpression);
+        assert_eq!(error.to_string(), "Empty expression");
+    }
+}
@@ -0,0 +1,34 @@
+/// Struct to represent a match of a regex in a word
+/// - It has the byte offsets where the match starts and ends
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    /// The byte offset of the first char of the match
+    pub start: usize,
+    /// The byte offset after the last char of the match
+    pub end: usize,
+}
+
+/// Implementation of the Match struct
+impl Match {
+    /// Returns the matched part of the word
+    /// # Arguments
+    /// * `word` - The word where the match was found
+    /// # Returns
+    /// * A string slice
+    /// # Example
+    /// let m = Match { start: 1, end: 3 };
+    /// assert_eq!(m.as_str("hola"), "ol");
+    pub fn as_str<'a>(&self, word: &'a str) -> &'a str {
+        &word[self.start..self.end]
+    }
+
+    /// Returns the length of the match in bytes
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns whether the match is empty, like the ones of a*
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
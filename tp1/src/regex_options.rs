@@ -0,0 +1,12 @@
+/// Struct to represent the options used to match a regex
+/// - The default options match Unicode text
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegexOptions {
+    /// Whether the classes only match ascii characters, like in the C locale
+    pub ascii: bool,
+    /// Whether the letters match regardless of their case ( -i )
+    pub ignore_case: bool,
+    /// Whether the matches are found like in Perl ( -P ), the leftmost match is the first one
+    /// that the priorities of the alternatives and quantifiers reach, instead of the longest one
+    pub perl: bool,
+}
@@ -1,6 +1,7 @@
 /// Represents the repetition of a regex.
-/// - It can be an exact number of times, a range of times or none.
-#[derive(Debug, PartialEq)]
+/// - It can be an exact number of times or a range of times.
+/// - A range is greedy, it prefers to repeat the step as many times as it can, unless it is lazy or possessive
+#[derive(Debug, Clone, PartialEq)]
 pub enum RegexRep {
     /// Represents the repetition of a regex's step exactly n times
     Exact(usize),
@@ -9,6 +10,32 @@ pub enum RegexRep {
         min: Option<usize>,
         max: Option<usize>,
     },
-    /// Represents the repetition of a regex's step that is value is not in the word
-    None,
+    /// Represents a range that prefers to repeat the step as few times as it can, like a*?
+    Lazy {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// Represents a range that repeats the step as many times as it can and never gives them back, like a*+
+    Possessive {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+}
+
+/// Implementation of the RegexRep enum
+impl RegexRep {
+    /// Returns the minimum and the maximum number of repetitions, None if there is no maximum
+    /// # Returns
+    /// * A tuple with the minimum and the maximum
+    /// # Example
+    /// let rep = RegexRep::Lazy { min: None, max: Some(2) };
+    /// assert_eq!(rep.bounds(), (0, Some(2)));
+    pub fn bounds(&self) -> (usize, Option<usize>) {
+        match self {
+            RegexRep::Exact(count) => (*count, Some(*count)),
+            RegexRep::Range { min, max }
+            | RegexRep::Lazy { min, max }
+            | RegexRep::Possessive { min, max } => (min.unwrap_or(0), *max),
+        }
+    }
 }
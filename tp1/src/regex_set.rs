@@ -0,0 +1,279 @@
+use crate::dfa::Dfa;
+use crate::matching::find_match;
+use crate::nfa::{Inst, Program};
+use crate::parser::Syntax;
+use crate::prefilter::Literals;
+use crate::regex::Regex;
+use crate::regex_error::RegexError;
+use crate::regex_options::RegexOptions;
+
+/// Struct to represent many regexes that are matched together
+/// - The regexes are joined into one automaton, so a line is scanned once to know which of them match,
+///   instead of once for each regex
+/// - The regexes with backreferences or atomic parts can't be part of the automaton, they are matched one by one
+/// # Example
+/// let mut set = RegexSet::new(&["ERROR", "time(out)?", "^WARN"]).unwrap();
+/// assert_eq!(set.matches("ERROR timeout"), Ok(vec![0, 1]));
+pub struct RegexSet {
+    /// The number of regexes
+    len: usize,
+    /// The DFA of the regexes joined into one program, None if there are none
+    dfa: Option<Dfa>,
+    /// The regex of each Match instruction of the joined program, by its position
+    patterns: Vec<Option<usize>>,
+    /// The regexes that are matched one by one, with their index
+    others: Vec<(usize, Program)>,
+    /// The required literals of the joined regexes, None if one of them has none
+    /// - A line without any of them is skipped without running the DFA
+    literals: Option<Literals>,
+}
+
+/// Implementation of the RegexSet struct
+impl RegexSet {
+    /// Compiles the expressions with the default options
+    /// # Arguments
+    /// * `expressions` - A slice with the expressions
+    /// # Returns
+    /// * A Result with a RegexSet or the error of the first expression that isn't valid
+    pub fn new<S: AsRef<str>>(expressions: &[S]) -> Result<Self, RegexError> {
+        RegexSet::with_options(expressions, RegexOptions::default())
+    }
+
+    /// Compiles the expressions with the given options
+    /// # Arguments
+    /// * `expressions` - A slice with the expressions
+    /// * `options` - A RegexOptions
+    /// # Returns
+    /// * A Result with a RegexSet or the error of the first expression that isn't valid
+    pub fn with_options<S: AsRef<str>>(
+        expressions: &[S],
+        options: RegexOptions,
+    ) -> Result<Self, RegexError> {
+        let syntax = Syntax {
+            perl: options.perl,
+            ..Default::default()
+        };
+        let mut programs = Vec::new();
+        for expression in expressions {
+            let regex = Regex::with_syntax(expression.as_ref(), &syntax)?;
+            programs.push(Program::with_options(&regex, options.clone()));
+        }
+        Ok(RegexSet::from_programs(programs))
+    }
+
+    /// Creates a set from compiled regexes, which must have the same options
+    /// # Arguments
+    /// * `programs` - A vector of Program
+    /// # Returns
+    /// * A RegexSet
+    pub fn from_programs(programs: Vec<Program>) -> Self {
+        let len = programs.len();
+        let mut joined = Vec::new();
+        let mut others = Vec::new();
+        for (index, program) in programs.into_iter().enumerate() {
+            match program.has_backrefs || program.has_atomic {
+                true => others.push((index, program)),
+                false => joined.push((index, program)),
+            }
+        }
+        let literals = match joined.len() > 1 {
+            true => joined
+                .iter()
+                .map(|(_, program)| program.literal.clone())
+                .collect::<Option<Vec<String>>>()
+                .map(|literals| Literals::new(&literals)),
+            false => None,
+        };
+        let (dfa, patterns) = match join(joined) {
+            Some((program, patterns)) => (Some(Dfa::new(program)), patterns),
+            None => (None, vec![]),
+        };
+        RegexSet {
+            len,
+            dfa,
+            patterns,
+            others,
+            literals,
+        }
+    }
+
+    /// Returns the number of regexes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no regexes
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks if any of the regexes matches the word
+    /// - The scan stops at the first match
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with a boolean, or a BacktrackLimit error
+    pub fn is_match(&mut self, word: &str) -> Result<bool, RegexError> {
+        let literals_in = self.literals_in(word);
+        if let Some(dfa) = self.dfa.as_mut().filter(|_| literals_in) {
+            if dfa.is_match(word)? {
+                return Ok(true);
+            }
+        }
+        for (_, program) in self.others.iter() {
+            if find_match(program, word)?.is_some() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the indexes of the regexes that match the word, sorted
+    /// # Arguments
+    /// * `word` - A reference to a str
+    /// # Returns
+    /// * A Result with a vector with the indexes, or a BacktrackLimit error
+    /// # Example
+    /// let mut set = RegexSet::new(&["a", "b", "c"]).unwrap();
+    /// assert_eq!(set.matches("cab"), Ok(vec![0, 1, 2]));
+    pub fn matches(&mut self, word: &str) -> Result<Vec<usize>, RegexError> {
+        let literals_in = self.literals_in(word);
+        let mut matched: Vec<usize> = match self.dfa.as_mut().filter(|_| literals_in) {
+            Some(dfa) => dfa
+                .matches(word)?
+                .into_iter()
+                .filter_map(|pc| self.patterns[pc])
+                .collect(),
+            None => vec![],
+        };
+        for (index, program) in self.others.iter() {
+            if find_match(program, word)?.is_some() {
+                matched.push(*index);
+            }
+        }
+        matched.sort_unstable();
+        Ok(matched)
+    }
+
+    /// Checks if the word has one of the required literals of the joined regexes, true if there are none
+    fn literals_in(&self, word: &str) -> bool {
+        self.literals
+            .as_ref()
+            .is_none_or(|literals| literals.is_in(word))
+    }
+}
+
+/// Joins programs into one, that starts all of them at the same time and has a Match for each one
+/// - The first instructions are splits that lead to the start of each program
+/// - The instructions of each program are moved after the ones before it, so their jumps are moved too
+/// - The required literal of a single program is kept, the ones of several programs are checked by the RegexSet
+/// # Returns
+/// * An Option with the program and the regex of each Match instruction, None if there are no programs
+fn join(programs: Vec<(usize, Program)>) -> Option<(Program, Vec<Option<usize>>)> {
+    let (_, first) = programs.first()?;
+    let options = first.options.clone();
+    let literal = match programs.len() {
+        1 => first.literal.clone(),
+        _ => None,
+    };
+    let start_of_line = programs.iter().all(|(_, p)| p.start_of_line);
+    let groups = programs.iter().map(|(_, p)| p.groups).max().unwrap_or(0);
+
+    let count = programs.len();
+    let mut insts = Vec::new();
+    let mut start = count;
+    for (i, (_, program)) in programs.iter().enumerate() {
+        match i + 1 == count {
+            true => insts.push(Inst::Jump(start)),
+            false => insts.push(Inst::Split(start, i + 1)),
+        }
+        start += program.insts.len();
+    }
+    let mut patterns = vec![None; count];
+    for (index, program) in programs {
+        let offset = insts.len();
+        for inst in program.insts {
+            let inst = match inst {
+                Inst::Split(first, second) => Inst::Split(first + offset, second + offset),
+                Inst::Jump(next) => Inst::Jump(next + offset),
+                inst => inst,
+            };
+            patterns.push((inst == Inst::Match).then_some(index));
+            insts.push(inst);
+        }
+    }
+
+    let program = Program {
+        insts,
+        start_of_line,
+        options,
+        groups,
+        names: vec![],
+        has_backrefs: false,
+        has_atomic: false,
+        literal,
+        steps: vec![],
+    };
+    Some((program, patterns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches() {
+        let mut set = RegexSet::new(&["ERROR", "time(out)?", "^WARN", "x|y"]).unwrap();
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.matches("ERROR timeout").unwrap(), vec![0, 1]);
+        assert_eq!(set.matches("WARN x").unwrap(), vec![2, 3]);
+        assert_eq!(set.matches("a WARN").unwrap(), vec![]);
+        assert!(set.is_match("y").unwrap());
+        assert!(!set.is_match("z").unwrap());
+    }
+
+    #[test]
+    fn anchors_and_words() {
+        let mut set = RegexSet::new(&["^a", "a$", "\\bab\\b", ""]).unwrap();
+        assert_eq!(set.matches("ab").unwrap(), vec![0, 2, 3]);
+        assert_eq!(set.matches("ba").unwrap(), vec![1, 3]);
+        assert_eq!(set.matches("cab").unwrap(), vec![3]);
+    }
+
+    #[test]
+    fn backtracking() {
+        let options = RegexOptions {
+            perl: true,
+            ..Default::default()
+        };
+        let mut set = RegexSet::with_options(&["(o)\\1", "a*+a", "b"], options).unwrap();
+        assert_eq!(set.matches("foo b").unwrap(), vec![0, 2]);
+        assert_eq!(set.matches("aaa").unwrap(), vec![]);
+        assert!(set.is_match("oo").unwrap());
+    }
+
+    #[test]
+    fn same_as_each_regex() {
+        let expressions = ["a+b", "b?c", "^ab", "c$", "(ab|ba)+", "[[:digit:]]{2}"];
+        let mut set = RegexSet::new(&expressions).unwrap();
+        let words = ["", "ab", "abc", "bac", "12", "x1x2", "c", "bbbab"];
+        for word in words {
+            let expected: Vec<usize> = (0..expressions.len())
+                .filter(|i| {
+                    let regex = Regex::new(expressions[*i]).unwrap();
+                    find_match(&Program::new(&regex), word).unwrap().is_some()
+                })
+                .collect();
+            assert_eq!(set.matches(word).unwrap(), expected, "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn empty_and_invalid() {
+        let mut set = RegexSet::new::<&str>(&[]).unwrap();
+        assert!(set.is_empty());
+        assert!(!set.is_match("a").unwrap());
+        assert_eq!(set.matches("a").unwrap(), vec![]);
+        assert!(RegexSet::new(&["a", "b{2,1}"]).is_err());
+    }
+}
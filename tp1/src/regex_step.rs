@@ -3,10 +3,10 @@ use crate::regex_val::RegexValue;
 
 /// Struct to represent a regex step
 /// - It has a RegexValue and a RegexRep
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RegexStep {
-    /// The value of the regex step {literal, wildcard, class, oneof}
+    /// The value of the regex step {literal, wildcard, class, set, group, restriction}
     pub val: RegexValue,
-    /// The repetition of the regex step {exact, range, none}
+    /// The repetition of the regex step {exact, range}
     pub rep: RegexRep,
 }
@@ -1,7 +1,10 @@
+use crate::char_set::CharSet;
 use crate::metachars::RegexClass;
+use crate::regex_step::RegexStep;
+use crate::type_of_line::RegexRestriction;
 
 /// Enum to represent a regex value
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RegexValue {
     /// Represents a literal character
     Literal(char),
@@ -9,6 +12,19 @@ pub enum RegexValue {
     Wildcard,
     /// Represents a class of characters
     Class(RegexClass),
-    /// Represents one of the characters in the vector
-    OneOf(Vec<char>),
+    /// Represents the set of characters of a bracket expression
+    Set(CharSet),
+    /// Represents a group that doesn't capture, like the alternatives of the whole expression
+    /// - It has its alternatives, each one a vector of RegexStep
+    Group(Vec<Vec<RegexStep>>),
+    /// Represents a capturing group ( ... ), with its number and its alternatives
+    /// - The groups are numbered from 1, in the order of their (
+    Capture(usize, Vec<Vec<RegexStep>>),
+    /// Represents a named capturing group (?P<name> ... ), with its number, its name and its alternatives
+    /// - It is numbered like the other groups, and can also be referred to by its name
+    NamedCapture(usize, String, Vec<Vec<RegexStep>>),
+    /// Represents a backreference, like \1, that matches the same text as the group
+    Backreference(usize),
+    /// Represents a restriction that doesn't consume characters, like ^ inside a group
+    Restriction(RegexRestriction),
 }
@@ -1,30 +1,41 @@
+use crate::parser::{parse_expression_with, Syntax};
 use crate::regex::Regex;
+use crate::regex_error::RegexError;
 
 /// Function to create regular expressions
-/// - It receives a string and returns a vector of regular expressions
+/// - It receives a string and returns a vector of regular expressions, one for each top-level alternative
+/// - Alternatives inside groups, brackets or escaped with \ are not split
 /// # Arguments
 /// * `expression` - A string that represents a regular expression
 /// # Returns
 /// * A vector of regular expressions or an error
-pub fn create_regular_expressions(expression: &str) -> Result<Vec<Regex>, &'static str> {
+pub fn create_regular_expressions(expression: &str) -> Result<Vec<Regex>, RegexError> {
+    create_regular_expressions_with(expression, &Syntax::default())
+}
+
+/// Function to create regular expressions, parsed with a Syntax
+/// # Arguments
+/// * `expression` - A string that represents a regular expression
+/// * `syntax` - A reference to a Syntax
+/// # Returns
+/// * A vector of regular expressions or an error
+pub fn create_regular_expressions_with(
+    expression: &str,
+    syntax: &Syntax,
+) -> Result<Vec<Regex>, RegexError> {
     if expression.is_empty() {
-        return Err("Empty expression");
-    }
-    let parts: Vec<&str> = expression.split('|').collect();
-    let mut regexes: Vec<Regex> = Vec::new();
-    for part in parts {
-        let regex = Regex::new(part);
-        match regex {
-            Ok(r) => regexes.push(r),
-            Err(_) => break,
-        }
+        return Err(RegexError::EmptyExpression);
     }
-    Ok(regexes)
+    let alternatives = parse_expression_with(expression, syntax)?;
+    Ok(alternatives.into_iter().map(Regex::from_steps).collect())
 }
 
 #[cfg(test)]
 mod regexes_creation_tests {
-    use crate::{regex_rep::RegexRep, regex_step::RegexStep, regex_val::RegexValue};
+    use crate::{
+        regex_rep::RegexRep, regex_step::RegexStep, regex_val::RegexValue,
+        type_of_line::RegexRestriction,
+    };
 
     use super::*;
 
@@ -64,6 +75,58 @@ mod regexes_creation_tests {
     fn test_2() {
         let expression = "";
         let result = create_regular_expressions(expression);
-        assert_eq!(result, Err("Empty expression"));
+        assert_eq!(result, Err(RegexError::EmptyExpression));
+    }
+
+    #[test]
+    fn test_3() {
+        let expression = "(foo|bar)baz|a\\|b";
+        let result = create_regular_expressions(expression).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            result[1],
+            Regex {
+                steps: vec![
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Literal('a')
+                    },
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Literal('|')
+                    },
+                    RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Literal('b')
+                    }
+                ],
+                backtracking: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_4() {
+        let expression = "^a|b$";
+        let result = create_regular_expressions(expression);
+        assert_eq!(
+            result,
+            Ok(vec![
+                Regex {
+                    steps: vec![RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Literal('a')
+                    }],
+                    backtracking: Some(vec![RegexRestriction::StartOfLine])
+                },
+                Regex {
+                    steps: vec![RegexStep {
+                        rep: RegexRep::Exact(1),
+                        val: RegexValue::Literal('b')
+                    }],
+                    backtracking: Some(vec![RegexRestriction::EndOfLine])
+                }
+            ])
+        );
     }
 }
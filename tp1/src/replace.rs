@@ -0,0 +1,321 @@
+use crate::bytes::Decoded;
+use crate::captures::Captures;
+use crate::input::LineReader;
+use crate::matching::captures_iter;
+use crate::nfa::Program;
+use crate::regex_error::RegexError;
+use crate::regex_match::Match;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+/// Replaces the matches of a line with the expansion of a template ( --replace )
+/// - The captures must have the offsets of the bytes, be in order and not overlap
+/// # Arguments
+/// * `bytes` - The bytes of the line
+/// * `template` - The replacement template, expanded like in Captures::expand
+/// * `all` - A slice with the Captures of each match
+/// # Returns
+/// * The bytes of the new line, and where each replacement is in it, to color them
+/// # Example
+/// let captures = vec![Captures::new(vec![Some(1), Some(3), Some(2), Some(3)])];
+/// let (line, replaced) = replace_line(b"abc", "<$1>", &captures);
+/// assert_eq!(line, b"a<c>");
+/// assert_eq!(replaced, vec![Match { start: 1, end: 4 }]);
+pub fn replace_line(bytes: &[u8], template: &str, all: &[Captures]) -> (Vec<u8>, Vec<Match>) {
+    let mut line = Vec::with_capacity(bytes.len());
+    let mut replaced = Vec::with_capacity(all.len());
+    let mut last = 0;
+    for captures in all {
+        let Some(m) = captures.get(0) else {
+            continue;
+        };
+        line.extend_from_slice(&bytes[last..m.start]);
+        let start = line.len();
+        captures.expand_bytes(template, bytes, &mut line);
+        replaced.push(Match {
+            start,
+            end: line.len(),
+        });
+        last = m.end;
+    }
+    line.extend_from_slice(&bytes[last..]);
+    (line, replaced)
+}
+
+/// Finds the matches of the compiled regexes in a line, with the offsets of its bytes
+/// # Arguments
+/// * `programs` - A slice of Program
+/// * `bytes` - The bytes of the line
+/// # Returns
+/// * A Result with a vector with the Captures of each match, or a BacktrackLimit error
+pub fn byte_captures(programs: &[Program], bytes: &[u8]) -> Result<Vec<Captures>, RegexError> {
+    let decoded = Decoded::new(bytes);
+    Ok(captures_iter(programs, decoded.as_str())?
+        .into_iter()
+        .map(|captures| decoded.byte_captures(captures))
+        .collect())
+}
+
+/// Replaces the matches in every line of a file and writes it again ( --write )
+/// - The file is read one line at a time and the new lines are written to a TempFile, so it is never all in memory
+/// - The line endings are kept as they are, and the file isn't written if no line changes
+/// - A binary file is only rewritten when `text` is true ( -a ), otherwise it is an error
+/// # Arguments
+/// * `path` - A string slice with the path
+/// * `programs` - A slice of Program
+/// * `template` - The replacement template
+/// * `text` - Whether the binary files are rewritten too
+/// # Returns
+/// * A Result with the number of lines that changed, or the io error, which is also used for a BacktrackLimit error
+pub fn rewrite_file(
+    path: &str,
+    programs: &[Program],
+    template: &str,
+    text: bool,
+) -> io::Result<usize> {
+    let mut reader = LineReader::new(BufReader::new(fs::File::open(path)?))?;
+    let mut output: Option<TempFile> = None;
+    let mut changed = 0;
+    while let Some(line) = reader.next_bytes()? {
+        if !text && reader.is_binary() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "binary file, use -a to rewrite it",
+            ));
+        }
+        let all = byte_captures(programs, &line).map_err(io::Error::other)?;
+        let new_line = (!all.is_empty())
+            .then(|| replace_line(&line, template, &all).0)
+            .filter(|new_line| *new_line != line);
+        let temp = match &mut output {
+            Some(temp) => temp,
+            None if new_line.is_none() => continue,
+            None => {
+                // The lines before the first one that changes are copied as they are
+                let mut temp = TempFile::create(path)?;
+                let before = reader.offset() as u64;
+                io::copy(&mut fs::File::open(path)?.take(before), &mut temp)?;
+                output.insert(temp)
+            }
+        };
+        changed += usize::from(new_line.is_some());
+        temp.write_all(new_line.as_deref().unwrap_or(&line))?;
+        temp.write_all(reader.line_ending())?;
+    }
+
+    if let Some(temp) = output {
+        temp.persist()?;
+    }
+    Ok(changed)
+}
+
+/// Struct to represent a temporary file in the directory of a file, that is renamed over it to replace it
+/// - A reader of the file sees the old content or the new one, never a part of it, even if the program stops
+/// - A symbolic link is resolved first, so the file it points to is replaced and the link is kept
+/// - The temporary file is removed if it is dropped before it is renamed
+pub struct TempFile {
+    /// The path of the temporary file
+    path: PathBuf,
+    /// The path of the file it replaces, without symbolic links
+    target: PathBuf,
+    /// The buffered temporary file
+    file: BufWriter<fs::File>,
+    /// Whether the temporary file was renamed over the file
+    renamed: bool,
+}
+
+/// Implementation of the TempFile struct
+impl TempFile {
+    /// Creates the temporary file of a file
+    /// # Arguments
+    /// * `path` - A string slice with the path of the file, that must exist
+    /// # Returns
+    /// * A Result with the TempFile or the io error
+    /// # Example
+    /// let mut temp = TempFile::create("notes.txt").unwrap();
+    /// temp.write_all(b"new notes").unwrap();
+    /// temp.persist().unwrap();
+    pub fn create(path: &str) -> io::Result<Self> {
+        let target = fs::canonicalize(path)?;
+        let name = target
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?;
+        let path = target.with_file_name(format!(
+            ".{}.tp1-{}",
+            name.to_string_lossy(),
+            std::process::id()
+        ));
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(TempFile {
+            path,
+            target,
+            file: BufWriter::new(file),
+            renamed: false,
+        })
+    }
+
+    /// Renames the temporary file over the file, with the permissions of the file
+    /// # Returns
+    /// * A Result with the io error, the file is left as it was if there is one
+    pub fn persist(mut self) -> io::Result<()> {
+        let permissions = fs::metadata(&self.target)?.permissions();
+        self.file.flush()?;
+        let file = self.file.get_ref();
+        file.set_permissions(permissions)?;
+        file.sync_all()?;
+        fs::rename(&self.path, &self.target)?;
+        self.renamed = true;
+        Ok(())
+    }
+}
+
+/// Writes to the buffered temporary file
+impl Write for TempFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Removes the temporary file if it wasn't renamed
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.renamed {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Writes a file through a TempFile, so it is replaced atomically
+/// - The new file keeps the permissions of the old one, and a symbolic link to it is kept
+/// # Arguments
+/// * `path` - A string slice with the path
+/// * `content` - The new content of the file
+/// # Returns
+/// * A Result with the io error
+pub fn write_atomically(path: &str, content: &[u8]) -> io::Result<()> {
+    let mut temp = TempFile::create(path)?;
+    temp.write_all(content)?;
+    temp.persist()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::Regex;
+    use std::path::Path;
+
+    fn programs(expression: &str) -> Vec<Program> {
+        vec![Program::new(&Regex::new(expression).unwrap())]
+    }
+
+    fn temp_file(name: &str, content: &[u8]) -> String {
+        let dir = std::env::temp_dir().join(format!("tp1_replace_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("file.txt");
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn replace() {
+        let programs = programs("([a-z]+)=([0-9]+)");
+        let all = byte_captures(&programs, b"a=1, bc=23").unwrap();
+        let (line, replaced) = replace_line(b"a=1, bc=23", "$2:$1", &all);
+        assert_eq!(line, b"1:a, 23:bc");
+        assert_eq!(
+            replaced,
+            vec![Match { start: 0, end: 3 }, Match { start: 5, end: 10 }]
+        );
+    }
+
+    #[test]
+    fn replace_bytes() {
+        let programs = programs("x(.)");
+        let all = byte_captures(&programs, b"\xe9x\xffx").unwrap();
+        let (line, _) = replace_line(b"\xe9x\xffx", "[$1]", &all);
+        assert_eq!(line, b"\xe9[\xff]x");
+    }
+
+    #[test]
+    fn rewrite() {
+        let path = temp_file("rewrite", b"port=80\r\nname=x\nport=8080");
+        let changed = rewrite_file(&path, &programs("port=([0-9]+)"), "port=9$1", false);
+        assert_eq!(changed.unwrap(), 2);
+        assert_eq!(fs::read(&path).unwrap(), b"port=980\r\nname=x\nport=98080");
+        let entries = fs::read_dir(Path::new(&path).parent().unwrap()).unwrap();
+        assert_eq!(entries.count(), 1);
+    }
+
+    #[test]
+    fn rewrite_nothing() {
+        let path = temp_file("nothing", b"a\nb\n");
+        let before = fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(rewrite_file(&path, &programs("z"), "y", false).unwrap(), 0);
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), before);
+    }
+
+    #[test]
+    fn rewrite_binary() {
+        let path = temp_file("binary", b"caf\xe9\n");
+        assert!(rewrite_file(&path, &programs("caf"), "tea", false).is_err());
+        assert_eq!(
+            rewrite_file(&path, &programs("caf"), "tea", true).unwrap(),
+            1
+        );
+        assert_eq!(fs::read(&path).unwrap(), b"tea\xe9\n");
+    }
+
+    #[test]
+    fn rewrite_missing() {
+        let path = temp_file("missing", b"");
+        assert!(rewrite_file(&format!("{}.none", path), &programs("a"), "b", false).is_err());
+    }
+
+    #[test]
+    fn rewrite_after_unchanged_lines() {
+        let content = "same line\n".repeat(10_000) + "old\nsame\nold";
+        let path = temp_file("unchanged", content.as_bytes());
+        assert_eq!(
+            rewrite_file(&path, &programs("old"), "new", false).unwrap(),
+            2
+        );
+        let expected = "same line\n".repeat(10_000) + "new\nsame\nnew";
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn rewrite_binary_after_a_change() {
+        let path = temp_file("late_binary", b"old\nx\x00y\n");
+        assert!(rewrite_file(&path, &programs("old"), "new", false).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"old\nx\x00y\n");
+        let entries = fs::read_dir(Path::new(&path).parent().unwrap()).unwrap();
+        assert_eq!(entries.count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rewrite_through_symlink() {
+        let path = temp_file("symlink", b"old\n");
+        let link = Path::new(&path).with_file_name("link.txt");
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        let link = link.to_string_lossy().to_string();
+        assert_eq!(
+            rewrite_file(&link, &programs("old"), "new", false).unwrap(),
+            1
+        );
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read(&path).unwrap(), b"new\n");
+    }
+}
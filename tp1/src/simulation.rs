@@ -0,0 +1,307 @@
+use crate::matching::{handle_regex_class, handle_regex_value};
+use crate::metachars::RegexClass;
+use crate::nfa::{Inst, Program};
+use crate::regex_options::RegexOptions;
+use crate::type_of_line::RegexRestriction;
+
+/// Struct to represent the set of active threads of the NFA
+/// - Each thread is a state and the position where its match started
+/// - It keeps the insertion order and checks membership in constant time
+struct ThreadSet {
+    /// The states in insertion order
+    dense: Vec<usize>,
+    /// Whether each state is in the set
+    present: Vec<bool>,
+    /// The start of the match of each state
+    starts: Vec<usize>,
+}
+
+impl ThreadSet {
+    fn new(size: usize) -> Self {
+        ThreadSet {
+            dense: Vec::with_capacity(size),
+            present: vec![false; size],
+            starts: vec![0; size],
+        }
+    }
+
+    fn insert(&mut self, state: usize, start: usize) -> bool {
+        if self.present[state] {
+            return false;
+        }
+        self.present[state] = true;
+        self.starts[state] = start;
+        self.dense.push(state);
+        true
+    }
+
+    fn clear(&mut self) {
+        for state in self.dense.drain(..) {
+            self.present[state] = false;
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+}
+
+/// Checks if the restriction holds at a position of the word
+/// - The word assertions look at the chars before and after the position, a word char is [[:alnum:]_]
+/// # Arguments
+/// * `restriction` - A reference to a RegexRestriction
+/// * `word` - A reference to a str
+/// * `position` - A byte offset of the word
+/// * `options` - A reference to a RegexOptions
+/// # Returns
+/// * A boolean
+/// # Example
+/// let result = handle_restriction(&RegexRestriction::EndOfLine, "ab", 2, &RegexOptions::default());
+/// assert_eq!(result, true);
+pub fn handle_restriction(
+    restriction: &RegexRestriction,
+    word: &str,
+    position: usize,
+    options: &RegexOptions,
+) -> bool {
+    match restriction {
+        RegexRestriction::StartOfLine => position == 0,
+        RegexRestriction::EndOfLine => position == word.len(),
+        RegexRestriction::None => true,
+        _ => {
+            let before = word[..position].chars().next_back();
+            let after = word[position..].chars().next();
+            handle_word_restriction(
+                restriction,
+                before.is_some_and(|c| is_word_char(c, options)),
+                after.is_some_and(|c| is_word_char(c, options)),
+            )
+        }
+    }
+}
+
+/// Checks if a char is part of a word, a letter, a digit or _
+/// # Arguments
+/// * `c` - A char
+/// * `options` - A reference to a RegexOptions
+/// # Returns
+/// * A boolean
+pub fn is_word_char(c: char, options: &RegexOptions) -> bool {
+    c == '_' || handle_regex_class(&RegexClass::Word, c, options)
+}
+
+/// Checks if a word assertion holds between two chars
+/// - The start and the end of the word count as chars that aren't part of a word
+/// # Arguments
+/// * `restriction` - A reference to a RegexRestriction
+/// * `before` - Whether the char before the position is a word char
+/// * `after` - Whether the char after the position is a word char
+/// # Returns
+/// * A boolean, true for the restrictions that aren't word assertions
+/// # Example
+/// let result = handle_word_restriction(&RegexRestriction::StartOfWord, false, true);
+/// assert_eq!(result, true);
+pub fn handle_word_restriction(restriction: &RegexRestriction, before: bool, after: bool) -> bool {
+    match restriction {
+        RegexRestriction::WordBoundary => before != after,
+        RegexRestriction::NotWordBoundary => before == after,
+        RegexRestriction::StartOfWord => !before && after,
+        RegexRestriction::EndOfWord => before && !after,
+        RegexRestriction::NotAfterWordChar => !before,
+        RegexRestriction::NotBeforeWordChar => !after,
+        _ => true,
+    }
+}
+
+/// Adds a thread and every thread reachable from it without consuming characters
+/// - Assertions are checked here, since they depend only on the position
+/// - The saves of the groups don't change the state of the thread here, the positions are only kept by the backtracking
+/// - It uses an explicit stack so long chains of splits can't overflow the call stack
+fn add_thread(
+    program: &Program,
+    set: &mut ThreadSet,
+    stack: &mut Vec<usize>,
+    (state, start): (usize, usize),
+    word: &str,
+    position: usize,
+) {
+    stack.push(state);
+    while let Some(pc) = stack.pop() {
+        if !set.insert(pc, start) {
+            continue;
+        }
+        match &program.insts[pc] {
+            Inst::Jump(next) => stack.push(*next),
+            Inst::Save(_) => stack.push(pc + 1),
+            Inst::Split(first, second) => {
+                stack.push(*second);
+                stack.push(*first);
+            }
+            Inst::Assert(restriction)
+                if handle_restriction(restriction, word, position, &program.options) =>
+            {
+                stack.push(pc + 1);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Checks if the state consumes the character
+fn consumes(program: &Program, inst: &Inst, c: char) -> bool {
+    match inst {
+        Inst::Char(val) => handle_regex_value(val, c, &program.options),
+        _ => false,
+    }
+}
+
+/// Runs the NFA over the word from a byte offset and returns the leftmost-longest match
+/// - A new thread is started at every position until a match is found, so a single pass
+///   over the word tries every start position
+/// - Threads that started earlier have priority, and a thread is dropped when a match
+///   that starts before it was already found
+/// - Every character is processed once for the whole set of threads, so the time is
+///   linear in the length of the word
+/// - The anchors still see the whole word, so ^ doesn't match at the offset
+/// # Arguments
+/// * `program` - A reference to a Program
+/// * `word` - A reference to a str
+/// * `from` - The byte offset where the search starts, at the start of a char
+/// # Returns
+/// * An Option with the byte offsets where the match starts and ends
+/// # Example
+/// let program = Program::new(&Regex::new("ab").unwrap());
+/// let result = leftmost_longest_at(&program, "ab ab", 1);
+/// assert_eq!(result, Some((3, 5)));
+pub fn leftmost_longest_at(program: &Program, word: &str, from: usize) -> Option<(usize, usize)> {
+    let size = program.insts.len();
+    let mut current = ThreadSet::new(size);
+    let mut next = ThreadSet::new(size);
+    let mut stack = Vec::new();
+    let mut found: Option<(usize, usize)> = None;
+    let mut position = from;
+
+    loop {
+        if found.is_none() && (position == from || !program.start_of_line) {
+            add_thread(
+                program,
+                &mut current,
+                &mut stack,
+                (0, position),
+                word,
+                position,
+            );
+        }
+        if current.is_empty() {
+            break;
+        }
+
+        let c = word[position..].chars().next();
+        let next_position = position + c.map_or(0, char::len_utf8);
+        for &pc in current.dense.iter() {
+            let start = current.starts[pc];
+            if found.is_some_and(|(found_start, _)| start > found_start) {
+                continue;
+            }
+            match (&program.insts[pc], c) {
+                (Inst::Match, _)
+                    if found.is_none_or(|(found_start, found_end)| {
+                        start < found_start || position > found_end
+                    }) =>
+                {
+                    found = Some((start, position));
+                }
+                (inst, Some(c)) if consumes(program, inst, c) => add_thread(
+                    program,
+                    &mut next,
+                    &mut stack,
+                    (pc + 1, start),
+                    word,
+                    next_position,
+                ),
+                _ => {}
+            }
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        next.clear();
+        if c.is_none() {
+            break;
+        }
+        position = next_position;
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::Regex;
+
+    fn find(expression: &str, word: &str) -> Option<(usize, usize)> {
+        let program = Program::new(&Regex::new(expression).unwrap());
+        leftmost_longest_at(&program, word, 0)
+    }
+
+    #[test]
+    fn literal() {
+        assert_eq!(find("ab", "abc"), Some((0, 2)));
+        assert_eq!(find("ab", "ac"), None);
+    }
+
+    #[test]
+    fn needs_backtracking() {
+        assert_eq!(find("a*ab", "aaab"), Some((0, 4)));
+        assert_eq!(find("[[:alpha:]]+ing", "singing"), Some((0, 7)));
+        assert_eq!(find("a{2,4}a", "aaaaa"), Some((0, 5)));
+    }
+
+    #[test]
+    fn longest_match() {
+        assert_eq!(find("a*", "aaab"), Some((0, 3)));
+        assert_eq!(find("a*", "b"), Some((0, 0)));
+    }
+
+    #[test]
+    fn unanchored() {
+        assert_eq!(find("hola", "dijo hola mundo"), Some((5, 9)));
+        assert_eq!(find("a+", "baaab"), Some((1, 4)));
+        assert_eq!(find("ab", "aab"), Some((1, 3)));
+    }
+
+    #[test]
+    fn leftmost() {
+        assert_eq!(find("b*a", "bba ba"), Some((0, 3)));
+        assert_eq!(find("xa*", "xaa xaaa"), Some((0, 3)));
+    }
+
+    #[test]
+    fn multibyte() {
+        assert_eq!(find("a", "ñandu"), Some((2, 3)));
+        assert_eq!(find("d.", "dñ"), Some((0, 3)));
+    }
+
+    #[test]
+    fn from_offset() {
+        let program = Program::new(&Regex::new("ab").unwrap());
+        assert_eq!(leftmost_longest_at(&program, "ab ab", 1), Some((3, 5)));
+        assert_eq!(leftmost_longest_at(&program, "ab ab", 5), None);
+        let program = Program::new(&Regex::new("^a").unwrap());
+        assert_eq!(leftmost_longest_at(&program, "aa", 1), None);
+    }
+
+    #[test]
+    fn start_of_line() {
+        assert_eq!(find("^hola", "hola mundo"), Some((0, 4)));
+        assert_eq!(find("^hola", "dijo hola"), None);
+    }
+
+    #[test]
+    fn end_of_line() {
+        assert_eq!(find("a*$", "aaa"), Some((0, 3)));
+        assert_eq!(find("a*$", "aab"), Some((3, 3)));
+        assert_eq!(find("mundo$", "hola mundo"), Some((5, 10)));
+        assert_eq!(find("hola$", "hola mundo"), None);
+    }
+}
@@ -1,51 +1,75 @@
+use crate::regex_error::RegexError;
+use crate::regex_rep::RegexRep;
 use crate::regex_step::RegexStep;
+use crate::regex_val::RegexValue;
 
 /// Enum to represent a regex restriction
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RegexRestriction {
     /// Represents the start of line metacharacter
     StartOfLine,
     /// Represents the end of line metacharacter
     EndOfLine,
+    /// Represents a word boundary, \\b
+    WordBoundary,
+    /// Represents a position that isn't a word boundary, \\B
+    NotWordBoundary,
+    /// Represents the start of a word, \\<
+    StartOfWord,
+    /// Represents the end of a word, \\>
+    EndOfWord,
+    /// Represents a position without a word char before it, where a match of -w starts
+    NotAfterWordChar,
+    /// Represents a position without a word char after it, where a match of -w ends
+    NotBeforeWordChar,
     /// Represents the none restriction
     None,
 }
 
 /// Function to handle the start of line metacharacter
-/// - It receives a mutable reference to an Option of a vector of RegexRestriction and returns a Result with an Option of a RegexStep or an error
-/// # Arguments
-/// * `backtracking` - A mutable reference to an Option of a vector of RegexRestriction
+/// - It returns a RegexStep that asserts the start of the line
 /// # Returns
 /// * A Result with an Option of a RegexStep or an error
 /// # Example
-/// let mut backtracking = None;
-/// let result = handle_start_of_line(&mut backtracking);
-/// assert_eq!(result, Ok(None));
-pub fn handle_start_of_line(
-    backtracking: &mut Option<Vec<RegexRestriction>>,
-) -> Result<Option<RegexStep>, &'static str> {
-    if let Some(backtracking_vec) = backtracking {
-        backtracking_vec.push(RegexRestriction::StartOfLine);
-    }
-    Ok(None)
+/// let result = handle_start_of_line();
+/// assert_eq!(result, Ok(Some(RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Restriction(RegexRestriction::StartOfLine) })));
+pub fn handle_start_of_line() -> Result<Option<RegexStep>, RegexError> {
+    Ok(Some(RegexStep {
+        rep: RegexRep::Exact(1),
+        val: RegexValue::Restriction(RegexRestriction::StartOfLine),
+    }))
 }
 
 /// Function to handle the end of line metacharacter
-/// - It receives a mutable reference to an Option of a vector of RegexRestriction and returns a Result with an Option of a RegexStep or an error
-/// # Arguments
-/// * `backtracking` - A mutable reference to an Option of a vector of RegexRestriction
+/// - It returns a RegexStep that asserts the end of the line
 /// # Returns
 /// * A Result with an Option of a RegexStep or an error
 /// # Example
-/// let mut backtracking = None;
-/// let result = handle_end_of_line(&mut backtracking);
-/// assert_eq!(result, Ok(None));
-/// assert_eq!(backtracking, Some(vec![RegexRestriction::EndOfLine]));
-pub fn handle_end_of_line(
-    backtracking: &mut Option<Vec<RegexRestriction>>,
-) -> Result<Option<RegexStep>, &'static str> {
-    if let Some(backtracking_vec) = backtracking {
-        backtracking_vec.push(RegexRestriction::EndOfLine);
-    }
-    Ok(None)
+/// let result = handle_end_of_line();
+/// assert_eq!(result, Ok(Some(RegexStep { rep: RegexRep::Exact(1), val: RegexValue::Restriction(RegexRestriction::EndOfLine) })));
+pub fn handle_end_of_line() -> Result<Option<RegexStep>, RegexError> {
+    Ok(Some(RegexStep {
+        rep: RegexRep::Exact(1),
+        val: RegexValue::Restriction(RegexRestriction::EndOfLine),
+    }))
+}
+
+/// Function to handle the word assertions
+/// - \\b is a word boundary, \\B isn't one, \\< is the start of a word and \\> its end
+/// # Arguments
+/// * `c` - The char after the \\
+/// # Returns
+/// * An Option with the RegexValue, or None if the char isn't a word assertion
+/// # Example
+/// let result = handle_word_assertion('<');
+/// assert_eq!(result, Some(RegexValue::Restriction(RegexRestriction::StartOfWord)));
+pub fn handle_word_assertion(c: char) -> Option<RegexValue> {
+    let restriction = match c {
+        'b' => RegexRestriction::WordBoundary,
+        'B' => RegexRestriction::NotWordBoundary,
+        '<' => RegexRestriction::StartOfWord,
+        '>' => RegexRestriction::EndOfWord,
+        _ => return None,
+    };
+    Some(RegexValue::Restriction(restriction))
 }
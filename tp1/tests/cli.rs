@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Creates an empty directory for a test, with the files and their content
+fn test_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tp1_cli_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (file, content) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+/// Runs the program in a directory, and returns its output and exit code
+fn run(dir: &Path, args: &[&str]) -> (String, i32) {
+    run_with_colors(dir, args, "")
+}
+
+/// Runs the program in a directory with the value of GREP_COLORS, and returns its output and exit code
+fn run_with_colors(dir: &Path, args: &[&str], colors: &str) -> (String, i32) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tp1"))
+        .args(args)
+        .current_dir(dir)
+        .env("GREP_COLORS", colors)
+        .output()
+        .unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        output.status.code().unwrap(),
+    )
+}
+
+#[test]
+fn files_without_match_exit_code() {
+    let dir = test_dir("without_match", &[("f", "x\n"), ("g", "y\n")]);
+    assert_eq!(run(&dir, &["-L", "x", "f"]), (String::new(), 0));
+    assert_eq!(run(&dir, &["-L", "x", "g"]), ("g\n".to_string(), 1));
+    assert_eq!(run(&dir, &["-L", "x", "f", "g"]), ("g\n".to_string(), 0));
+}
+
+#[test]
+fn zero_context_separates_groups() {
+    let dir = test_dir("zero_context", &[("f", "a\nb\na\na\n")]);
+    for context in ["-A0", "-B0", "-C0"] {
+        assert_eq!(
+            run(&dir, &[context, "a", "f"]),
+            ("a\n--\na\na\n".to_string(), 0)
+        );
+    }
+    assert_eq!(run(&dir, &["a", "f"]), ("a\na\na\n".to_string(), 0));
+}
+
+#[test]
+fn recursive_file_names() {
+    let dir = test_dir("recursive", &[("x", "a\n"), ("sub/y", "a\n")]);
+    assert_eq!(run(&dir, &["-r", "a", "x"]), ("a\n".to_string(), 0));
+    assert_eq!(run(&dir, &["-r", "a"]), ("sub/y:a\nx:a\n".to_string(), 0));
+    assert_eq!(
+        run(&dir, &["-r", "a", "."]),
+        ("./sub/y:a\n./x:a\n".to_string(), 0)
+    );
+    assert_eq!(run(&dir, &["-r", "a", "sub"]), ("sub/y:a\n".to_string(), 0));
+}
+
+#[test]
+fn ascii_classes() {
+    let dir = test_dir("ascii", &[("f", "año\n")]);
+    assert_eq!(
+        run(&dir, &["-o", "[[:alpha:]]+", "f"]),
+        ("año\n".to_string(), 0)
+    );
+    assert_eq!(
+        run(&dir, &["--ascii", "-o", "[[:alpha:]]+", "f"]),
+        ("a\no\n".to_string(), 0)
+    );
+}
+
+#[test]
+fn colored_context_matches() {
+    let dir = test_dir("context_colors", &[("f", "xa\nb\n")]);
+    let args = ["--color=always", "-v", "-B1", "a", "f"];
+    assert_eq!(
+        run_with_colors(&dir, &args, "mc=32:ne"),
+        ("x\x1b[32ma\x1b[m\nb\n".to_string(), 0)
+    );
+    assert_eq!(
+        run_with_colors(&dir, &args, "ms=31:mc=32:sl=1:cx=2:rv:ne"),
+        (
+            "\x1b[1mx\x1b[m\x1b[32ma\x1b[m\n\x1b[2mb\x1b[m\n".to_string(),
+            0
+        )
+    );
+}
@@ -0,0 +1,177 @@
+use std::fs;
+
+use tp1::internal::matching::{captures, find_at, find_match};
+use tp1::internal::nfa::Program;
+use tp1::internal::regex::Regex;
+use tp1::internal::regular_expressions::create_regular_expressions;
+use tp1::RegexOptions;
+
+/// The fixtures, in the format of the AT&T testregex suite by Glenn Fowler
+const FIXTURES: [&str; 3] = ["basic.dat", "repetition.dat", "nullsubexpr.dat"];
+
+/// A test vector of a fixture
+/// - The groups that the result lists are checked too, the ones after them aren't
+struct Vector {
+    line: usize,
+    ignore_case: bool,
+    pattern: String,
+    string: String,
+    expected: Expected,
+}
+
+enum Expected {
+    /// The overall match and each group, None for a group that didn't match
+    Match(Vec<Option<(usize, usize)>>),
+    NoMatch,
+    Error,
+}
+
+/// Replaces the C escapes of a field, and NULL with the empty string
+fn unescape(field: &str, escapes: bool) -> String {
+    if field == "NULL" {
+        return String::new();
+    }
+    if !escapes {
+        return field.to_string();
+    }
+    let mut result = String::new();
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let code = u32::from_str_radix(&hex, 16).unwrap();
+                result.push(char::from_u32(code).unwrap());
+            }
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Reads the result of a vector, the first (start,end) pair is the overall match and the next ones are the groups
+fn expected(result: &str) -> Expected {
+    if result == "NOMATCH" {
+        return Expected::NoMatch;
+    }
+    let Some(pairs) = result.strip_prefix('(') else {
+        return Expected::Error;
+    };
+    let groups = pairs
+        .trim_end_matches(')')
+        .split(")(")
+        .map(|pair| {
+            let (start, end) = pair.split_once(',').unwrap();
+            start.parse().ok().zip(end.parse().ok())
+        })
+        .collect();
+    Expected::Match(groups)
+}
+
+/// Reads the extended regular expression vectors of a fixture
+fn vectors(fixture: &str) -> Vec<Vector> {
+    let path = format!("{}/tests/data/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+    let content = fs::read_to_string(path).unwrap();
+    let mut vectors = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').filter(|f| !f.is_empty()).collect();
+        let flags = fields[0];
+        if !flags.contains('E') {
+            continue;
+        }
+        let escapes = flags.contains('$');
+        vectors.push(Vector {
+            line: number + 1,
+            ignore_case: flags.contains('i'),
+            pattern: unescape(fields[1], escapes),
+            string: unescape(fields[2], escapes),
+            expected: expected(fields[3]),
+        });
+    }
+    vectors
+}
+
+/// Runs a vector, returning a description of the failure if the results are not the expected ones
+/// - The expression is run whole as a single regex, and split by create_regular_expressions into one regex for each alternative
+fn run(fixture: &str, vector: &Vector) -> Option<String> {
+    let options = RegexOptions {
+        ignore_case: vector.ignore_case,
+        ..Default::default()
+    };
+    let context = format!(
+        "{}:{}: {:?} on {:?}",
+        fixture, vector.line, vector.pattern, vector.string
+    );
+    let whole = Regex::new(&vector.pattern);
+    let split = create_regular_expressions(&vector.pattern);
+    let groups = match &vector.expected {
+        Expected::Error => {
+            return match (whole, split) {
+                (Err(_), Err(_)) => None,
+                _ => Some(format!("{}: expected an error", context)),
+            };
+        }
+        Expected::NoMatch => None,
+        Expected::Match(groups) => Some(groups),
+    };
+    let expected = groups.map(|groups| groups[0].unwrap());
+    let (whole, split) = match (whole, split) {
+        (Ok(whole), Ok(split)) => (whole, split),
+        (Err(e), _) | (_, Err(e)) => return Some(format!("{}: {}", context, e)),
+    };
+    let program = Program::with_options(&whole, options.clone());
+    let found = find_match(&program, &vector.string);
+    if found != Ok(expected) {
+        return Some(format!("{}: {:?}, expected {:?}", context, found, expected));
+    }
+    if let Some(groups) = groups {
+        let found: Vec<Option<(usize, usize)>> = match captures(&program, &vector.string) {
+            Ok(Some(captures)) => (0..groups.len())
+                .map(|group| captures.get(group).map(|m| (m.start, m.end)))
+                .collect(),
+            other => return Some(format!("{}: captures {:?}", context, other)),
+        };
+        if found != *groups {
+            return Some(format!(
+                "{}: groups {:?}, expected {:?}",
+                context, found, groups
+            ));
+        }
+    }
+    let programs: Vec<Program> = split
+        .iter()
+        .map(|regex| Program::with_options(regex, options.clone()))
+        .collect();
+    let found = find_at(&programs, &vector.string, 0).map(|m| m.map(|m| (m.start, m.end)));
+    if found != Ok(expected) {
+        return Some(format!(
+            "{}: {:?} with the alternatives split, expected {:?}",
+            context, found, expected
+        ));
+    }
+    None
+}
+
+#[test]
+fn leftmost_longest_conformance() {
+    let mut failures = Vec::new();
+    for fixture in FIXTURES {
+        let vectors = vectors(fixture);
+        assert!(!vectors.is_empty(), "{} has no vectors", fixture);
+        failures.extend(vectors.iter().filter_map(|v| run(fixture, v)));
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
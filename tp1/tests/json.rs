@@ -0,0 +1,49 @@
+use std::fs;
+use std::process::Command;
+
+/// Runs the program with --json on a file with the content, and returns its output
+fn json_output(name: &str, content: &[u8], args: &[&str]) -> String {
+    let dir = std::env::temp_dir().join(format!("tp1_json_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("file.txt");
+    fs::write(&path, content).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_tp1"))
+        .arg("--json")
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Returns the number of "match" events, and the number of matched lines in the stats of the "end" event
+fn matches_and_stats(output: &str) -> (usize, usize) {
+    let events = output
+        .lines()
+        .filter(|line| line.starts_with("{\"type\":\"match\""))
+        .count();
+    let end = output
+        .lines()
+        .find(|line| line.starts_with("{\"type\":\"end\""))
+        .unwrap();
+    let start = end.find("\"matched_lines\":").unwrap() + "\"matched_lines\":".len();
+    let digits: String = end[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    (events, digits.parse().unwrap())
+}
+
+#[test]
+fn matched_lines_are_the_match_events() {
+    let output = json_output("text", b"a1\nb\na2\n", &["-A1", "a"]);
+    assert_eq!(matches_and_stats(&output), (2, 2));
+}
+
+#[test]
+fn binary_line_is_not_a_matched_line() {
+    let output = json_output("binary", b"a1\nb\na\xff2\na3\n", &["a"]);
+    assert_eq!(matches_and_stats(&output), (1, 1));
+    assert!(output.contains("\"searches_with_match\":1"));
+}
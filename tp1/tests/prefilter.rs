@@ -0,0 +1,107 @@
+use tp1::internal::dfa::Dfa;
+use tp1::internal::matching::{captures, find_iter};
+use tp1::internal::nfa::Program;
+use tp1::internal::regex::Regex;
+use tp1::RegexOptions;
+
+const EXPRESSIONS: [&str; 13] = [
+    "ERROR [[:digit:]]+",
+    "\\bWARN\\b",
+    "timeout$",
+    "^WARN",
+    "a(bc)+d",
+    "(ab|cd)ef",
+    "x?y?z",
+    "err(or)?",
+    "[[:upper:]]+ [0-9]{2}",
+    "(o)\\1",
+    "ñandú",
+    "a.{2}b",
+    "",
+];
+
+const WORDS: [&str; 8] = [
+    "ERROR", " ", "42", "timeout", "WARN", "abcbcd", "cdef", "ñandú",
+];
+
+/// Builds lines from the words, so that some contain the literals and some only parts of them
+fn lines() -> Vec<String> {
+    let mut lines = vec![String::new()];
+    let mut seed: u32 = 7;
+    for _ in 0..500 {
+        let mut line = String::new();
+        for _ in 0..(seed % 5) {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            let word = WORDS[(seed >> 16) as usize % WORDS.len()];
+            let cut = (seed >> 8) as usize % (word.len() + 1);
+            match word.is_char_boundary(cut) && seed.is_multiple_of(3) {
+                true => line.push_str(&word[..cut]),
+                false => line.push_str(word),
+            }
+        }
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        lines.push(line);
+    }
+    lines
+}
+
+fn programs(expression: &str, options: RegexOptions) -> (Program, Program) {
+    let regex = Regex::new(expression).unwrap();
+    let program = Program::with_options(&regex, options);
+    let mut without = program.clone();
+    without.literal = None;
+    (program, without)
+}
+
+#[test]
+fn same_results_with_and_without_prefilter() {
+    let lines = lines();
+    for ignore_case in [false, true] {
+        let options = RegexOptions {
+            ignore_case,
+            ..Default::default()
+        };
+        for expression in EXPRESSIONS {
+            let (program, without) = programs(expression, options.clone());
+            let mut dfa = Dfa::new(program.clone());
+            let mut dfa_without = Dfa::new(without.clone());
+            for line in lines.iter() {
+                assert_eq!(
+                    dfa.is_match(line),
+                    dfa_without.is_match(line),
+                    "{} on {:?}",
+                    expression,
+                    line
+                );
+                assert_eq!(
+                    find_iter(std::slice::from_ref(&program), line),
+                    find_iter(std::slice::from_ref(&without), line),
+                    "{} on {:?}",
+                    expression,
+                    line
+                );
+                assert_eq!(
+                    captures(&program, line),
+                    captures(&without, line),
+                    "{} on {:?}",
+                    expression,
+                    line
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn prefilter_is_used() {
+    let (program, _) = programs("ERROR [[:digit:]]+", RegexOptions::default());
+    assert_eq!(program.literal, Some("ERROR ".to_string()));
+    let (program, _) = programs(
+        "ERROR [[:digit:]]+",
+        RegexOptions {
+            ignore_case: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(program.literal, None);
+}
@@ -0,0 +1,78 @@
+use tp1::internal::matching::find_match;
+use tp1::internal::nfa::Program;
+use tp1::internal::regex::Regex;
+use tp1::RegexOptions;
+use tp1::RegexSet;
+
+/// Returns the next number of a linear congruential generator, so the words are the same in every run
+fn next(seed: &mut u32) -> usize {
+    *seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+    (*seed >> 16) as usize
+}
+
+fn word(seed: &mut u32, len: usize) -> String {
+    (0..len)
+        .map(|_| (b'a' + (next(seed) % 26) as u8) as char)
+        .collect()
+}
+
+/// Builds patterns of random words with a few operators, and lines of random words where some patterns match
+fn patterns_and_lines(count: usize, lines: usize) -> (Vec<String>, Vec<String>) {
+    let mut seed: u32 = 11;
+    let suffixes = ["", "[0-9]+", "s?", "(ing|ed)"];
+    let words: Vec<String> = (0..count)
+        .map(|_| {
+            let len = 5 + next(&mut seed) % 4;
+            word(&mut seed, len)
+        })
+        .collect();
+    let patterns = words
+        .iter()
+        .map(|w| format!("{}{}", w, suffixes[next(&mut seed) % suffixes.len()]))
+        .collect();
+    let lines = (0..lines)
+        .map(|i| {
+            let mut line: Vec<String> = (0..8)
+                .map(|_| {
+                    let len = 3 + next(&mut seed) % 7;
+                    word(&mut seed, len)
+                })
+                .collect();
+            if i % 10 == 0 {
+                let word = &words[next(&mut seed) % words.len()];
+                line.push(format!("{}{}", word, ["", "7", "ing"][next(&mut seed) % 3]));
+            }
+            line.join(" ")
+        })
+        .collect();
+    (patterns, lines)
+}
+
+/// Returns which lines match any of the patterns, with the set and with a loop over the patterns
+fn set_and_loop(options: RegexOptions) -> (Vec<bool>, Vec<bool>) {
+    let (patterns, lines) = patterns_and_lines(60, 200);
+    let programs: Vec<Program> = patterns
+        .iter()
+        .map(|p| Program::with_options(&Regex::new(p).unwrap(), options.clone()))
+        .collect();
+    let mut set = RegexSet::from_programs(programs.clone());
+    let with_set = lines.iter().map(|l| set.is_match(l).unwrap()).collect();
+    let with_loop = lines
+        .iter()
+        .map(|l| programs.iter().any(|p| find_match(p, l).unwrap().is_some()))
+        .collect();
+    (with_set, with_loop)
+}
+
+#[test]
+fn many_patterns_match_like_the_loop() {
+    for ignore_case in [false, true] {
+        let options = RegexOptions {
+            ignore_case,
+            ..Default::default()
+        };
+        let (with_set, with_loop) = set_and_loop(options);
+        assert_eq!(with_set, with_loop);
+        assert!(with_set.iter().filter(|m| **m).count() >= 5);
+    }
+}